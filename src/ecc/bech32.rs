@@ -0,0 +1,293 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+// BIP173 (Bech32) / BIP350 (Bech32m)
+
+const CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+// ASCII文字(小文字) -> 5bitの値。文字セットに無い文字は0xff
+const CHARSET_INDEX: [u8; 128] = build_charset_index();
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+// 全体の長さの上限
+const MAX_LENGTH: usize = 90;
+
+const fn build_charset_index() -> [u8; 128] {
+    let mut table = [0xffu8; 128];
+    let charset = CHARSET.as_bytes();
+    let mut i = 0;
+    while i < charset.len() {
+        table[charset[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bech32Variant {
+    // witness version 0 で使う
+    Bech32,
+    // witness version 1 以降で使う
+    Bech32m,
+}
+
+impl Bech32Variant {
+    fn constant(&self) -> u32 {
+        match self {
+            Bech32Variant::Bech32 => 1,
+            Bech32Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+// Bech32の文字列を読めなかった理由。位置は入力文字列の先頭からの文字数
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bech32Error {
+    // 90文字を超える、またはデータ部がチェックサムの6文字より短い
+    InvalidLength,
+    // 区切り文字'1'が無い
+    MissingSeparator,
+    // HRPが空か83文字を超える、もしくはASCII 33〜126以外の文字を含む
+    InvalidHrp,
+    // 大文字と小文字が混ざっている
+    MixedCase(usize),
+    // データ部に文字セットに無い文字がある
+    InvalidCharacter(usize),
+    // チェックサムが合わない。1文字だけの誤りであればその位置が入る
+    InvalidChecksum(Vec<usize>),
+}
+
+impl Display for Bech32Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bech32Error::InvalidLength => write!(f, "bad bech32: invalid length"),
+            Bech32Error::MissingSeparator => write!(f, "bad bech32: missing separator '1'"),
+            Bech32Error::InvalidHrp => write!(f, "bad bech32: invalid human-readable part"),
+            Bech32Error::MixedCase(pos) => write!(f, "bad bech32: mixed case at position {}", pos),
+            Bech32Error::InvalidCharacter(pos) => {
+                write!(f, "bad bech32: invalid character at position {}", pos)
+            }
+            Bech32Error::InvalidChecksum(positions) if positions.is_empty() => {
+                write!(f, "bad bech32: invalid checksum")
+            }
+            Bech32Error::InvalidChecksum(positions) => {
+                write!(f, "bad bech32: invalid checksum, error at position {:?}", positions)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Bech32Error {}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ (*v as u32);
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+// HRPの各文字の上位3bit、0、下位5bit を並べたもの
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut result: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    result.push(0);
+    result.extend(hrp.bytes().map(|b| b & 0x1f));
+    result
+}
+
+fn checksum_variant(hrp: &str, data: &[u8]) -> Option<Bech32Variant> {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    let residue = polymod(&values);
+    [Bech32Variant::Bech32, Bech32Variant::Bech32m]
+        .iter()
+        .find(|v| v.constant() == residue)
+        .copied()
+}
+
+fn create_checksum(hrp: &str, data: &[u8], variant: Bech32Variant) -> Vec<u8> {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0u8; 6]);
+    let residue = polymod(&values) ^ variant.constant();
+    (0..6).map(|i| ((residue >> (5 * (5 - i))) & 0x1f) as u8).collect()
+}
+
+fn is_valid_hrp(hrp: &str) -> bool {
+    !hrp.is_empty() && hrp.len() <= 83 && hrp.bytes().all(|b| (33..=126).contains(&b))
+}
+
+// data は5bitずつの値。出力は小文字
+pub fn bech32_encode(hrp: &str, data: &[u8], variant: Bech32Variant) -> Result<String, Bech32Error> {
+    if !is_valid_hrp(hrp) {
+        return Err(Bech32Error::InvalidHrp);
+    }
+    if hrp.len() + 1 + data.len() + 6 > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength);
+    }
+    let hrp = hrp.to_lowercase();
+    let mut result = hrp.clone();
+    result.push('1');
+    for v in data.iter().chain(create_checksum(&hrp, data, variant).iter()) {
+        result.push(CHARSET.as_bytes()[(*v & 0x1f) as usize] as char);
+    }
+    Ok(result)
+}
+
+// (HRP(小文字), チェックサムを除いたデータ部の5bitの値, チェックサムの種類) を返す
+#[allow(dead_code)]
+pub fn bech32_decode(s: &str) -> Result<(String, Vec<u8>, Bech32Variant), Bech32Error> {
+    if s.len() > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength);
+    }
+    let has_lower = s.bytes().any(|b| b.is_ascii_lowercase());
+    let has_upper = s.bytes().any(|b| b.is_ascii_uppercase());
+    if has_lower && has_upper {
+        // 最初の文字と大文字小文字が食い違う最初の位置
+        let first_upper = s.bytes().find(|b| b.is_ascii_alphabetic()).unwrap().is_ascii_uppercase();
+        let pos = s
+            .bytes()
+            .position(|b| b.is_ascii_alphabetic() && b.is_ascii_uppercase() != first_upper)
+            .unwrap();
+        return Err(Bech32Error::MixedCase(pos));
+    }
+    let s = s.to_lowercase();
+    let separator = s.rfind('1').ok_or(Bech32Error::MissingSeparator)?;
+    let hrp = &s[..separator];
+    if !is_valid_hrp(hrp) {
+        return Err(Bech32Error::InvalidHrp);
+    }
+    if s.len() - separator - 1 < 6 {
+        return Err(Bech32Error::InvalidLength);
+    }
+    let mut data = vec![];
+    for (i, c) in s.char_indices().skip(separator + 1) {
+        if (c as u32) >= 128 || CHARSET_INDEX[c as usize] == 0xff {
+            return Err(Bech32Error::InvalidCharacter(i));
+        }
+        data.push(CHARSET_INDEX[c as usize]);
+    }
+    match checksum_variant(hrp, &data) {
+        Some(variant) => {
+            data.truncate(data.len() - 6);
+            Ok((hrp.to_string(), data, variant))
+        }
+        None => Err(Bech32Error::InvalidChecksum(locate_error(hrp, &data, separator + 1))),
+    }
+}
+
+// 1文字だけ置き換えればチェックサムが合う位置を探す。
+// Bech32は4文字までの誤りを必ず検出できるので、1文字の誤りであれば位置は一意に決まる
+fn locate_error(hrp: &str, data: &[u8], offset: usize) -> Vec<usize> {
+    let mut candidate = data.to_vec();
+    for i in 0..data.len() {
+        for v in 0..32u8 {
+            if v == data[i] {
+                continue;
+            }
+            candidate[i] = v;
+            if checksum_variant(hrp, &candidate).is_some() {
+                return vec![offset + i];
+            }
+        }
+        candidate[i] = data[i];
+    }
+    vec![]
+}
+
+// from bit ずつの値を to bit ずつの値に詰め直す。
+// pad が false の場合、余ったbitが from bit 以上あるか0以外ならNone
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let max = (1u32 << to) - 1;
+    let mut result = vec![];
+    for v in data {
+        if (*v as u32) >> from != 0 {
+            return None;
+        }
+        acc = (acc << from) | *v as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+
+    #[test]
+    fn test_bech32_vectors() {
+        // BIP173 / BIP350 の valid なチェックサムの例
+        let bech32 = [
+            "A12UEL5L",
+            "a12uel5l",
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            "?1ezyfcl",
+        ];
+        let bech32m = [
+            "A1LQFN3A",
+            "a1lqfn3a",
+            "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            "?1v759aa",
+        ];
+        for (strings, variant) in [(bech32, Bech32Variant::Bech32), (bech32m, Bech32Variant::Bech32m)].iter() {
+            for s in strings.iter() {
+                let (hrp, data, decoded) = bech32_decode(s).unwrap();
+                assert_eq!(decoded, *variant, "{}", s);
+                assert_eq!(bech32_encode(&hrp, &data, *variant).unwrap(), s.to_lowercase());
+            }
+        }
+
+        assert_eq!(bech32_decode("pzry9x0s0muk").unwrap_err(), Bech32Error::MissingSeparator);
+        assert_eq!(bech32_decode("1pzry9x0s0muk").unwrap_err(), Bech32Error::InvalidHrp);
+        assert_eq!(bech32_decode("x1b4n0q5v").unwrap_err(), Bech32Error::InvalidCharacter(2));
+        assert_eq!(bech32_decode("li1dgmt3").unwrap_err(), Bech32Error::InvalidLength);
+        assert_eq!(bech32_decode("A1G7SGD8").unwrap_err(), Bech32Error::InvalidChecksum(vec![]));
+        assert_eq!(bech32_decode("\u{80}1eym55h").unwrap_err(), Bech32Error::InvalidHrp);
+        assert_eq!(bech32_decode("abcdef1Qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw").unwrap_err(), Bech32Error::MixedCase(7));
+        assert_eq!(
+            bech32_decode("an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx")
+                .unwrap_err(),
+            Bech32Error::InvalidLength
+        );
+
+        // 1文字だけ間違えた場合は位置が分かる
+        let mut s = "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw".to_string();
+        s.replace_range(10..11, "q");
+        assert_eq!(bech32_decode(&s).unwrap_err(), Bech32Error::InvalidChecksum(vec![10]));
+    }
+
+    #[test]
+    fn test_convert_bits() {
+        let data = vec![0x00u8, 0x14, 0x75, 0x1e, 0x76, 0xe8];
+        let five = convert_bits(&data, 8, 5, true).unwrap();
+        assert_eq!(convert_bits(&five, 5, 8, false).unwrap(), data);
+        // 余りが0以外
+        assert_eq!(convert_bits(&[0x1f], 5, 8, false), None);
+        // 範囲外の値
+        assert_eq!(convert_bits(&[0x20], 5, 8, true), None);
+    }
+}
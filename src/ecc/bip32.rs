@@ -0,0 +1,677 @@
+use crate::ecc::decode::{decode_base58_checksum, Base58Error};
+use crate::ecc::encode::encode_base58_checksum;
+use crate::ecc::network::Network;
+use crate::ecc::secp256k1_point::{new_secp256k1point_g, PublicKeyError, Secp256k1Point};
+use crate::ecc::secp256k1_privatekey::{new_secp_256k1privatekey, Secp256k1PrivateKey};
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, Secp256k1ScalarElement, SECP256K1_N,
+};
+use crate::ecc::u256::U256;
+use crate::ecc::zeroize::new_zeroizing;
+use crate::helper::helper::{hash160, hmac_sha512};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+// これ以上のインデックスはhardened
+pub const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+// シリアライズ時の先頭4byte。testnet, signet, regtest はtprv/tpubを使う
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const TPRV_VERSION: [u8; 4] = [0x04, 0x35, 0x83, 0x94];
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+
+// 拡張鍵を作れなかった、もしくは読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bip32Error {
+    Base58(Base58Error),
+    // シリアライズされた拡張鍵が78byteでない
+    InvalidLength(usize),
+    // xprv, xpub, tprv, tpub のどれでもない
+    UnknownVersion([u8; 4]),
+    // xprvとして読もうとしたらxpubだった (またはその逆)
+    KeyTypeMismatch,
+    // 秘密鍵の前の1byteが0x00でない
+    InvalidPrivateKeyPrefix(u8),
+    // 秘密鍵が [1, n-1] に無い
+    InvalidPrivateKey,
+    InvalidPublicKey(PublicKeyError),
+    // depthが0なのに親のフィンガープリントかインデックスが0でない
+    InvalidMasterKey,
+    // シードは16〜64byte
+    InvalidSeedLength(usize),
+    // 拡張公開鍵からはhardenedの子を導出できない
+    HardenedFromPublic(u32),
+    // I_L >= n か、子の鍵が0(無限遠点)になった。確率は 2^-127 程度で、次のインデックスを使う
+    InvalidChild(u32),
+    // "m/84'/0'/0'" の形でない
+    InvalidPath(String),
+    // depthは1byteなので255より深くは導出できない
+    MaxDepthExceeded,
+}
+
+impl Display for Bip32Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bip32Error::Base58(e) => write!(f, "bad extended key: {}", e),
+            Bip32Error::InvalidLength(len) => {
+                write!(f, "bad extended key length: {} (expected 78)", len)
+            }
+            Bip32Error::UnknownVersion(v) => write!(
+                f,
+                "unknown extended key version: {:02x}{:02x}{:02x}{:02x}",
+                v[0], v[1], v[2], v[3]
+            ),
+            Bip32Error::KeyTypeMismatch => write!(f, "extended key is not of the expected type"),
+            Bip32Error::InvalidPrivateKeyPrefix(b) => {
+                write!(f, "bad extended private key prefix: {:#04x}", b)
+            }
+            Bip32Error::InvalidPrivateKey => write!(f, "private key is not in [1, n-1]"),
+            Bip32Error::InvalidPublicKey(e) => write!(f, "bad extended public key: {}", e),
+            Bip32Error::InvalidMasterKey => {
+                write!(f, "zero depth with non-zero parent fingerprint or index")
+            }
+            Bip32Error::InvalidSeedLength(len) => {
+                write!(f, "seed must be 16 to 64 bytes, got {}", len)
+            }
+            Bip32Error::HardenedFromPublic(i) => write!(
+                f,
+                "cannot derive hardened child {}' from a public key",
+                i - HARDENED_OFFSET
+            ),
+            Bip32Error::InvalidChild(i) => write!(f, "child {} is invalid, use the next index", i),
+            Bip32Error::InvalidPath(s) => write!(f, "bad derivation path: {:?}", s),
+            Bip32Error::MaxDepthExceeded => write!(f, "cannot derive beyond depth 255"),
+        }
+    }
+}
+
+impl std::error::Error for Bip32Error {}
+
+// 派生パス。インデックスはhardenedならHARDENED_OFFSETを足した値で持つ
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivationPath {
+    pub(crate) indexes: Vec<u32>,
+}
+
+#[allow(dead_code)]
+pub fn new_derivation_path(indexes: Vec<u32>) -> DerivationPath {
+    DerivationPath { indexes }
+}
+
+// "0", "5", "84'", "84h" のような1段分のインデックス
+#[allow(dead_code)]
+pub fn parse_child_number(s: &str) -> Result<u32, Bip32Error> {
+    let invalid = || Bip32Error::InvalidPath(s.to_string());
+    let (digits, hardened) = match s.strip_suffix('\'').or_else(|| s.strip_suffix('h')) {
+        Some(digits) => (digits, true),
+        None => (s, false),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let index: u32 = digits.parse().map_err(|_| invalid())?;
+    if index >= HARDENED_OFFSET {
+        return Err(invalid());
+    }
+    Ok(if hardened { index + HARDENED_OFFSET } else { index })
+}
+
+impl DerivationPath {
+    // "m" もしくは "m/84'/0'/0'/0/5"。hardenedは ' と h のどちらでも書ける
+    #[allow(dead_code)]
+    pub fn parse(s: &str) -> Result<DerivationPath, Bip32Error> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(Bip32Error::InvalidPath(s.to_string()));
+        }
+        let indexes = parts
+            .map(|part| parse_child_number(part).map_err(|_| Bip32Error::InvalidPath(s.to_string())))
+            .collect::<Result<Vec<u32>, Bip32Error>>()?;
+        Ok(new_derivation_path(indexes))
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in self.indexes.iter() {
+            if *index >= HARDENED_OFFSET {
+                write!(f, "/{}'", index - HARDENED_OFFSET)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtendedPrivateKey {
+    pub(crate) network: Network,
+    pub(crate) depth: u8,
+    pub(crate) parent_fingerprint: [u8; 4],
+    pub(crate) child_number: u32,
+    pub(crate) chain_code: [u8; 32],
+    pub(crate) private_key: Secp256k1PrivateKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedPublicKey {
+    pub(crate) network: Network,
+    pub(crate) depth: u8,
+    pub(crate) parent_fingerprint: [u8; 4],
+    pub(crate) child_number: u32,
+    pub(crate) chain_code: [u8; 32],
+    pub(crate) public_key: Secp256k1Point,
+}
+
+// 32byteを [1, n-1] の秘密鍵として読む。範囲外ならNone
+fn parse_secret(bytes: &[u8]) -> Option<Secp256k1ScalarElement> {
+    let value = new_zeroizing(U256::from_be_slice(bytes)?);
+    if value.is_zero() || *value >= SECP256K1_N {
+        return None;
+    }
+    Some(new_secp256k1scalarelement_from_u256(*value))
+}
+
+// 公開鍵の圧縮形式のhash160。先頭4byteがフィンガープリント
+fn identifier(point: &Secp256k1Point) -> Vec<u8> {
+    hash160(point.clone().compressed_sec())
+}
+
+fn fingerprint_of(point: &Secp256k1Point) -> [u8; 4] {
+    let mut fingerprint = [0u8; 4];
+    fingerprint.copy_from_slice(&identifier(point)[..4]);
+    fingerprint
+}
+
+// version(4) || depth(1) || fingerprint(4) || child number(4) || chain code(32) || key(33)
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: &[u8; 4],
+    child_number: u32,
+    chain_code: &[u8; 32],
+    key: &[u8],
+) -> Vec<u8> {
+    let mut v = version.to_vec();
+    v.push(depth);
+    v.extend_from_slice(parent_fingerprint);
+    v.extend_from_slice(&child_number.to_be_bytes());
+    v.extend_from_slice(chain_code);
+    v.extend_from_slice(key);
+    v
+}
+
+// 78byteのペイロードを (network, 秘密鍵かどうか, depth, fingerprint, child number, chain code, key) に分ける
+#[allow(clippy::type_complexity)]
+fn deserialize(s: &str) -> Result<(Network, bool, u8, [u8; 4], u32, [u8; 32], Vec<u8>), Bip32Error> {
+    let data = decode_base58_checksum(s).map_err(Bip32Error::Base58)?;
+    if data.len() != 78 {
+        return Err(Bip32Error::InvalidLength(data.len()));
+    }
+    let mut version = [0u8; 4];
+    version.copy_from_slice(&data[..4]);
+    let (network, is_private) = match version {
+        XPRV_VERSION => (Network::Mainnet, true),
+        XPUB_VERSION => (Network::Mainnet, false),
+        TPRV_VERSION => (Network::Testnet, true),
+        TPUB_VERSION => (Network::Testnet, false),
+        _ => return Err(Bip32Error::UnknownVersion(version)),
+    };
+    let depth = data[4];
+    let mut parent_fingerprint = [0u8; 4];
+    parent_fingerprint.copy_from_slice(&data[5..9]);
+    let mut child_number = [0u8; 4];
+    child_number.copy_from_slice(&data[9..13]);
+    let child_number = u32::from_be_bytes(child_number);
+    if depth == 0 && (parent_fingerprint != [0u8; 4] || child_number != 0) {
+        return Err(Bip32Error::InvalidMasterKey);
+    }
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&data[13..45]);
+    Ok((
+        network,
+        is_private,
+        depth,
+        parent_fingerprint,
+        child_number,
+        chain_code,
+        data[45..].to_vec(),
+    ))
+}
+
+impl ExtendedPrivateKey {
+    // マスター鍵。I = HMAC-SHA512("Bitcoin seed", seed) の左半分が秘密鍵、右半分がチェーンコード
+    #[allow(dead_code)]
+    pub fn from_seed(seed: &[u8], network: Network) -> Result<ExtendedPrivateKey, Bip32Error> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err(Bip32Error::InvalidSeedLength(seed.len()));
+        }
+        let i = new_zeroizing(hmac_sha512(b"Bitcoin seed", seed));
+        let secret = parse_secret(&i[..32]);
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&i[32..]);
+        let secret = secret.ok_or(Bip32Error::InvalidPrivateKey)?;
+        Ok(ExtendedPrivateKey {
+            network,
+            depth: 0,
+            parent_fingerprint: [0u8; 4],
+            child_number: 0,
+            chain_code,
+            private_key: new_secp_256k1privatekey(secret),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn parse(s: &str) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let (network, is_private, depth, parent_fingerprint, child_number, chain_code, key) =
+            deserialize(s)?;
+        let key = new_zeroizing(key);
+        if !is_private {
+            return Err(Bip32Error::KeyTypeMismatch);
+        }
+        if key[0] != 0 {
+            return Err(Bip32Error::InvalidPrivateKeyPrefix(key[0]));
+        }
+        let secret = parse_secret(&key[1..]);
+        let secret = secret.ok_or(Bip32Error::InvalidPrivateKey)?;
+        Ok(ExtendedPrivateKey {
+            network,
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            private_key: new_secp_256k1privatekey(secret),
+        })
+    }
+
+    // CKDpriv。hardenedなら 0x00 || k、そうでなければ圧縮公開鍵をHMACに入れる
+    #[allow(dead_code)]
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::MaxDepthExceeded)?;
+        let mut data = new_zeroizing(Vec::with_capacity(37));
+        if index >= HARDENED_OFFSET {
+            data.push(0u8);
+            data.extend_from_slice(&new_zeroizing(self.private_key.expose_secret_bytes())[..]);
+        } else {
+            data.extend_from_slice(&self.private_key.point.clone().compressed_sec());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+        let i = new_zeroizing(hmac_sha512(&self.chain_code, &data));
+        let tweak = parse_secret(&i[..32]);
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&i[32..]);
+
+        let tweak = tweak.ok_or(Bip32Error::InvalidChild(index))?;
+        let secret = tweak + self.private_key.expose_secret().clone();
+        if secret.is_zero() {
+            return Err(Bip32Error::InvalidChild(index));
+        }
+        Ok(ExtendedPrivateKey {
+            network: self.network,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            private_key: new_secp_256k1privatekey(secret),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let mut key = self.clone();
+        for index in path.indexes.iter() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    #[allow(dead_code)]
+    pub fn extended_public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            network: self.network,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            public_key: self.private_key.point.clone(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn identifier(&self) -> Vec<u8> {
+        identifier(&self.private_key.point)
+    }
+
+    #[allow(dead_code)]
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint_of(&self.private_key.point)
+    }
+}
+
+// xprv/tprv
+impl Display for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let version = if self.network.is_testnet() { TPRV_VERSION } else { XPRV_VERSION };
+        let mut key = new_zeroizing(Vec::with_capacity(33));
+        key.push(0u8);
+        key.extend_from_slice(&new_zeroizing(self.private_key.expose_secret_bytes())[..]);
+        let data = new_zeroizing(serialize(
+            version,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        ));
+        let s = encode_base58_checksum(data.to_vec());
+        write!(f, "{}", s)
+    }
+}
+
+impl ExtendedPublicKey {
+    #[allow(dead_code)]
+    pub fn parse(s: &str) -> Result<ExtendedPublicKey, Bip32Error> {
+        let (network, is_private, depth, parent_fingerprint, child_number, chain_code, key) =
+            deserialize(s)?;
+        if is_private {
+            return Err(Bip32Error::KeyTypeMismatch);
+        }
+        let public_key = Secp256k1Point::parse_compressed_sec(key).map_err(Bip32Error::InvalidPublicKey)?;
+        Ok(ExtendedPublicKey {
+            network,
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            public_key,
+        })
+    }
+
+    // CKDpub。K_i = I_L * G + K
+    #[allow(dead_code)]
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedPublicKey, Bip32Error> {
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::MaxDepthExceeded)?;
+        if index >= HARDENED_OFFSET {
+            return Err(Bip32Error::HardenedFromPublic(index));
+        }
+        let mut data = self.public_key.clone().compressed_sec();
+        data.extend_from_slice(&index.to_be_bytes());
+        let i = hmac_sha512(&self.chain_code, &data);
+        let tweak = parse_secret(&i[..32]).ok_or(Bip32Error::InvalidChild(index))?;
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&i[32..]);
+
+        let public_key = new_secp256k1point_g().mul_from_sec256k1scalar_element(tweak) + self.public_key.clone();
+        if public_key.is_infinity() {
+            return Err(Bip32Error::InvalidChild(index));
+        }
+        Ok(ExtendedPublicKey {
+            network: self.network,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            public_key,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedPublicKey, Bip32Error> {
+        let mut key = self.clone();
+        for index in path.indexes.iter() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    #[allow(dead_code)]
+    pub fn identifier(&self) -> Vec<u8> {
+        identifier(&self.public_key)
+    }
+
+    #[allow(dead_code)]
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint_of(&self.public_key)
+    }
+}
+
+// xpub/tpub
+impl Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let version = if self.network.is_testnet() { TPUB_VERSION } else { XPUB_VERSION };
+        let data = serialize(
+            version,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.public_key.clone().compressed_sec(),
+        );
+        write!(f, "{}", encode_base58_checksum(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::helper::helper::decode_hex;
+
+    // (パス, xprv, xpub)。BIP32のテストベクタ
+    fn check_vectors(seed: &str, vectors: &[(&str, &str, &str)]) {
+        let master = ExtendedPrivateKey::from_seed(&decode_hex(seed).unwrap(), Network::Mainnet).unwrap();
+        let mut parent: Option<ExtendedPrivateKey> = None;
+        for (path, xprv, xpub) in vectors.iter() {
+            let path = DerivationPath::parse(path).unwrap();
+            let key = master.derive_path(&path).unwrap();
+            assert_eq!(key.to_string(), *xprv, "{}", path);
+            assert_eq!(key.extended_public_key().to_string(), *xpub, "{}", path);
+
+            // 文字列から読み直しても同じ
+            assert_eq!(ExtendedPrivateKey::parse(xprv).unwrap().to_string(), *xprv);
+            assert_eq!(ExtendedPublicKey::parse(xpub).unwrap(), key.extended_public_key());
+
+            // 非hardenedなら親のxpubからも同じ子が導出できる
+            if let Some(parent) = parent {
+                assert_eq!(key.parent_fingerprint, parent.fingerprint());
+                let index = *path.indexes.last().unwrap();
+                let from_public = parent.extended_public_key().derive_child(index);
+                if index < HARDENED_OFFSET {
+                    assert_eq!(from_public.unwrap(), key.extended_public_key());
+                } else {
+                    assert_eq!(from_public.unwrap_err(), Bip32Error::HardenedFromPublic(index));
+                }
+            }
+            parent = Some(key);
+        }
+    }
+
+    #[test]
+    fn test_vector_1() {
+        check_vectors(
+            "000102030405060708090a0b0c0d0e0f",
+            &[
+                ("m", "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi", "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"),
+                ("m/0h", "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7", "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"),
+                ("m/0h/1", "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs", "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"),
+                ("m/0h/1/2h", "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM", "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"),
+                ("m/0h/1/2h/2", "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334", "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"),
+                ("m/0h/1/2h/2/1000000000", "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76", "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vector_2() {
+        check_vectors(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+            &[
+                ("m", "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U", "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB"),
+                ("m/0", "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt", "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH"),
+                ("m/0/2147483647h", "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9", "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a"),
+                ("m/0/2147483647h/1", "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef", "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon"),
+                ("m/0/2147483647h/1/2147483646h", "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc", "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL"),
+                ("m/0/2147483647h/1/2147483646h/2", "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j", "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vector_3() {
+        // 先頭が0x00の秘密鍵も32byteのまま扱う
+        check_vectors(
+            "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+            &[
+                ("m", "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6", "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13"),
+                ("m/0h", "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L", "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let path = DerivationPath::parse("m/84'/0'/0'/0/5").unwrap();
+        assert_eq!(
+            path.indexes,
+            vec![84 + HARDENED_OFFSET, HARDENED_OFFSET, HARDENED_OFFSET, 0, 5]
+        );
+        assert_eq!(path.to_string(), "m/84'/0'/0'/0/5");
+        assert_eq!(DerivationPath::parse("m/84h/0h/0h/0/5").unwrap(), path);
+        assert!(DerivationPath::parse("m").unwrap().indexes.is_empty());
+        assert_eq!(
+            DerivationPath::parse("m/2147483647'").unwrap().indexes,
+            vec![u32::MAX]
+        );
+        for bad in ["", "n/0", "m/", "m//0", "0/1", "m/0x", "m/-1", "m/+1", "m/2147483648", "m/0''"].iter() {
+            assert_eq!(
+                DerivationPath::parse(bad).unwrap_err(),
+                Bip32Error::InvalidPath(bad.to_string()),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_testnet_and_fingerprint() {
+        let seed = decode_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPrivateKey::from_seed(&seed, Network::Testnet).unwrap();
+        assert!(master.to_string().starts_with("tprv"));
+        assert!(master.extended_public_key().to_string().starts_with("tpub"));
+        assert_eq!(
+            ExtendedPrivateKey::parse(&master.to_string()).unwrap().network,
+            Network::Testnet
+        );
+        // テストベクタ1のマスター鍵の識別子
+        assert_eq!(
+            master.identifier(),
+            decode_hex("3442193e1bb70916e914552172cd4e2dbc9df811").unwrap()
+        );
+        assert_eq!(master.fingerprint(), [0x34, 0x42, 0x19, 0x3e]);
+        assert_eq!(
+            ExtendedPrivateKey::from_seed(&seed[..15], Network::Mainnet).unwrap_err(),
+            Bip32Error::InvalidSeedLength(15)
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let seed = decode_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let mut key = ExtendedPrivateKey::from_seed(&seed, Network::Mainnet).unwrap();
+        for _ in 0..255 {
+            key = key.derive_child(0).unwrap();
+        }
+        assert_eq!(key.depth, 255);
+        // 256段目はdepthが0に戻ってしまうので導出できない
+        assert_eq!(key.derive_child(0).unwrap_err(), Bip32Error::MaxDepthExceeded);
+        assert_eq!(
+            key.derive_child(HARDENED_OFFSET).unwrap_err(),
+            Bip32Error::MaxDepthExceeded
+        );
+        assert_eq!(
+            key.extended_public_key().derive_child(0).unwrap_err(),
+            Bip32Error::MaxDepthExceeded
+        );
+        assert_eq!(
+            ExtendedPrivateKey::parse(&key.to_string()).unwrap().depth,
+            255
+        );
+    }
+
+    #[test]
+    fn test_invalid_extended_keys() {
+        let chain_code = [1u8; 32];
+        let pubkey = new_secp256k1point_g().compressed_sec();
+        let mut secret = vec![0u8; 32];
+        secret[31] = 1;
+        let encode = |version: [u8; 4], depth: u8, fingerprint: [u8; 4], index: u32, key: &[u8]| {
+            encode_base58_checksum(serialize(version, depth, &fingerprint, index, &chain_code, key))
+        };
+        let private = |key: &[u8]| {
+            let mut v = vec![0u8];
+            v.extend_from_slice(key);
+            v
+        };
+
+        // 正しい鍵は読める
+        assert!(ExtendedPublicKey::parse(&encode(XPUB_VERSION, 1, [1; 4], 0, &pubkey)).is_ok());
+        assert!(ExtendedPrivateKey::parse(&encode(XPRV_VERSION, 1, [1; 4], 0, &private(&secret))).is_ok());
+
+        assert_eq!(
+            ExtendedPrivateKey::parse(&encode(XPUB_VERSION, 1, [1; 4], 0, &pubkey)).unwrap_err(),
+            Bip32Error::KeyTypeMismatch
+        );
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode(XPRV_VERSION, 1, [1; 4], 0, &private(&secret))).unwrap_err(),
+            Bip32Error::KeyTypeMismatch
+        );
+        let mut bad_prefix = pubkey.clone();
+        bad_prefix[0] = 0x04;
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode(XPUB_VERSION, 1, [1; 4], 0, &bad_prefix)).unwrap_err(),
+            Bip32Error::InvalidPublicKey(PublicKeyError::InvalidPrefix(0x04))
+        );
+        let mut bad_prefix = private(&secret);
+        bad_prefix[0] = 0x04;
+        assert_eq!(
+            ExtendedPrivateKey::parse(&encode(XPRV_VERSION, 1, [1; 4], 0, &bad_prefix)).unwrap_err(),
+            Bip32Error::InvalidPrivateKeyPrefix(0x04)
+        );
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode(XPUB_VERSION, 0, [1; 4], 0, &pubkey)).unwrap_err(),
+            Bip32Error::InvalidMasterKey
+        );
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode(XPUB_VERSION, 0, [0; 4], 1, &pubkey)).unwrap_err(),
+            Bip32Error::InvalidMasterKey
+        );
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode([0xde, 0xad, 0xbe, 0xef], 1, [1; 4], 0, &pubkey)).unwrap_err(),
+            Bip32Error::UnknownVersion([0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(
+            ExtendedPrivateKey::parse(&encode(XPRV_VERSION, 1, [1; 4], 0, &private(&[0u8; 32]))).unwrap_err(),
+            Bip32Error::InvalidPrivateKey
+        );
+        let n = SECP256K1_N.to_be_bytes();
+        assert_eq!(
+            ExtendedPrivateKey::parse(&encode(XPRV_VERSION, 1, [1; 4], 0, &private(&n))).unwrap_err(),
+            Bip32Error::InvalidPrivateKey
+        );
+        // x = 7 に対応する点は曲線上に無い
+        let mut not_on_curve = vec![0x02u8];
+        not_on_curve.extend_from_slice(&[0u8; 31]);
+        not_on_curve.push(7);
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode(XPUB_VERSION, 1, [1; 4], 0, &not_on_curve)).unwrap_err(),
+            Bip32Error::InvalidPublicKey(PublicKeyError::NotOnCurve)
+        );
+        assert_eq!(
+            ExtendedPublicKey::parse(&encode_base58_checksum(vec![0u8; 77])).unwrap_err(),
+            Bip32Error::InvalidLength(77)
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::ecc::bip32::{Bip32Error, ExtendedPrivateKey};
+use crate::ecc::network::Network;
+use crate::ecc::zeroize::{new_zeroizing, Zeroize, Zeroizing};
+use crate::helper::helper::sha256;
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha512;
+use once_cell::sync::Lazy;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use unicode_normalization::UnicodeNormalization;
+
+// BIP39の英語の単語リスト (bitcoin/bips の english.txt と同じ内容)。アルファベット順に並んでいる
+static ENGLISH_WORDS: Lazy<Vec<&'static str>> =
+    Lazy::new(|| include_str!("bip39_english.txt").lines().collect());
+
+const PBKDF2_ROUNDS: u32 = 2048;
+
+// ニーモニックを作れなかった、もしくは読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bip39Error {
+    // 単語数が 12, 15, 18, 21, 24 のどれでもない
+    InvalidWordCount(usize),
+    // n番目(0始まり)の単語が単語リストに無い。単語自体は秘密なのでエラーに含めない
+    UnknownWord(usize),
+    // エントロピーが 16〜32byte の4の倍数でない
+    InvalidEntropyLength(usize),
+    // 最後の単語に含まれるチェックサムが合わない
+    InvalidChecksum,
+}
+
+impl Display for Bip39Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bip39Error::InvalidWordCount(n) => write!(f, "mnemonic has {} words", n),
+            Bip39Error::UnknownWord(i) => write!(f, "word {} is not in the wordlist", i + 1),
+            Bip39Error::InvalidEntropyLength(len) => {
+                write!(f, "entropy must be 16 to 32 bytes in multiples of 4, got {}", len)
+            }
+            Bip39Error::InvalidChecksum => write!(f, "bad mnemonic checksum"),
+        }
+    }
+}
+
+impl std::error::Error for Bip39Error {}
+
+// ニーモニックはエントロピーで持つ。秘密鍵と同じくDebugで表示されず、ドロップ時に0で上書きされる
+#[derive(Clone, PartialEq)]
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.entropy.zeroize();
+    }
+}
+
+impl Debug for Mnemonic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mnemonic")
+            .field("words", &self.word_count())
+            .field("entropy", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl Mnemonic {
+    #[allow(dead_code)]
+    pub fn from_entropy(entropy: &[u8]) -> Result<Mnemonic, Bip39Error> {
+        if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+            return Err(Bip39Error::InvalidEntropyLength(entropy.len()));
+        }
+        Ok(Mnemonic {
+            entropy: entropy.to_vec(),
+        })
+    }
+
+    // OSの乱数生成器から word_count 語のニーモニックを作る
+    #[allow(dead_code)]
+    pub fn generate(word_count: usize) -> Result<Mnemonic, Bip39Error> {
+        if !(12..=24).contains(&word_count) || !word_count.is_multiple_of(3) {
+            return Err(Bip39Error::InvalidWordCount(word_count));
+        }
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        OsRng.fill_bytes(&mut entropy);
+        Ok(Mnemonic { entropy })
+    }
+
+    // 空白区切りの単語列を読む。大文字や全角スペースはNFKD正規化と小文字化で吸収する
+    #[allow(dead_code)]
+    pub fn parse(phrase: &str) -> Result<Mnemonic, Bip39Error> {
+        let normalized: String = phrase.nfkd().collect::<String>().to_lowercase();
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+        if !(12..=24).contains(&words.len()) || !words.len().is_multiple_of(3) {
+            return Err(Bip39Error::InvalidWordCount(words.len()));
+        }
+
+        // 11bitずつ並べると エントロピー(ENT bit) || チェックサム(ENT/32 bit) になる
+        let mut bits: Vec<bool> = Vec::with_capacity(words.len() * 11);
+        for (i, word) in words.iter().enumerate() {
+            let index = ENGLISH_WORDS
+                .binary_search(word)
+                .map_err(|_| Bip39Error::UnknownWord(i))?;
+            for j in (0..11).rev() {
+                bits.push((index >> j) & 1 == 1);
+            }
+        }
+        let entropy_bits = bits.len() / 33 * 32;
+        let mut entropy = vec![0u8; entropy_bits / 8];
+        for (i, bit) in bits[..entropy_bits].iter().enumerate() {
+            if *bit {
+                entropy[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        let mnemonic = Mnemonic { entropy };
+        let checksum = mnemonic.checksum_bits();
+        if bits[entropy_bits..] != checksum[..] {
+            return Err(Bip39Error::InvalidChecksum);
+        }
+        Ok(mnemonic)
+    }
+
+    // sha256(entropy) の先頭 ENT/32 bit
+    fn checksum_bits(&self) -> Vec<bool> {
+        let hash = sha256(self.entropy.clone());
+        (0..self.entropy.len() / 4)
+            .map(|i| hash[i / 8] & (0x80 >> (i % 8)) != 0)
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn word_count(&self) -> usize {
+        self.entropy.len() * 3 / 4
+    }
+
+    // エントロピーをそのまま返す。呼び出し側で外に漏らさないこと
+    #[allow(dead_code)]
+    pub fn expose_entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    // 単語をスペース1つで区切った文字列
+    #[allow(dead_code)]
+    pub fn expose_phrase(&self) -> String {
+        let mut bits: Vec<bool> = vec![];
+        for b in self.entropy.iter() {
+            for j in (0..8).rev() {
+                bits.push((b >> j) & 1 == 1);
+            }
+        }
+        bits.extend(self.checksum_bits());
+        bits.chunks(11)
+            .map(|chunk| {
+                let index = chunk.iter().fold(0usize, |acc, bit| (acc << 1) | *bit as usize);
+                ENGLISH_WORDS[index]
+            })
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    // PBKDF2-HMAC-SHA512(NFKD(phrase), "mnemonic" || NFKD(passphrase), 2048回) で64byteのシードを作る
+    // フレーズ・パスフレーズ・シードはどれも秘密なので、途中の文字列も含めてドロップ時に消す
+    #[allow(dead_code)]
+    pub fn to_seed(&self, passphrase: &str) -> Zeroizing<[u8; 64]> {
+        let phrase = new_zeroizing(self.expose_phrase());
+        let salt = new_zeroizing(format!("mnemonic{}", passphrase));
+        let salt = new_zeroizing(salt.nfkd().collect::<String>());
+        let mut mac = Hmac::new(Sha512::new(), phrase.as_bytes());
+        let mut seed = new_zeroizing([0u8; 64]);
+        pbkdf2(&mut mac, salt.as_bytes(), PBKDF2_ROUNDS, &mut seed[..]);
+        seed
+    }
+
+    // シードから作ったBIP32のマスター鍵
+    #[allow(dead_code)]
+    pub fn to_master_key(&self, passphrase: &str, network: Network) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let seed = self.to_seed(passphrase);
+        ExtendedPrivateKey::from_seed(&seed[..], network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::helper::helper::decode_hex;
+
+    #[test]
+    fn test_wordlist() {
+        assert_eq!(ENGLISH_WORDS.len(), 2048);
+        assert_eq!(ENGLISH_WORDS[0], "abandon");
+        assert_eq!(ENGLISH_WORDS[2047], "zoo");
+        assert!(ENGLISH_WORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_vectors_english() {
+        // (entropy, mnemonic, seed)。パスフレーズは "TREZOR"
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                "ffffffffffffffffffffffffffffffff",
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+                "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+            ),
+            (
+                "808080808080808080808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter always",
+                "107d7c02a5aa6f38c58083ff74f04c607c2d2c0ecc55501dadd72d025b751bc27fe913ffb796f841c49b1d33b610cf0e91d3aa239027f5e99fe4ce9e5088cd65",
+            ),
+            (
+                "8080808080808080808080808080808080808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+                "c0c519bd0e91a2ed54357d9d1ebef6f5af218a153624cf4f2da911a0ed8f7a09e2ef61af0aca007096df430022f7a2b6fb91661a9589097069720d015e4e982f",
+            ),
+        ];
+        for (entropy, phrase, seed) in vectors.iter() {
+            let entropy = decode_hex(entropy).unwrap();
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+            assert_eq!(mnemonic.expose_phrase(), *phrase);
+            let parsed = Mnemonic::parse(phrase).unwrap();
+            assert_eq!(parsed.expose_entropy(), &entropy[..]);
+            assert_eq!(parsed.to_seed("TREZOR").to_vec(), decode_hex(seed).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_master_key_and_passphrase() {
+        let mnemonic = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        assert_eq!(
+            mnemonic.to_master_key("TREZOR", Network::Mainnet).unwrap().to_string(),
+            "xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF"
+        );
+        // パスフレーズもNFKD正規化してから使う (全角英字と全角スペースは半角になる)
+        let expected = decode_hex("26c162169a3a434ad5634c1d791dd77a693c36dc45501e9f4b063489e277eebdfaa8f7fb9694b78ddefb144e7e501c9394e37ef33d22ce74d51e0830b091ead1").unwrap();
+        assert_eq!(mnemonic.to_seed("ｐａｓｓ　ｗｏｒｄ").to_vec(), expected);
+        assert_eq!(mnemonic.to_seed("pass word").to_vec(), expected);
+
+        // 大文字や余分な空白があっても同じニーモニック
+        let messy = Mnemonic::parse("  ABANDON abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon\tabout\n").unwrap();
+        assert_eq!(messy, mnemonic);
+        assert_eq!(format!("{:?}", mnemonic), "Mnemonic { words: 12, entropy: \"[REDACTED]\" }");
+    }
+
+    #[test]
+    fn test_generate_and_errors() {
+        for n in [12usize, 15, 18, 21, 24].iter() {
+            let mnemonic = Mnemonic::generate(*n).unwrap();
+            assert_eq!(mnemonic.word_count(), *n);
+            assert_eq!(Mnemonic::parse(&mnemonic.expose_phrase()).unwrap(), mnemonic);
+        }
+        assert_eq!(Mnemonic::generate(13).unwrap_err(), Bip39Error::InvalidWordCount(13));
+        assert_eq!(Mnemonic::from_entropy(&[0u8; 15]).unwrap_err(), Bip39Error::InvalidEntropyLength(15));
+
+        assert_eq!(
+            Mnemonic::parse("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").unwrap_err(),
+            Bip39Error::InvalidWordCount(11)
+        );
+        assert_eq!(
+            Mnemonic::parse("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").unwrap_err(),
+            Bip39Error::InvalidChecksum
+        );
+        assert_eq!(
+            Mnemonic::parse("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandonn about").unwrap_err(),
+            Bip39Error::UnknownWord(10)
+        );
+    }
+}
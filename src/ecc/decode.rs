@@ -1,51 +1,160 @@
 use crate::ecc::encode;
-use crate::ecc::secp256k1_scalar_element::{
-    new_secp256k1scalarelement, new_secp256k1scalarelement_from_u64,
-};
 use crate::helper::helper::hash256;
-use num_bigint::BigUint;
-use num_traits::Zero;
-use std::ops::{Add, Mul};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+// ASCII文字 -> Base58の値。アルファベットに無い文字は0xff
+const BASE58_INDEX: [u8; 128] = build_base58_index();
+
+const fn build_base58_index() -> [u8; 128] {
+    let mut table = [0xffu8; 128];
+    let alphabet = encode::BASE58_ALPHABET.as_bytes();
+    let mut i = 0;
+    while i < alphabet.len() {
+        table[alphabet[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
 
-// FIXME: 遅い！
 pub fn char_to_base58_index(c: char) -> Result<u8, &'static str> {
-    for (i, val) in encode::BASE58_ALPHABET.chars().enumerate() {
-        if c == val {
-            return Ok(i as u8);
+    if (c as u32) < 128 && BASE58_INDEX[c as usize] != 0xff {
+        return Ok(BASE58_INDEX[c as usize]);
+    }
+    Err("failed to find a character in base58")
+}
+
+// Base58Checkを読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum Base58Error {
+    // アルファベットに無い文字
+    InvalidCharacter(char),
+    // チェックサムの4byte(バージョン付きの場合は5byte)に満たない
+    TooShort,
+    // hash256の先頭4byteと一致しない
+    InvalidChecksum,
+}
+
+impl Display for Base58Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter(c) => write!(f, "invalid base58 character: {:?}", c),
+            Base58Error::TooShort => write!(f, "base58check data is too short"),
+            Base58Error::InvalidChecksum => write!(f, "bad base58check checksum"),
         }
     }
-    return Err("failed to find a character in base58");
 }
 
-pub fn address_decode_base58(addr: String) -> Result<Vec<u8>, String> {
-    let mut v = new_secp256k1scalarelement(BigUint::zero());
-    let _58 = new_secp256k1scalarelement(BigUint::from(58u32));
-    for c in addr.chars() {
-        v = v.mul(_58.clone());
-        let idx = char_to_base58_index(c);
-        match idx {
-            Ok(idx) => {
-                v = v.add(new_secp256k1scalarelement_from_u64(idx as u64));
-            }
-            Err(e) => {
-                return Err(e.to_string());
-            }
+impl std::error::Error for Base58Error {}
+
+// 任意の長さのBase58をデコードする。先頭の'1'はそれぞれ0x00になる
+pub fn decode_base58(s: &str) -> Result<Vec<u8>, Base58Error> {
+    // 58進数をリトルエンディアンのバイト列に1桁ずつ足し込む
+    let mut bytes: Vec<u8> = vec![];
+    for c in s.chars() {
+        let mut carry = char_to_base58_index(c).map_err(|_| Base58Error::InvalidCharacter(c))? as u32;
+        for b in bytes.iter_mut() {
+            carry += (*b as u32) * 58;
+            *b = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
         }
     }
-    let combined = v.to_n_bytes_be(25);
-    return match combined {
-        None => Err("failed to cast bytes (at Secp256k1ScalarElement::to_n_bytes_be)".to_string()),
-        Some(combined) => {
-            let checksum = combined[combined.len() - 4..].to_vec();
-            let striped = combined[..combined.len() - 4].to_vec();
-            if hash256(striped.clone())[..4] != checksum {
-                return Err(format!(
-                    "bad address: {:?} \nvs \n{:?}",
-                    checksum.to_vec(),
-                    hash256(striped.clone())[..4].to_vec(),
-                ));
-            }
-            Ok(combined[1..combined.len() - 4].to_owned()) // hash160
+    let zeros = s.chars().take_while(|c| *c == '1').count();
+    bytes.extend(std::iter::repeat_n(0u8, zeros));
+    bytes.reverse();
+    Ok(bytes)
+}
+
+// Base58Checkをデコードし、チェックサムを除いたバイト列を返す
+pub fn decode_base58_checksum(s: &str) -> Result<Vec<u8>, Base58Error> {
+    let combined = decode_base58(s)?;
+    if combined.len() < 4 {
+        return Err(Base58Error::TooShort);
+    }
+    let (payload, checksum) = combined.split_at(combined.len() - 4);
+    if hash256(payload.to_vec())[..4] != *checksum {
+        return Err(Base58Error::InvalidChecksum);
+    }
+    Ok(payload.to_vec())
+}
+
+// アドレスやWIFのように先頭1byteがバージョンのBase58Checkを (バージョン, ペイロード) に分ける。
+// xpubのようにバージョンが4byteのものは decode_base58_checksum の結果を分ければ良い
+pub fn decode_base58_checksum_with_version(s: &str) -> Result<(u8, Vec<u8>), Base58Error> {
+    let payload = decode_base58_checksum(s)?;
+    if payload.is_empty() {
+        return Err(Base58Error::TooShort);
+    }
+    Ok((payload[0], payload[1..].to_vec()))
+}
+
+// P2PKH/P2SHのアドレスからhash160を取り出す
+#[allow(dead_code)]
+pub fn address_decode_base58(addr: String) -> Result<Vec<u8>, String> {
+    let (_, hash160) = decode_base58_checksum_with_version(&addr).map_err(|e| format!("bad address: {}", e))?;
+    if hash160.len() != 20 {
+        return Err(format!("bad address: invalid payload length {}", hash160.len()));
+    }
+    Ok(hash160)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::encode::{encode_base58, encode_base58_checksum};
+    use crate::helper::helper::decode_hex;
+
+    #[test]
+    fn test_decode_base58() {
+        // bitcoin/src/test/data/base58_encode_decode.json
+        let vectors = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            ("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+            ("00eb15231dfceb60925886b67d065299925915aeb172c06647", "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+        for (hex, b58) in vectors.iter() {
+            let bytes = decode_hex(hex).unwrap();
+            assert_eq!(decode_base58(b58).unwrap(), bytes, "{}", b58);
+            assert_eq!(encode_base58(bytes), *b58);
         }
-    };
+        assert_eq!(decode_base58("0OIl").unwrap_err(), Base58Error::InvalidCharacter('0'));
+        assert_eq!(decode_base58("2gé").unwrap_err(), Base58Error::InvalidCharacter('é'));
+    }
+
+    #[test]
+    fn test_decode_base58_checksum() {
+        // nより大きい値や25byte以外の長さもそのまま読める
+        let long = vec![0xffu8; 78];
+        assert_eq!(decode_base58_checksum(&encode_base58_checksum(long.clone())).unwrap(), long);
+        let zeros = vec![0u8, 0, 0, 1];
+        assert_eq!(decode_base58_checksum(&encode_base58_checksum(zeros.clone())).unwrap(), zeros);
+
+        let (version, hash160) =
+            decode_base58_checksum_with_version("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").unwrap();
+        assert_eq!(version, 0x00);
+        assert_eq!(hash160, decode_hex("62e907b15cbf27d5425399ebf6f0fb50ebb88f18").unwrap());
+        assert_eq!(
+            address_decode_base58("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string()).unwrap(),
+            hash160
+        );
+        assert_eq!(
+            decode_base58_checksum("1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L").unwrap_err(),
+            Base58Error::InvalidChecksum
+        );
+        assert_eq!(decode_base58_checksum("2g").unwrap_err(), Base58Error::TooShort);
+    }
 }
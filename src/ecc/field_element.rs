@@ -1,6 +1,7 @@
 use std::fmt::{Display, Formatter};
 use std::fmt;
 use std::ops::{Sub, Add, Mul, Rem, Div};
+use crate::ecc::weierstrass::PrimeField;
 
 // Debugの自動実装
 #[derive(Debug, Copy, Clone)]
@@ -93,6 +94,15 @@ impl FieldElement {
     }
 }
 
+impl PrimeField for FieldElement {
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+    fn inv(&self) -> FieldElement {
+        FieldElement::inv(*self)
+    }
+}
+
 impl Div for FieldElement {
     type Output = FieldElement;
 
@@ -1,5 +1,6 @@
 use crate::ecc::field_point;
 use crate::ecc::field_element;
+use crate::ecc::weierstrass::WeierstrassCurve;
 
 #[derive(Debug, Clone)]
 pub struct FieldPlanarGraph {
@@ -28,6 +29,21 @@ impl PartialEq for FieldPlanarGraph {
     }
 }
 
+// 右辺が x^3 + ax + b の形であることを前提に、b = rhs(0), a = rhs(1) - 1 - b で係数を取り出す
+impl WeierstrassCurve for FieldPlanarGraph {
+    type Field = field_element::FieldElement;
+
+    fn a(&self) -> field_element::FieldElement {
+        (self.rhs)(self.element(1)) - self.element(1) - self.b()
+    }
+    fn b(&self) -> field_element::FieldElement {
+        (self.rhs)(self.element(0))
+    }
+    fn element(&self, v: u64) -> field_element::FieldElement {
+        field_element::new_field_element(v as i64, self.field)
+    }
+}
+
 pub fn new_field_planar_graph(
     lhs: fn(y: field_element::FieldElement) -> field_element::FieldElement,
     rhs: fn(y: field_element::FieldElement) -> field_element::FieldElement,
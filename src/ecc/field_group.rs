@@ -0,0 +1,274 @@
+use crate::ecc::field_element::new_field_element;
+use crate::ecc::field_graph::FieldPlanarGraph;
+use crate::ecc::field_point_on_curve::FieldPointOnGraph;
+use crate::ecc::weierstrass::{
+    new_weierstrass_point_infinity, new_weierstrass_point_unchecked, WeierstrassCurve,
+};
+use std::collections::HashMap;
+
+// 小さい体上の曲線の群を調べるためのツール。
+// どれも全探索か sqrt(n) 程度の計算量なので、secp256k1 (n ≒ 2^256) では
+// BSGSでもρ法でも 2^128 回程度の群演算が必要になり現実的に解けない。
+
+// HashMapのキーにするために座標を数値にする
+fn point_key(p: &FieldPointOnGraph) -> Option<(u64, u64)> {
+    if p.is_infinity() {
+        return None;
+    }
+    Some((p.x.num, p.y.num))
+}
+
+// 無限遠点を含めた曲線上の全ての点。無限遠点、xの昇順、yの昇順で並ぶ
+#[allow(dead_code)]
+pub fn enumerate_points(graph: &FieldPlanarGraph) -> Vec<FieldPointOnGraph> {
+    let prime = graph.element(0).prime;
+    // y^2 の値からyを引けるようにしておく
+    let mut roots: HashMap<u64, Vec<u64>> = HashMap::new();
+    for y in 0..prime {
+        let fy = new_field_element(y as i64, prime);
+        roots.entry((graph.lhs)(fy).num).or_default().push(y);
+    }
+    let mut points = vec![new_weierstrass_point_infinity(graph.clone())];
+    for x in 0..prime {
+        let fx = new_field_element(x as i64, prime);
+        if let Some(ys) = roots.get(&(graph.rhs)(fx).num) {
+            for y in ys {
+                points.push(new_weierstrass_point_unchecked(
+                    graph.clone(),
+                    fx,
+                    new_field_element(*y as i64, prime),
+                ));
+            }
+        }
+    }
+    points
+}
+
+// 群の位数 (点の個数)
+#[allow(dead_code)]
+pub fn group_order(graph: &FieldPlanarGraph) -> u64 {
+    enumerate_points(graph).len() as u64
+}
+
+// nP = 0 となる最小のn。ラグランジュの定理より点の位数は群の位数の約数なので、約数だけを調べる
+#[allow(dead_code)]
+pub fn point_order(point: &FieldPointOnGraph, group_order: u64) -> u64 {
+    for d in 1..=group_order {
+        if group_order.is_multiple_of(d) && point.mul(d as u128).is_infinity() {
+            return d;
+        }
+    }
+    panic!("point order must divide the group order");
+}
+
+// 群全体を生成する点。群が巡回群でなければ空になる
+#[allow(dead_code)]
+pub fn find_generators(graph: &FieldPlanarGraph) -> Vec<FieldPointOnGraph> {
+    let points = enumerate_points(graph);
+    let n = points.len() as u64;
+    points
+        .into_iter()
+        .filter(|p| point_order(p, n) == n)
+        .collect()
+}
+
+// kG = Q となるkを 0..order で総当たりする
+#[allow(dead_code)]
+pub fn discrete_log_brute_force(
+    g: &FieldPointOnGraph,
+    q: &FieldPointOnGraph,
+    order: u64,
+) -> Option<u64> {
+    let mut r = new_weierstrass_point_infinity(g.curve.clone());
+    for k in 0..order {
+        if r == *q {
+            return Some(k);
+        }
+        r = r + g.clone();
+    }
+    None
+}
+
+// Baby-step giant-step。m = ceil(sqrt(order)) として k = im + j に分解する。
+// jG (0 <= j < m) を表にしておき、Q - imG が表にあるかを調べる
+#[allow(dead_code)]
+pub fn discrete_log_bsgs(g: &FieldPointOnGraph, q: &FieldPointOnGraph, order: u64) -> Option<u64> {
+    let mut m = (order as f64).sqrt() as u64;
+    while m * m < order {
+        m += 1;
+    }
+    let mut baby = HashMap::new();
+    let mut r = new_weierstrass_point_infinity(g.curve.clone());
+    for j in 0..m {
+        baby.entry(point_key(&r)).or_insert(j);
+        r = r + g.clone();
+    }
+    // r = mG
+    let giant = r.neg();
+    let mut gamma = q.clone();
+    for i in 0..m {
+        if let Some(j) = baby.get(&point_key(&gamma)) {
+            return Some(i * m + j);
+        }
+        gamma = gamma + giant.clone();
+    }
+    None
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        return a;
+    }
+    gcd(b, a % b)
+}
+
+// 拡張ユークリッドで a^-1 mod m を求める (gcd(a, m) = 1 が前提)
+fn inv_mod(a: u64, m: u64) -> u64 {
+    let (mut r0, mut r1) = (m as i128, (a % m) as i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        let (r, t) = (r0 - q * r1, t0 - q * t1);
+        r0 = r1;
+        r1 = r;
+        t0 = t1;
+        t1 = t;
+    }
+    t0.rem_euclid(m as i128) as u64
+}
+
+// ρ法の1ステップ。R = aG + bQ を保ったまま、xで3つに分けて擬似ランダムに進む
+fn rho_step(
+    r: &FieldPointOnGraph,
+    a: u64,
+    b: u64,
+    g: &FieldPointOnGraph,
+    q: &FieldPointOnGraph,
+    order: u64,
+) -> (FieldPointOnGraph, u64, u64) {
+    let partition = if r.is_infinity() { 0 } else { r.x.num % 3 };
+    match partition {
+        0 => (r.clone() + q.clone(), a, (b + 1) % order),
+        1 => (r.double(), (a * 2) % order, (b * 2) % order),
+        _ => (r.clone() + g.clone(), (a + 1) % order, b),
+    }
+}
+
+// Pollardのρ法 (Floydの循環検出)。
+// a1G + b1Q = a2G + b2Q から (b2 - b1)k ≡ a1 - a2 (mod order) を解く。
+// orderが素数でない場合は解の候補が複数あるので、実際にkGを計算して確かめる
+#[allow(dead_code)]
+pub fn discrete_log_pollard_rho(
+    g: &FieldPointOnGraph,
+    q: &FieldPointOnGraph,
+    order: u64,
+) -> Option<u64> {
+    if *q == new_weierstrass_point_infinity(g.curve.clone()) {
+        return Some(0);
+    }
+    // 出発点を変えて何度か試す
+    for seed in 1..order.min(32) {
+        let start = g.mul(seed as u128) + q.clone();
+        let (mut tortoise, mut ta, mut tb) = (start.clone(), seed, 1);
+        let (mut hare, mut ha, mut hb) = (start, seed, 1);
+        for _ in 0..(4 * order + 4) {
+            let t = rho_step(&tortoise, ta, tb, g, q, order);
+            tortoise = t.0;
+            ta = t.1;
+            tb = t.2;
+            let h = rho_step(&hare, ha, hb, g, q, order);
+            let h = rho_step(&h.0, h.1, h.2, g, q, order);
+            hare = h.0;
+            ha = h.1;
+            hb = h.2;
+            if tortoise == hare {
+                break;
+            }
+        }
+        if tortoise != hare {
+            continue;
+        }
+        let lhs = (tb + order - hb) % order;
+        let rhs = (ha + order - ta) % order;
+        if lhs == 0 {
+            continue;
+        }
+        let d = gcd(lhs, order);
+        if !rhs.is_multiple_of(d) {
+            continue;
+        }
+        let reduced = order / d;
+        let k0 = ((rhs / d) as u128 * inv_mod(lhs / d, reduced) as u128 % reduced as u128) as u64;
+        for i in 0..d {
+            let k = k0 + i * reduced;
+            if g.mul(k as u128) == *q {
+                return Some(k);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::field_element::FieldElement;
+    use crate::ecc::field_graph::new_field_planar_graph;
+    use crate::ecc::weierstrass::new_weierstrass_point;
+
+    fn graph(prime: u64) -> FieldPlanarGraph {
+        let lhs = |y: FieldElement| y * y;
+        let rhs = |x: FieldElement| x * x * x + new_field_element(7, x.prime);
+        new_field_planar_graph(lhs, rhs, prime, "y^2 = x^3 + 7")
+    }
+
+    #[test]
+    fn test_f223_group() {
+        let g = graph(223);
+        let points = enumerate_points(&g);
+        assert_eq!(points.len(), 252);
+        assert_eq!(group_order(&g), 252);
+        assert!(points[0].is_infinity());
+        assert!(points[1..].iter().all(|p| p.is_on_curve()));
+
+        // 252 = 2^2 * 3^2 * 7 だが位数252の点は無く、巡回群ではない
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for p in points.iter() {
+            *counts.entry(point_order(p, 252)).or_default() += 1;
+        }
+        assert_eq!(counts[&42], 144);
+        assert_eq!(counts[&21], 48);
+        assert_eq!(counts[&1], 1);
+        assert!(find_generators(&g).is_empty());
+
+        // P.58 の (15,86) は位数7、(47,71) は位数21
+        let p = new_weierstrass_point(g.clone(), new_field_element(15, 223), new_field_element(86, 223)).unwrap();
+        assert_eq!(point_order(&p, 252), 7);
+        let p = new_weierstrass_point(g, new_field_element(47, 223), new_field_element(71, 223)).unwrap();
+        assert_eq!(point_order(&p, 252), 21);
+
+        // 位数が素数でない部分群でも離散対数が解ける
+        let q = p.mul(13);
+        assert_eq!(discrete_log_brute_force(&p, &q, 21), Some(13));
+        assert_eq!(discrete_log_bsgs(&p, &q, 21), Some(13));
+        assert_eq!(discrete_log_pollard_rho(&p, &q, 21), Some(13));
+    }
+
+    #[test]
+    fn test_prime_order_group() {
+        // F163 上の y^2 = x^3 + 7 は位数139の素数位数の群なので、無限遠点以外は全て生成元
+        let g = graph(163);
+        assert_eq!(group_order(&g), 139);
+        let generators = find_generators(&g);
+        assert_eq!(generators.len(), 138);
+
+        let base = &generators[0];
+        for k in [0u64, 1, 77, 138].iter() {
+            let q = base.mul(*k as u128);
+            assert_eq!(discrete_log_brute_force(base, &q, 139), Some(*k));
+            assert_eq!(discrete_log_bsgs(base, &q, 139), Some(*k));
+            assert_eq!(discrete_log_pollard_rho(base, &q, 139), Some(*k));
+        }
+    }
+}
@@ -1,122 +1,28 @@
-use crate::ecc::field_element;
 use crate::ecc::field_graph;
 use crate::ecc::field_graph::FieldPlanarGraph;
 use crate::ecc::field_point;
-use crate::ecc::field_point::{new_empty_point, new_point, FieldPoint};
-use std::ops::Add;
+use crate::ecc::weierstrass::{
+    new_weierstrass_point, new_weierstrass_point_infinity, WeierstrassPoint,
+};
 
+// 演算はsecp256k1と共通のWeierstrassPointで行う
+pub type FieldPointOnGraph = WeierstrassPoint<FieldPlanarGraph>;
 
 pub fn new_field_point_on_graph(
     point: field_point::FieldPoint,
     graph: field_graph::FieldPlanarGraph,
 ) -> Result<FieldPointOnGraph, &'static str> {
     if point.is_infinity {
-        return Ok(FieldPointOnGraph { graph, point });
-    }
-
-    // 点がグラフ上にあること
-    if (graph.lhs)(point.y) != (graph.rhs)(point.x) {
-        return Err("point not on curve");
-    }
-
-    return Ok(FieldPointOnGraph { graph, point });
-}
-
-#[derive(Debug, Clone)]
-pub struct FieldPointOnGraph {
-    pub graph: FieldPlanarGraph,
-    pub point: FieldPoint,
-}
-
-impl FieldPointOnGraph {
-    fn inner_mul(self, f: FieldPointOnGraph, v: u128) -> FieldPointOnGraph {
-        let prime = self.point.x.prime;
-        if v == 0 {
-            return new_field_point_on_graph(new_empty_point(prime), self.graph).unwrap();
-        }
-        if v % 2 == 0 {
-            let half_res = self.inner_mul(f, v / 2).clone();
-            let half_res2 = half_res.clone();
-            return half_res + half_res2;
-        }
-        let cf = f.clone();
-        f.clone() + f.inner_mul(cf, v - 1)
-    }
-    fn mul(self, v: u128) -> FieldPointOnGraph {
-        let this = self.clone();
-        self.inner_mul(this, v)
-    }
-}
-
-impl PartialEq for FieldPointOnGraph {
-    fn eq(&self, other: &Self) -> bool {
-        return self.point == other.point && self.graph == other.graph;
-    }
-}
-
-impl Add for FieldPointOnGraph {
-    type Output = FieldPointOnGraph;
-    fn add(self, rhs: Self) -> Self::Output {
-        if self.graph != rhs.graph {
-            panic!(
-                "graph mismatch. can't add function with \n lhs graph: {} \n rhs graph: {}",
-                self.graph.expression, rhs.graph.expression
-            );
-        }
-
-        if self.point.is_infinity {
-            return rhs;
-        }
-
-        if rhs.point.is_infinity {
-            return self;
-        }
-
-        // 楕円曲線の座標が一致する時は接点の傾きを利用する。
-        if self == rhs {
-            let prime = self.point.x.prime; // 適当に素数を持ってくる
-            let f0 = field_element::new_field_element(0, prime);
-            let f1 = field_element::new_field_element(1, prime);
-            let f2 = field_element::new_field_element(2, prime);
-            let f3 = field_element::new_field_element(3, prime);
-            if self.point.y == f0 && rhs.point.y == f0 {
-                return new_field_point_on_graph(field_point::new_empty_point(prime), self.graph)
-                    .unwrap();
-            }
-
-            let b = (self.graph.rhs)(f0);
-            let a = (self.graph.rhs)(f1) - f1 - b;
-            let x = self.point.x;
-            let y = self.point.y;
-            let s = (f3 * x * x + a) / (f2 * y);
-            let x3 = s * s - (f2 * x);
-            let y3 = s * (x - x3) - y;
-            let p = new_point(x3, y3);
-            return new_field_point_on_graph(p, self.graph).unwrap();
-        }
-
-        // 加法逆元の場合、無限遠点を返す
-        let prime = self.point.x.prime; // 適当に素数を持ってくる
-        let f0 = field_element::new_field_element(0, prime);
-        if self.point.x == rhs.point.x && (self.point.y + rhs.point.y) == f0 {
-            let prime = self.point.x.prime; // 適当に素数を持ってくる
-            return new_field_point_on_graph(field_point::new_empty_point(prime), self.graph)
-                .unwrap();
-        }
-
-        let s = (rhs.point.y - self.point.y) / (rhs.point.x - self.point.x);
-        let x3 = s * s - self.point.x - rhs.point.x;
-        let y3 = s * (self.point.x - x3) - self.point.y;
-
-        let p = new_point(x3, y3);
-        return new_field_point_on_graph(p, self.graph).unwrap();
+        return Ok(new_weierstrass_point_infinity(graph));
     }
+    new_weierstrass_point(graph, point.x, point.y)
 }
 
 #[cfg(test)]
 mod tests {
     extern crate test;
     use super::*;
+    use crate::ecc::field_element;
     use crate::ecc::field_graph::new_field_planar_graph;
     use crate::ecc::field_point_on_curve;
     use test::Bencher;
@@ -162,7 +68,7 @@ mod tests {
                 assert_eq!(expected_point_on_graph, sum);
                 println!(
                     "F: {} 楕円曲線y^2 = x^3 + 7 上での 点{} + {} = {}",
-                    field, left_point, right_point, sum.point
+                    field, left_point, right_point, sum
                 );
             }
 
@@ -194,7 +100,7 @@ mod tests {
                 assert_eq!(expected_point_on_graph, sum);
                 println!(
                     "F: {} 楕円曲線y^2 = x^3 + 7 上での 点{} + {} = {}",
-                    field, left_point, right_point, sum.point
+                    field, left_point, right_point, sum
                 );
             }
 
@@ -226,7 +132,7 @@ mod tests {
                 assert_eq!(expected_point_on_graph, sum);
                 println!(
                     "F: {} 楕円曲線y^2 = x^3 + 7 上での 点{} + {} = {}",
-                    field, left_point, right_point, sum.point
+                    field, left_point, right_point, sum
                 );
             }
         }
@@ -326,7 +232,7 @@ mod tests {
             let g = graph.clone();
             let gp = field_point_on_curve::new_field_point_on_graph(p, g).unwrap();
             let gp = gp.mul(i);
-            println!("{}: {}", i, gp.point);
+            println!("{}: {}", i, gp);
         }
         {
             let x = field_element::new_field_element(47, 223);
@@ -0,0 +1,750 @@
+use crate::ecc::secp256k1_ecmult::mul_g_ct;
+use crate::ecc::secp256k1_point::{new_secp256k1point_g, Secp256k1Point};
+use crate::ecc::secp256k1_scalar_element::{new_secp256k1scalarelement_from_u64, Secp256k1ScalarElement};
+use crate::ecc::secp256k1_schnorr::{
+    hash_to_scalar, new_secp256k1schnorrsignature, schnorr_challenge, Secp256k1SchnorrSignature,
+};
+use crate::ecc::zeroize::{new_zeroizing, Zeroize};
+use crate::helper::helper::sha256;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+// FROST (t-of-n の閾値Schnorr署名)
+// 署名はBIP340の形式なので、グループ公開鍵に対して Secp256k1Point::verify_schnorr で検証できる。
+// 参加者の識別子は 1..=n で、秘密の多項式 f に対して f(識別子) が各参加者の秘密鍵シェアになる。
+
+fn scalar(v: u32) -> Secp256k1ScalarElement {
+    new_secp256k1scalarelement_from_u64(v as u64)
+}
+
+// 公開鍵のyが奇数なら-1、偶数なら1
+fn parity_factor(p: &Secp256k1Point) -> Secp256k1ScalarElement {
+    let one = new_secp256k1scalarelement_from_u64(1);
+    if p.has_even_y() {
+        one
+    } else {
+        -one
+    }
+}
+
+// 呼び出し側から渡された乱数と秘密の値から、用途ごとに別のスカラーを導出する
+// 0になった場合(事実上起こらない)はNone
+fn derive_scalar(
+    tag: &str,
+    rand: &[u8; 32],
+    secret: &[u8],
+    counter: u32,
+) -> Option<Secp256k1ScalarElement> {
+    let mut data = new_zeroizing(rand.to_vec());
+    data.extend_from_slice(secret);
+    data.extend_from_slice(&counter.to_be_bytes());
+    let k = hash_to_scalar(tag, &data);
+    if k.is_zero() {
+        return None;
+    }
+    Some(k)
+}
+
+// f(x) = a_0 + a_1 x + ... + a_{t-1} x^{t-1} をホーナー法で計算する
+fn evaluate_polynomial(coefficients: &[Secp256k1ScalarElement], x: u32) -> Secp256k1ScalarElement {
+    let x = scalar(x);
+    let mut result = new_secp256k1scalarelement_from_u64(0);
+    for a in coefficients.iter().rev() {
+        result = result * x.clone() + a.clone();
+    }
+    result
+}
+
+// 係数のコミットメント C_j = a_j G から f(x)G = sum(x^j C_j) を計算する
+fn evaluate_commitments(commitments: &[Secp256k1Point], x: u32) -> Secp256k1Point {
+    let mut powers = vec![];
+    let mut power = new_secp256k1scalarelement_from_u64(1);
+    for _ in commitments {
+        powers.push(power.clone());
+        power = power * scalar(x);
+    }
+    Secp256k1Point::multi_mul(commitments, &powers)
+}
+
+// 参加者の集合 indices で f(0) を復元するときの index のラグランジュ係数
+fn lagrange_coefficient(index: u32, indices: &[u32]) -> Secp256k1ScalarElement {
+    let mut numerator = new_secp256k1scalarelement_from_u64(1);
+    let mut denominator = new_secp256k1scalarelement_from_u64(1);
+    for j in indices {
+        if *j == index {
+            continue;
+        }
+        numerator = numerator * scalar(*j);
+        denominator = denominator * (scalar(*j) - scalar(index));
+    }
+    numerator / denominator
+}
+
+// 参加者ごとの秘密鍵シェア。秘密鍵と同じくDebugで表示されず、ドロップ時に0で上書きされる
+#[derive(Clone)]
+pub struct FrostKeyShare {
+    pub(crate) index: u32,
+    secret: Secp256k1ScalarElement,
+    // secret * G
+    pub(crate) public: Secp256k1Point,
+}
+
+fn new_frostkeyshare(index: u32, secret: Secp256k1ScalarElement) -> FrostKeyShare {
+    FrostKeyShare {
+        index,
+        public: mul_g_ct(&secret.value).to_affine(),
+        secret,
+    }
+}
+
+impl Drop for FrostKeyShare {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl Debug for FrostKeyShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostKeyShare")
+            .field("index", &self.index)
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+impl FrostKeyShare {
+    // 多項式のコミットメントと照らし合わせて、配られたシェアが正しいか確認する (Feldman VSS)
+    #[allow(dead_code)]
+    pub fn verify(&self, commitments: &[Secp256k1Point]) -> bool {
+        evaluate_commitments(commitments, self.index) == self.public
+    }
+}
+
+// 全員が共有する公開情報。検証用公開鍵 Y_i = s_i G は署名シェアの検証に使う
+#[derive(Clone, Debug)]
+pub struct FrostPublicKeyPackage {
+    pub(crate) threshold: u32,
+    pub(crate) group_key: Secp256k1Point,
+    pub(crate) verifying_shares: Vec<(u32, Secp256k1Point)>,
+}
+
+impl FrostPublicKeyPackage {
+    // グループ公開鍵(BIP340ではx座標だけを使う)
+    #[allow(dead_code)]
+    pub fn group_key(&self) -> Secp256k1Point {
+        self.group_key.clone()
+    }
+
+    fn verifying_share(&self, index: u32) -> Option<&Secp256k1Point> {
+        self.verifying_shares
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, p)| p)
+    }
+}
+
+fn new_frostpublickeypackage(threshold: u32, participants: u32, commitments: &[Secp256k1Point]) -> FrostPublicKeyPackage {
+    FrostPublicKeyPackage {
+        threshold,
+        group_key: commitments[0].clone(),
+        verifying_shares: (1..=participants)
+            .map(|i| (i, evaluate_commitments(commitments, i)))
+            .collect(),
+    }
+}
+
+// 信頼できるディーラーが秘密鍵をt-of-nに分割する。
+// 戻り値は各参加者のシェア、公開情報、シェアを検証するための多項式のコミットメント
+#[allow(dead_code)]
+pub fn frost_trusted_dealer_keygen(
+    secret: &Secp256k1ScalarElement,
+    threshold: u32,
+    participants: u32,
+    rand: &[u8; 32],
+) -> Option<(Vec<FrostKeyShare>, FrostPublicKeyPackage, Vec<Secp256k1Point>)> {
+    if secret.is_zero() || threshold == 0 || threshold > participants {
+        return None;
+    }
+    let secret_bytes = new_zeroizing(secret.value.to_be_bytes());
+    let mut coefficients = vec![secret.clone()];
+    for j in 1..threshold {
+        match derive_scalar("FROST/coefficient", rand, &secret_bytes[..], j) {
+            Some(a) => coefficients.push(a),
+            None => {
+                for a in coefficients.iter_mut() {
+                    a.zeroize();
+                }
+                return None;
+            }
+        }
+    }
+    let commitments: Vec<Secp256k1Point> = coefficients
+        .iter()
+        .map(|a| mul_g_ct(&a.value).to_affine())
+        .collect();
+    let shares = (1..=participants)
+        .map(|i| new_frostkeyshare(i, evaluate_polynomial(&coefficients, i)))
+        .collect();
+    for a in coefficients.iter_mut() {
+        a.zeroize();
+    }
+    let package = new_frostpublickeypackage(threshold, participants, &commitments);
+    Some((shares, package, commitments))
+}
+
+// DKGの1ラウンド目で全員に公開する値。
+// 定数項の知識の証明 (R, mu) で、他人のコミットメントを打ち消すような鍵の選び方を防ぐ
+#[derive(Clone, Debug)]
+pub struct FrostDkgCommitment {
+    pub(crate) index: u32,
+    pub(crate) commitments: Vec<Secp256k1Point>,
+    pub(crate) proof_r: Secp256k1Point,
+    pub(crate) proof_mu: Secp256k1ScalarElement,
+}
+
+// DKGの1ラウンド目で作った秘密の多項式。係数はDebugで表示されず、ドロップ時に0で上書きされる
+pub struct FrostDkgSecretPackage {
+    index: u32,
+    threshold: u32,
+    participants: u32,
+    coefficients: Vec<Secp256k1ScalarElement>,
+}
+
+impl Drop for FrostDkgSecretPackage {
+    fn drop(&mut self) {
+        for a in self.coefficients.iter_mut() {
+            a.zeroize();
+        }
+    }
+}
+
+impl Debug for FrostDkgSecretPackage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostDkgSecretPackage")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .field("participants", &self.participants)
+            .finish()
+    }
+}
+
+// c = hash_FROST/dkg(index || C_0 || R)
+fn dkg_challenge(index: u32, constant: &Secp256k1Point, r: &Secp256k1Point) -> Secp256k1ScalarElement {
+    let mut data = index.to_be_bytes().to_vec();
+    data.append(&mut constant.clone().compressed_sec());
+    data.append(&mut r.clone().compressed_sec());
+    hash_to_scalar("FROST/dkg", &data)
+}
+
+// DKGの1ラウンド目。randは毎回新しい乱数でなければならない
+#[allow(dead_code)]
+pub fn frost_dkg_round1(
+    index: u32,
+    threshold: u32,
+    participants: u32,
+    rand: &[u8; 32],
+) -> Option<(FrostDkgSecretPackage, FrostDkgCommitment)> {
+    if index == 0 || index > participants || threshold == 0 || threshold > participants {
+        return None;
+    }
+    let index_bytes = index.to_be_bytes();
+    // 途中で失敗しても、導出済みの係数はDropで消える
+    let mut secret_package = FrostDkgSecretPackage {
+        index,
+        threshold,
+        participants,
+        coefficients: vec![],
+    };
+    for j in 0..threshold {
+        let a = derive_scalar("FROST/coefficient", rand, &index_bytes, j)?;
+        secret_package.coefficients.push(a);
+    }
+    let commitments: Vec<Secp256k1Point> = secret_package
+        .coefficients
+        .iter()
+        .map(|a| mul_g_ct(&a.value).to_affine())
+        .collect();
+    let k = new_zeroizing(derive_scalar("FROST/dkg nonce", rand, &index_bytes, 0)?);
+    let proof_r = mul_g_ct(&k.value).to_affine();
+    let c = dkg_challenge(index, &commitments[0], &proof_r);
+    let proof_mu = k.clone() + secret_package.coefficients[0].clone() * c;
+    let commitment = FrostDkgCommitment {
+        index,
+        commitments,
+        proof_r,
+        proof_mu,
+    };
+    Some((secret_package, commitment))
+}
+
+impl FrostDkgSecretPackage {
+    // DKGの2ラウンド目で参加者receiverに秘密裏に送るシェア f_i(receiver)。
+    // f_i(0) はグループの秘密鍵への自分の寄与なので、1..=n 以外の識別子にはNoneを返す
+    #[allow(dead_code)]
+    pub fn share_for(&self, receiver: u32) -> Option<Secp256k1ScalarElement> {
+        if receiver == 0 || receiver > self.participants {
+            return None;
+        }
+        Some(evaluate_polynomial(&self.coefficients, receiver))
+    }
+
+    // 全員のコミットメントと自分宛のシェア(送り主の識別子, シェア)から鍵シェアを作る。
+    // 知識の証明かシェアが正しくない参加者がいた場合は、その識別子をErrで返す
+    #[allow(dead_code)]
+    pub fn finalize(
+        &self,
+        commitments: &[FrostDkgCommitment],
+        shares: &[(u32, Secp256k1ScalarElement)],
+    ) -> Result<(FrostKeyShare, FrostPublicKeyPackage), Vec<u32>> {
+        let mut failures = vec![];
+        let mut accepted: Vec<(&FrostDkgCommitment, Secp256k1ScalarElement)> = vec![];
+        for sender in 1..=self.participants {
+            let commitment = commitments.iter().find(|c| c.index == sender);
+            let share = if sender == self.index {
+                self.share_for(self.index)
+            } else {
+                shares.iter().find(|(i, _)| *i == sender).map(|(_, s)| s.clone())
+            };
+            match (commitment, share) {
+                (Some(commitment), Some(share))
+                    if commitment.commitments.len() == self.threshold as usize
+                        && commitment.verify_proof()
+                        && new_frostkeyshare(self.index, share.clone()).verify(&commitment.commitments) =>
+                {
+                    accepted.push((commitment, share))
+                }
+                _ => failures.push(sender),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(failures);
+        }
+
+        // 全員の多項式の和が、グループの秘密の多項式になる
+        let mut secret = new_secp256k1scalarelement_from_u64(0);
+        for (_, share) in accepted.iter() {
+            secret = secret + share.clone();
+        }
+        let ones = vec![new_secp256k1scalarelement_from_u64(1); accepted.len()];
+        let mut group_commitments = vec![];
+        for j in 0..self.threshold as usize {
+            let points: Vec<Secp256k1Point> = accepted.iter().map(|(c, _)| c.commitments[j].clone()).collect();
+            group_commitments.push(Secp256k1Point::multi_mul(&points, &ones));
+        }
+        if group_commitments[0].is_infinity() {
+            return Err(vec![]);
+        }
+        Ok((
+            new_frostkeyshare(self.index, secret),
+            new_frostpublickeypackage(self.threshold, self.participants, &group_commitments),
+        ))
+    }
+}
+
+impl FrostDkgCommitment {
+    // mu G == R + c C_0
+    fn verify_proof(&self) -> bool {
+        let c = dkg_challenge(self.index, &self.commitments[0], &self.proof_r);
+        let lhs = Secp256k1Point::multi_mul(&[new_secp256k1point_g()], std::slice::from_ref(&self.proof_mu));
+        let rhs = Secp256k1Point::multi_mul(
+            &[self.proof_r.clone(), self.commitments[0].clone()],
+            &[new_secp256k1scalarelement_from_u64(1), c],
+        );
+        lhs == rhs
+    }
+}
+
+// 署名ごとに使い捨てるノンス。使い回すと秘密鍵シェアが漏れるので署名時に消費する。
+// Debugで表示されず、ドロップ時に0で上書きされる
+pub struct FrostSigningNonces {
+    index: u32,
+    hiding: Secp256k1ScalarElement,
+    binding: Secp256k1ScalarElement,
+}
+
+impl Drop for FrostSigningNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+impl Debug for FrostSigningNonces {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostSigningNonces")
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+// 公開するノンスのコミットメント (D = dG, E = eG)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrostSigningCommitment {
+    pub(crate) index: u32,
+    pub(crate) hiding: Secp256k1Point,
+    pub(crate) binding: Secp256k1Point,
+}
+
+// 署名の1ラウンド目。randは毎回新しい乱数でなければならない
+// ノンスが0になった場合(事実上起こらない)はNone
+#[allow(dead_code)]
+pub fn frost_commit(
+    share: &FrostKeyShare,
+    rand: &[u8; 32],
+) -> Option<(FrostSigningNonces, FrostSigningCommitment)> {
+    let secret_bytes = new_zeroizing(share.secret.value.to_be_bytes());
+    let hiding = new_zeroizing(derive_scalar("FROST/nonce", rand, &secret_bytes[..], 0)?);
+    let binding = new_zeroizing(derive_scalar("FROST/nonce", rand, &secret_bytes[..], 1)?);
+    let commitment = FrostSigningCommitment {
+        index: share.index,
+        hiding: mul_g_ct(&hiding.value).to_affine(),
+        binding: mul_g_ct(&binding.value).to_affine(),
+    };
+    let nonces = FrostSigningNonces {
+        index: share.index,
+        hiding: hiding.clone(),
+        binding: binding.clone(),
+    };
+    Some((nonces, commitment))
+}
+
+// 署名シェア
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrostSignatureShare {
+    pub(crate) index: u32,
+    pub(crate) z: Secp256k1ScalarElement,
+}
+
+// 1回の署名で参加者全員が共有する値
+#[derive(Clone, Debug)]
+pub struct FrostSession {
+    package: FrostPublicKeyPackage,
+    commitments: Vec<FrostSigningCommitment>,
+    // 参加者ごとの束縛係数 rho_i
+    binding_factors: Vec<Secp256k1ScalarElement>,
+    // 最終的な署名のR
+    r: Secp256k1Point,
+    // BIP340のチャレンジ
+    e: Secp256k1ScalarElement,
+}
+
+// 署名に参加する t 人以上のコミットメントからセッションを作る。
+// rho_i = hash_FROST/binding(Y.x || sha256(m) || 全員のコミットメント || i), R = sum(D_i + rho_i E_i)
+#[allow(dead_code)]
+pub fn new_frostsession(
+    package: &FrostPublicKeyPackage,
+    commitments: &[FrostSigningCommitment],
+    msg: &[u8],
+) -> Option<FrostSession> {
+    let mut commitments = commitments.to_vec();
+    commitments.sort_by_key(|c| c.index);
+    // 同じコミットメントの重複は取り除くが、同じ識別子で中身が違うものはどちらを使うか決められない
+    if commitments.windows(2).any(|w| w[0].index == w[1].index && w[0] != w[1]) {
+        return None;
+    }
+    commitments.dedup();
+    if commitments.len() < package.threshold as usize
+        || commitments.iter().any(|c| package.verifying_share(c.index).is_none())
+    {
+        return None;
+    }
+    let group_x = package.group_key.x_only();
+    let mut prefix = group_x.clone();
+    prefix.append(&mut sha256(msg.to_vec()));
+    for c in commitments.iter() {
+        prefix.extend_from_slice(&c.index.to_be_bytes());
+        prefix.append(&mut c.hiding.clone().compressed_sec());
+        prefix.append(&mut c.binding.clone().compressed_sec());
+    }
+    let binding_factors: Vec<Secp256k1ScalarElement> = commitments
+        .iter()
+        .map(|c| {
+            let mut data = prefix.clone();
+            data.extend_from_slice(&c.index.to_be_bytes());
+            hash_to_scalar("FROST/binding", &data)
+        })
+        .collect();
+
+    let mut points = vec![];
+    let mut scalars = vec![];
+    for (c, rho) in commitments.iter().zip(binding_factors.iter()) {
+        points.push(c.hiding.clone());
+        scalars.push(new_secp256k1scalarelement_from_u64(1));
+        points.push(c.binding.clone());
+        scalars.push(rho.clone());
+    }
+    let r = Secp256k1Point::multi_mul(&points, &scalars);
+    if r.is_infinity() {
+        return None;
+    }
+    let e = schnorr_challenge(&r.x_only(), &group_x, msg);
+    Some(FrostSession {
+        package: package.clone(),
+        commitments,
+        binding_factors,
+        r,
+        e,
+    })
+}
+
+impl FrostSession {
+    fn indices(&self) -> Vec<u32> {
+        self.commitments.iter().map(|c| c.index).collect()
+    }
+
+    fn position(&self, index: u32) -> Option<usize> {
+        self.commitments.iter().position(|c| c.index == index)
+    }
+
+    // z_i = ±(d_i + rho_i e_i) + e lambda_i (±s_i)。
+    // BIP340ではRとグループ公開鍵のyを偶数として扱うので、奇数の場合はノンスと秘密鍵シェアを反転する
+    #[allow(dead_code)]
+    pub fn sign(&self, nonces: FrostSigningNonces, share: &FrostKeyShare) -> Option<FrostSignatureShare> {
+        if nonces.index != share.index {
+            return None;
+        }
+        let i = self.position(share.index)?;
+        let commitment = &self.commitments[i];
+        if commitment.hiding != mul_g_ct(&nonces.hiding.value).to_affine()
+            || commitment.binding != mul_g_ct(&nonces.binding.value).to_affine()
+        {
+            return None;
+        }
+        let lambda = lagrange_coefficient(share.index, &self.indices());
+        let k = new_zeroizing(nonces.hiding.clone() + self.binding_factors[i].clone() * nonces.binding.clone());
+        let z = parity_factor(&self.r) * k.clone()
+            + self.e.clone() * lambda * parity_factor(&self.package.group_key) * share.secret.clone();
+        Some(FrostSignatureShare { index: share.index, z })
+    }
+
+    // z_i G == ±(D_i + rho_i E_i) + e lambda_i (±Y_i) を確認する
+    #[allow(dead_code)]
+    pub fn verify_share(&self, share: &FrostSignatureShare) -> bool {
+        let i = match self.position(share.index) {
+            Some(i) => i,
+            None => return false,
+        };
+        let verifying_share = self.package.verifying_share(share.index).unwrap();
+        let commitment = &self.commitments[i];
+        let nonce_factor = parity_factor(&self.r);
+        let lambda = lagrange_coefficient(share.index, &self.indices());
+        let rhs = Secp256k1Point::multi_mul(
+            &[
+                commitment.hiding.clone(),
+                commitment.binding.clone(),
+                verifying_share.clone(),
+            ],
+            &[
+                nonce_factor.clone(),
+                nonce_factor * self.binding_factors[i].clone(),
+                self.e.clone() * lambda * parity_factor(&self.package.group_key),
+            ],
+        );
+        let lhs = Secp256k1Point::multi_mul(&[new_secp256k1point_g()], std::slice::from_ref(&share.z));
+        lhs == rhs
+    }
+
+    // 署名シェアを検証してから足し合わせる。不正なシェアがあればその識別子をErrで返す
+    #[allow(dead_code)]
+    pub fn aggregate(&self, shares: &[FrostSignatureShare]) -> Result<Secp256k1SchnorrSignature, Vec<u32>> {
+        let mut failures: Vec<u32> = shares
+            .iter()
+            .filter(|s| !self.verify_share(s))
+            .map(|s| s.index)
+            .collect();
+        // 署名に参加した全員分のシェアが揃っていなければならない
+        for index in self.indices() {
+            if !shares.iter().any(|s| s.index == index) {
+                failures.push(index);
+            }
+        }
+        if !failures.is_empty() {
+            return Err(failures);
+        }
+        let mut z = new_secp256k1scalarelement_from_u64(0);
+        for index in self.indices() {
+            z = z + shares.iter().find(|s| s.index == index).unwrap().z.clone();
+        }
+        Ok(new_secp256k1schnorrsignature(self.r.x, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement_from_hex_str;
+
+    // signersの鍵シェアで一通りの署名を行う
+    fn sign(
+        shares: &[FrostKeyShare],
+        package: &FrostPublicKeyPackage,
+        signers: &[u32],
+        msg: &[u8],
+    ) -> Result<Secp256k1SchnorrSignature, Vec<u32>> {
+        let mut nonces = vec![];
+        let mut commitments = vec![];
+        for index in signers {
+            let share = &shares[*index as usize - 1];
+            let (n, c) = frost_commit(share, &[*index as u8; 32]).unwrap();
+            nonces.push(n);
+            commitments.push(c);
+        }
+        let session = new_frostsession(package, &commitments, msg).unwrap();
+        let signature_shares: Vec<FrostSignatureShare> = nonces
+            .into_iter()
+            .zip(signers.iter())
+            .map(|(n, index)| session.sign(n, &shares[*index as usize - 1]).unwrap())
+            .collect();
+        session.aggregate(&signature_shares)
+    }
+
+    #[test]
+    fn test_lagrange_interpolation() {
+        let secret = new_secp256k1scalarelement_from_hex_str("c0ffee").unwrap();
+        let (shares, _, _) = frost_trusted_dealer_keygen(&secret, 3, 5, &[1u8; 32]).unwrap();
+        for indices in [[1u32, 2, 3], [2, 4, 5], [5, 1, 3]].iter() {
+            let mut recovered = new_secp256k1scalarelement_from_u64(0);
+            for i in indices.iter() {
+                recovered = recovered + lagrange_coefficient(*i, indices) * shares[*i as usize - 1].secret.clone();
+            }
+            assert_eq!(recovered, secret);
+        }
+        // 閾値未満のシェアでは復元できない
+        let indices = [1u32, 2];
+        let mut recovered = new_secp256k1scalarelement_from_u64(0);
+        for i in indices.iter() {
+            recovered = recovered + lagrange_coefficient(*i, &indices) * shares[*i as usize - 1].secret.clone();
+        }
+        assert_ne!(recovered, secret);
+    }
+
+    #[test]
+    fn test_trusted_dealer_2_of_3() {
+        let msg = [0x42u8; 32];
+        // グループ公開鍵のyが奇数・偶数の両方を確認する
+        let mut parities = vec![];
+        for secret in 1..=8u64 {
+            let secret = new_secp256k1scalarelement_from_u64(secret * 0x1234567);
+            let (shares, package, commitments) =
+                frost_trusted_dealer_keygen(&secret, 2, 3, &[7u8; 32]).unwrap();
+            parities.push(package.group_key().has_even_y());
+            assert_eq!(package.group_key(), mul_g_ct(&secret.value).to_affine());
+            for share in shares.iter() {
+                assert!(share.verify(&commitments));
+            }
+            let mut forged = shares[0].clone();
+            forged.public = shares[1].public.clone();
+            assert!(!forged.verify(&commitments));
+            let debug = format!("{:?}", shares[0]);
+            assert!(debug.starts_with("FrostKeyShare { index: 1, public: "));
+            assert!(!debug.contains("secret"));
+            let (nonces, _) = frost_commit(&shares[0], &[1u8; 32]).unwrap();
+            assert_eq!(format!("{:?}", nonces), "FrostSigningNonces { index: 1 }");
+
+            for signers in [vec![1u32, 2], vec![1, 3], vec![2, 3], vec![1, 2, 3]].iter() {
+                let sig = sign(&shares, &package, signers, &msg).unwrap();
+                assert!(package.group_key().verify_schnorr(&msg, &sig));
+                assert!(!package.group_key().verify_schnorr(&[0x43u8; 32], &sig));
+            }
+            // 閾値に満たない人数ではセッションを作れない
+            let (_, commitment) = frost_commit(&shares[0], &[1u8; 32]).unwrap();
+            assert!(new_frostsession(&package, &[commitment.clone(), commitment.clone()], &msg).is_none());
+            // 同じ識別子で違うコミットメントがあるとセッションを作れない
+            let (_, other) = frost_commit(&shares[0], &[2u8; 32]).unwrap();
+            let (_, second) = frost_commit(&shares[1], &[1u8; 32]).unwrap();
+            assert!(new_frostsession(&package, &[commitment.clone(), second.clone(), commitment.clone()], &msg).is_some());
+            assert!(new_frostsession(&package, &[commitment, second, other], &msg).is_none());
+        }
+        assert!(parities.contains(&true) && parities.contains(&false));
+        assert!(frost_trusted_dealer_keygen(&new_secp256k1scalarelement_from_u64(1), 4, 3, &[0u8; 32]).is_none());
+    }
+
+    #[test]
+    fn test_invalid_signature_share() {
+        let msg = b"FROST";
+        let secret = new_secp256k1scalarelement_from_u64(99);
+        let (shares, package, _) = frost_trusted_dealer_keygen(&secret, 2, 3, &[3u8; 32]).unwrap();
+        let (n1, c1) = frost_commit(&shares[0], &[1u8; 32]).unwrap();
+        let (n3, c3) = frost_commit(&shares[2], &[3u8; 32]).unwrap();
+        let session = new_frostsession(&package, &[c1, c3.clone()], msg).unwrap();
+        let (other, _) = frost_commit(&shares[2], &[4u8; 32]).unwrap();
+        // コミットメントと違うノンスでは署名しない
+        assert!(session.sign(other, &shares[2]).is_none());
+        let z1 = session.sign(n1, &shares[0]).unwrap();
+        let z3 = session.sign(n3, &shares[2]).unwrap();
+        assert!(session.verify_share(&z1));
+        assert!(session.verify_share(&z3));
+
+        let mut bad = z3.clone();
+        bad.z = bad.z + new_secp256k1scalarelement_from_u64(1);
+        assert!(!session.verify_share(&bad));
+        assert_eq!(session.aggregate(&[z1.clone(), bad]), Err(vec![3]));
+        assert_eq!(session.aggregate(std::slice::from_ref(&z1)), Err(vec![3]));
+        let sig = session.aggregate(&[z3, z1]).unwrap();
+        assert!(package.group_key().verify_schnorr(msg, &sig));
+    }
+
+    #[test]
+    fn test_dkg_3_of_5() {
+        let (threshold, participants) = (3u32, 5u32);
+        let mut secret_packages = vec![];
+        let mut commitments = vec![];
+        for i in 1..=participants {
+            let (secret_package, commitment) =
+                frost_dkg_round1(i, threshold, participants, &[i as u8 + 10; 32]).unwrap();
+            secret_packages.push(secret_package);
+            commitments.push(commitment);
+        }
+        // 2ラウンド目: 各参加者が他の参加者にシェアを送る
+        let received = |receiver: u32| -> Vec<(u32, Secp256k1ScalarElement)> {
+            secret_packages
+                .iter()
+                .filter(|p| p.index != receiver)
+                .map(|p| (p.index, p.share_for(receiver).unwrap()))
+                .collect()
+        };
+        let mut shares = vec![];
+        let mut packages = vec![];
+        for p in secret_packages.iter() {
+            let (share, package) = p.finalize(&commitments, &received(p.index)).unwrap();
+            shares.push(share);
+            packages.push(package);
+        }
+        // 全員が同じグループ公開鍵と検証用公開鍵を得る
+        for package in packages.iter() {
+            assert_eq!(package.group_key(), packages[0].group_key());
+            assert_eq!(package.verifying_shares, packages[0].verifying_shares);
+        }
+        for share in shares.iter() {
+            assert_eq!(packages[0].verifying_share(share.index), Some(&share.public));
+        }
+
+        let msg = [0x99u8; 32];
+        for signers in [vec![1u32, 3, 5], vec![2, 3, 4], vec![1, 2, 3, 4, 5]].iter() {
+            let sig = sign(&shares, &packages[0], signers, &msg).unwrap();
+            assert!(packages[0].group_key().verify_schnorr(&msg, &sig));
+        }
+
+        assert_eq!(
+            format!("{:?}", secret_packages[0]),
+            "FrostDkgSecretPackage { index: 1, threshold: 3, participants: 5 }"
+        );
+        assert!(secret_packages[0].share_for(0).is_none());
+        assert!(secret_packages[0].share_for(participants + 1).is_none());
+
+        // 参加者2が参加者1に不正なシェアを送った
+        let mut tampered = received(1);
+        tampered[0].1 = tampered[0].1.clone() + new_secp256k1scalarelement_from_u64(1);
+        assert_eq!(secret_packages[0].finalize(&commitments, &tampered).err(), Some(vec![2]));
+        // 参加者4の知識の証明が不正
+        let mut forged = commitments.clone();
+        forged[3].proof_mu = forged[3].proof_mu.clone() + new_secp256k1scalarelement_from_u64(1);
+        assert_eq!(secret_packages[0].finalize(&forged, &received(1)).err(), Some(vec![4]));
+        // 参加者5のコミットメントが届いていない
+        assert_eq!(
+            secret_packages[0].finalize(&commitments[..4], &received(1)).err(),
+            Some(vec![5])
+        );
+    }
+}
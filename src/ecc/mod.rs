@@ -1,13 +1,33 @@
+pub mod bech32;
+pub mod bip32;
+pub mod bip39;
 pub mod decode;
 pub mod encode;
 pub mod field_element;
 pub mod field_graph;
+pub mod field_group;
 pub mod field_point;
 pub mod field_point_on_curve;
+pub mod frost;
+pub mod musig2;
+pub mod network;
+pub mod p256;
 pub mod point;
+pub mod secp256k1_batch_verify;
 pub mod secp256k1_curve;
+pub mod secp256k1_ecmult;
 pub mod secp256k1_field;
+pub mod secp256k1_jacobian_point;
 pub mod secp256k1_point;
 pub mod secp256k1_privatekey;
+pub mod secp256k1_projective_point;
+pub mod secp256k1_recoverable_signature;
 pub mod secp256k1_scalar_element;
+pub mod secp256k1_schnorr;
 pub mod secp256k1_signature;
+pub mod segwit_address;
+pub mod signed_message;
+pub mod taproot;
+pub mod u256;
+pub mod weierstrass;
+pub mod zeroize;
@@ -0,0 +1,828 @@
+use crate::ecc::secp256k1_ecmult::mul_g_ct;
+use crate::ecc::secp256k1_point::{new_secp256k1point_g, new_secp256k1point_infinity, Secp256k1Point};
+use crate::ecc::secp256k1_privatekey::Secp256k1PrivateKey;
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, new_secp256k1scalarelement_from_u64,
+    Secp256k1ScalarElement, SECP256K1_N,
+};
+use crate::ecc::secp256k1_schnorr::{
+    hash_to_scalar, new_secp256k1schnorrsignature, schnorr_challenge, Secp256k1SchnorrSignature,
+};
+use crate::ecc::u256::U256;
+use crate::helper::helper::tagged_hash;
+
+// BIP327 (MuSig2)
+// n-of-n の参加者で1つの集約公開鍵を作り、2ラウンドでBIP340のSchnorr署名を作る。
+
+// cbytes(P): 圧縮形式(33byte)
+fn cbytes(p: &Secp256k1Point) -> Vec<u8> {
+    p.clone().compressed_sec()
+}
+
+// cbytes_ext(P): 無限遠点は33byteの0で表す
+fn cbytes_ext(p: &Secp256k1Point) -> Vec<u8> {
+    if p.is_infinity() {
+        return vec![0u8; 33];
+    }
+    cbytes(p)
+}
+
+// cpoint(x): 圧縮形式の点を読む。曲線上に無い場合はNone
+fn cpoint(v: &[u8]) -> Option<Secp256k1Point> {
+    Secp256k1Point::parse_compressed_sec(v.to_vec()).ok()
+}
+
+// cpoint_ext(x): 33byteの0は無限遠点として読む
+fn cpoint_ext(v: &[u8]) -> Option<Secp256k1Point> {
+    if v.len() == 33 && v.iter().all(|b| *b == 0) {
+        return Some(new_secp256k1point_infinity());
+    }
+    cpoint(v)
+}
+
+// 32byteのビッグエンディアンをスカラーとして読む。n以上の場合はNone
+fn scalar_from_bytes(v: &[u8]) -> Option<Secp256k1ScalarElement> {
+    let value = U256::from_be_slice(v)?;
+    if value >= SECP256K1_N {
+        return None;
+    }
+    Some(new_secp256k1scalarelement_from_u256(value))
+}
+
+// 集約公開鍵のyが奇数なら-1、偶数なら1
+fn parity_factor(q: &Secp256k1Point) -> Secp256k1ScalarElement {
+    let one = new_secp256k1scalarelement_from_u64(1);
+    if q.has_even_y() {
+        one
+    } else {
+        -one
+    }
+}
+
+// 公開鍵を圧縮形式の辞書順に並べる
+#[allow(dead_code)]
+pub fn musig2_key_sort(pubkeys: &[Secp256k1Point]) -> Vec<Secp256k1Point> {
+    let mut sorted = pubkeys.to_vec();
+    sorted.sort_by_key(cbytes);
+    sorted
+}
+
+// 鍵集約の結果。Qは集約公開鍵、gaccとtaccはtweakを適用した時の符号と加算した値の累積
+#[derive(Clone, Debug)]
+pub struct MuSig2KeyAggContext {
+    pub(crate) pubkeys: Vec<Vec<u8>>,
+    // hash_KeyAgg list(pk_1 || ... || pk_u)
+    list_hash: Vec<u8>,
+    // pk_1と異なる最初の公開鍵(係数が1になる)
+    second_key: Option<Vec<u8>>,
+    pub(crate) q: Secp256k1Point,
+    pub(crate) gacc: Secp256k1ScalarElement,
+    pub(crate) tacc: Secp256k1ScalarElement,
+}
+
+// Q = sum(a_i * P_i)。公開鍵が無い場合とQが無限遠点になる場合はNone
+#[allow(dead_code)]
+pub fn musig2_key_agg(pubkeys: &[Secp256k1Point]) -> Option<MuSig2KeyAggContext> {
+    if pubkeys.is_empty() || pubkeys.iter().any(|p| p.is_infinity()) {
+        return None;
+    }
+    let pubkeys: Vec<Vec<u8>> = pubkeys.iter().map(cbytes).collect();
+    let list_hash = tagged_hash("KeyAgg list", &pubkeys.concat());
+    let second_key = pubkeys.iter().find(|pk| **pk != pubkeys[0]).cloned();
+    let mut ctx = MuSig2KeyAggContext {
+        pubkeys,
+        list_hash,
+        second_key,
+        q: new_secp256k1point_infinity(),
+        gacc: new_secp256k1scalarelement_from_u64(1),
+        tacc: new_secp256k1scalarelement_from_u64(0),
+    };
+    let points: Vec<Secp256k1Point> = ctx.pubkeys.iter().map(|pk| cpoint(pk).unwrap()).collect();
+    let coefficients: Vec<Secp256k1ScalarElement> =
+        ctx.pubkeys.iter().map(|pk| ctx.coefficient(pk)).collect();
+    ctx.q = Secp256k1Point::multi_mul(&points, &coefficients);
+    if ctx.q.is_infinity() {
+        return None;
+    }
+    Some(ctx)
+}
+
+impl MuSig2KeyAggContext {
+    // a_i = hash_KeyAgg coefficient(L || pk_i)。2番目の鍵だけは1にする
+    fn coefficient(&self, pk: &[u8]) -> Secp256k1ScalarElement {
+        if self.second_key.as_deref() == Some(pk) {
+            return new_secp256k1scalarelement_from_u64(1);
+        }
+        let mut data = self.list_hash.clone();
+        data.extend_from_slice(pk);
+        hash_to_scalar("KeyAgg coefficient", &data)
+    }
+
+    // 集約公開鍵(BIP340ではx座標だけを使う)
+    #[allow(dead_code)]
+    pub fn aggregate_key(&self) -> Secp256k1Point {
+        self.q.clone()
+    }
+
+    // 集約公開鍵にtweakを足す。is_xonlyならyを偶数とみなしてから足す(BIP341のTapTweak用)
+    #[allow(dead_code)]
+    pub fn apply_tweak(&self, tweak: &[u8], is_xonly: bool) -> Option<MuSig2KeyAggContext> {
+        if tweak.len() != 32 {
+            return None;
+        }
+        let g = if is_xonly {
+            parity_factor(&self.q)
+        } else {
+            new_secp256k1scalarelement_from_u64(1)
+        };
+        let t = scalar_from_bytes(tweak)?;
+        let q = Secp256k1Point::multi_mul(
+            &[self.q.clone(), new_secp256k1point_g()],
+            &[g.clone(), t.clone()],
+        );
+        if q.is_infinity() {
+            return None;
+        }
+        let mut ctx = self.clone();
+        ctx.q = q;
+        ctx.gacc = g.clone() * self.gacc.clone();
+        ctx.tacc = t + g * self.tacc.clone();
+        Some(ctx)
+    }
+}
+
+// 秘密ノンス。使い回すと秘密鍵が漏れるので、Cloneできないようにして署名時に消費する
+pub struct MuSig2SecNonce {
+    k1: Secp256k1ScalarElement,
+    k2: Secp256k1ScalarElement,
+    pk: Vec<u8>,
+}
+
+impl MuSig2SecNonce {
+    // 97byte: k1 || k2 || pk。テストベクタを読むためのもので、通常はnonce_genの結果をそのまま使う
+    #[allow(dead_code)]
+    pub(crate) fn parse(v: &[u8]) -> Option<MuSig2SecNonce> {
+        if v.len() != 97 {
+            return None;
+        }
+        let k1 = scalar_from_bytes(&v[..32])?;
+        let k2 = scalar_from_bytes(&v[32..64])?;
+        if k1.is_zero() || k2.is_zero() {
+            return None;
+        }
+        Some(MuSig2SecNonce {
+            k1,
+            k2,
+            pk: v[64..].to_vec(),
+        })
+    }
+}
+
+// 公開ノンス (R1, R2)。どちらも無限遠点にはならない
+#[derive(Clone, Debug, PartialEq)]
+pub struct MuSig2PubNonce {
+    pub(crate) r1: Secp256k1Point,
+    pub(crate) r2: Secp256k1Point,
+}
+
+impl MuSig2PubNonce {
+    #[allow(dead_code)]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = cbytes(&self.r1);
+        result.append(&mut cbytes(&self.r2));
+        result
+    }
+
+    #[allow(dead_code)]
+    pub fn parse(v: &[u8]) -> Option<MuSig2PubNonce> {
+        if v.len() != 66 {
+            return None;
+        }
+        Some(MuSig2PubNonce {
+            r1: cpoint(&v[..33])?,
+            r2: cpoint(&v[33..])?,
+        })
+    }
+}
+
+// 集約ノンス。参加者のノンスの和なので無限遠点になることがある
+#[derive(Clone, Debug, PartialEq)]
+pub struct MuSig2AggNonce {
+    pub(crate) r1: Secp256k1Point,
+    pub(crate) r2: Secp256k1Point,
+}
+
+impl MuSig2AggNonce {
+    #[allow(dead_code)]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = cbytes_ext(&self.r1);
+        result.append(&mut cbytes_ext(&self.r2));
+        result
+    }
+
+    #[allow(dead_code)]
+    pub fn parse(v: &[u8]) -> Option<MuSig2AggNonce> {
+        if v.len() != 66 {
+            return None;
+        }
+        Some(MuSig2AggNonce {
+            r1: cpoint_ext(&v[..33])?,
+            r2: cpoint_ext(&v[33..])?,
+        })
+    }
+}
+
+// ノンスを生成する。randは毎回新しい乱数でなければならない。
+// 秘密鍵・集約公開鍵(x-only)・メッセージ・追加の入力は、分かっていれば渡すことで乱数の偏りへの耐性が上がる
+// ノンスが0になった場合(事実上起こらない)はNone
+#[allow(dead_code)]
+pub fn musig2_nonce_gen(
+    rand: &[u8; 32],
+    pk: &Secp256k1Point,
+    secret: Option<&Secp256k1PrivateKey>,
+    aggpk: Option<&[u8]>,
+    msg: Option<&[u8]>,
+    extra_in: Option<&[u8]>,
+) -> Option<(MuSig2SecNonce, MuSig2PubNonce)> {
+    let seed: Vec<u8> = match secret {
+        Some(secret) => secret
+            .expose_secret()
+            .value
+            .to_be_bytes()
+            .iter()
+            .zip(tagged_hash("MuSig/aux", rand).iter())
+            .map(|(a, b)| a ^ b)
+            .collect(),
+        None => rand.to_vec(),
+    };
+    let pk = cbytes(pk);
+    let aggpk = aggpk.unwrap_or(&[]);
+    let extra_in = extra_in.unwrap_or(&[]);
+
+    let mut data = seed;
+    data.push(pk.len() as u8);
+    data.extend_from_slice(&pk);
+    data.push(aggpk.len() as u8);
+    data.extend_from_slice(aggpk);
+    match msg {
+        Some(msg) => {
+            data.push(1u8);
+            data.extend_from_slice(&(msg.len() as u64).to_be_bytes());
+            data.extend_from_slice(msg);
+        }
+        None => data.push(0u8),
+    }
+    data.extend_from_slice(&(extra_in.len() as u32).to_be_bytes());
+    data.extend_from_slice(extra_in);
+
+    let mut k = vec![];
+    for i in 0..2u8 {
+        let mut input = data.clone();
+        input.push(i);
+        let k_i = hash_to_scalar("MuSig/nonce", &input);
+        if k_i.is_zero() {
+            return None;
+        }
+        k.push(k_i);
+    }
+    let pubnonce = MuSig2PubNonce {
+        r1: mul_g_ct(&k[0].value).to_affine(),
+        r2: mul_g_ct(&k[1].value).to_affine(),
+    };
+    let secnonce = MuSig2SecNonce {
+        k1: k[0].clone(),
+        k2: k[1].clone(),
+        pk,
+    };
+    Some((secnonce, pubnonce))
+}
+
+// 全員の公開ノンスを足し合わせる
+#[allow(dead_code)]
+pub fn musig2_nonce_agg(pubnonces: &[MuSig2PubNonce]) -> MuSig2AggNonce {
+    let mut r1 = new_secp256k1point_infinity();
+    let mut r2 = new_secp256k1point_infinity();
+    for nonce in pubnonces {
+        r1 = r1 + nonce.r1.clone();
+        r2 = r2 + nonce.r2.clone();
+    }
+    MuSig2AggNonce { r1, r2 }
+}
+
+// 部分署名 (32byte)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MuSig2PartialSignature {
+    pub(crate) s: Secp256k1ScalarElement,
+}
+
+impl MuSig2PartialSignature {
+    #[allow(dead_code)]
+    pub fn serialize(&self) -> Vec<u8> {
+        self.s.value.to_be_bytes().to_vec()
+    }
+
+    // n以上の値は不正な部分署名としてNone
+    #[allow(dead_code)]
+    pub fn parse(v: &[u8]) -> Option<MuSig2PartialSignature> {
+        if v.len() != 32 {
+            return None;
+        }
+        Some(MuSig2PartialSignature {
+            s: scalar_from_bytes(v)?,
+        })
+    }
+}
+
+// 1回の署名で全員が共有する値
+#[derive(Clone, Debug)]
+pub struct MuSig2Session {
+    pub(crate) key_agg: MuSig2KeyAggContext,
+    // ノンスの係数
+    b: Secp256k1ScalarElement,
+    // 最終的な署名のR
+    r: Secp256k1Point,
+    // BIP340のチャレンジ
+    e: Secp256k1ScalarElement,
+}
+
+// b = hash_MuSig/noncecoef(aggnonce || Q.x || m), R = R1 + b*R2 (無限遠点の場合はG)
+#[allow(dead_code)]
+pub fn new_musig2session(
+    key_agg: &MuSig2KeyAggContext,
+    aggnonce: &MuSig2AggNonce,
+    msg: &[u8],
+) -> MuSig2Session {
+    let q_x = key_agg.q.x_only();
+    let mut data = aggnonce.serialize();
+    data.extend_from_slice(&q_x);
+    data.extend_from_slice(msg);
+    let b = hash_to_scalar("MuSig/noncecoef", &data);
+    let mut r = Secp256k1Point::multi_mul(
+        &[aggnonce.r1.clone(), aggnonce.r2.clone()],
+        &[new_secp256k1scalarelement_from_u64(1), b.clone()],
+    );
+    if r.is_infinity() {
+        r = new_secp256k1point_g();
+    }
+    let e = schnorr_challenge(&r.x_only(), &q_x, msg);
+    MuSig2Session {
+        key_agg: key_agg.clone(),
+        b,
+        r,
+        e,
+    }
+}
+
+impl MuSig2Session {
+    // 鍵集約に含まれていない公開鍵の場合はNone
+    fn coefficient(&self, pk: &[u8]) -> Option<Secp256k1ScalarElement> {
+        if !self.key_agg.pubkeys.iter().any(|p| p == pk) {
+            return None;
+        }
+        Some(self.key_agg.coefficient(pk))
+    }
+
+    // Rのyが奇数なら-1、偶数なら1
+    fn nonce_factor(&self) -> Secp256k1ScalarElement {
+        parity_factor(&self.r)
+    }
+
+    // s = k1 + b*k2 + e*a*d。秘密ノンスは消費される
+    #[allow(dead_code)]
+    pub fn partial_sign(
+        &self,
+        secnonce: MuSig2SecNonce,
+        secret: &Secp256k1PrivateKey,
+    ) -> Option<MuSig2PartialSignature> {
+        let pk = cbytes(&secret.point);
+        if pk != secnonce.pk {
+            return None;
+        }
+        let a = self.coefficient(&pk)?;
+        let nonce_factor = self.nonce_factor();
+        let k1 = nonce_factor.clone() * secnonce.k1;
+        let k2 = nonce_factor * secnonce.k2;
+        let g = parity_factor(&self.key_agg.q);
+        let d = g * self.key_agg.gacc.clone() * secret.expose_secret().clone();
+        let s = k1 + self.b.clone() * k2 + self.e.clone() * a * d;
+        Some(MuSig2PartialSignature { s })
+    }
+
+    // s*G == ±(R1 + b*R2) + e*a*g*gacc*P を確認する
+    #[allow(dead_code)]
+    pub fn partial_sig_verify(
+        &self,
+        psig: &MuSig2PartialSignature,
+        pubnonce: &MuSig2PubNonce,
+        pk: &Secp256k1Point,
+    ) -> bool {
+        if pk.is_infinity() {
+            return false;
+        }
+        let a = match self.coefficient(&cbytes(pk)) {
+            Some(a) => a,
+            None => return false,
+        };
+        let nonce_factor = self.nonce_factor();
+        let g = parity_factor(&self.key_agg.q) * self.key_agg.gacc.clone();
+        let rhs = Secp256k1Point::multi_mul(
+            &[pubnonce.r1.clone(), pubnonce.r2.clone(), pk.clone()],
+            &[
+                nonce_factor.clone(),
+                nonce_factor * self.b.clone(),
+                self.e.clone() * a * g,
+            ],
+        );
+        let lhs = Secp256k1Point::multi_mul(&[new_secp256k1point_g()], std::slice::from_ref(&psig.s));
+        lhs == rhs
+    }
+
+    // 部分署名を足し合わせ、tweakの分を加えてBIP340の署名にする
+    #[allow(dead_code)]
+    pub fn partial_sig_agg(&self, psigs: &[MuSig2PartialSignature]) -> Secp256k1SchnorrSignature {
+        let mut s = new_secp256k1scalarelement_from_u64(0);
+        for psig in psigs {
+            s = s + psig.s.clone();
+        }
+        let g = parity_factor(&self.key_agg.q);
+        s = s + self.e.clone() * g * self.key_agg.tacc.clone();
+        new_secp256k1schnorrsignature(self.r.x, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_privatekey::{
+        new_secp_256k1privatekey, new_secp_256k1privatekey_from_i32,
+    };
+    use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement_from_bytes_be;
+    use crate::helper::helper::decode_hex;
+
+    // テストベクタは https://github.com/bitcoin/bips/tree/master/bip-0327/vectors から
+
+    fn hex(s: &str) -> Vec<u8> {
+        decode_hex(s).unwrap()
+    }
+
+    fn points(hexes: &[&str]) -> Vec<Secp256k1Point> {
+        hexes.iter().map(|h| cpoint(&hex(h)).unwrap()).collect()
+    }
+
+    fn private_key(s: &str) -> Secp256k1PrivateKey {
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&hex(s));
+        new_secp_256k1privatekey(new_secp256k1scalarelement_from_bytes_be(&secret))
+    }
+
+    #[test]
+    fn test_key_agg_vectors() {
+        let keys = points(&[
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+        ]);
+        let cases: [(&[usize], &str); 4] = [
+            (&[0, 1, 2], "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"),
+            (&[2, 1, 0], "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B"),
+            (&[0, 0, 0], "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935"),
+            (&[0, 0, 1, 1], "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E"),
+        ];
+        for (indices, expected) in cases.iter() {
+            let pubkeys: Vec<Secp256k1Point> = indices.iter().map(|i| keys[*i].clone()).collect();
+            let ctx = musig2_key_agg(&pubkeys).unwrap();
+            assert_eq!(ctx.aggregate_key().x_only(), hex(expected));
+        }
+
+        // 曲線上に無い点・x >= p・不正なプレフィックスは公開鍵として読めない
+        assert!(cpoint(&hex("020000000000000000000000000000000000000000000000000000000000000005")).is_none());
+        assert!(cpoint(&hex("02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30")).is_none());
+        assert!(cpoint(&hex("04F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9")).is_none());
+        // tweakがn以上、tweakの結果が無限遠点になる場合は失敗する
+        let ctx = musig2_key_agg(&keys[..2]).unwrap();
+        assert!(ctx
+            .apply_tweak(&hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141"), true)
+            .is_none());
+        let ctx = musig2_key_agg(&points(&["03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9"]))
+            .unwrap();
+        assert!(ctx
+            .apply_tweak(&hex("252E4BD67410A76CDF933D30EAA1608214037F1B105A013ECCD3C5C184A6110B"), false)
+            .is_none());
+    }
+
+    #[test]
+    fn test_key_sort() {
+        let keys = points(&[
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+        ]);
+        let sorted = musig2_key_sort(&keys);
+        assert_eq!(sorted, vec![
+            keys[3].clone(),
+            keys[4].clone(),
+            keys[0].clone(),
+            keys[1].clone(),
+            keys[2].clone(),
+        ]);
+    }
+
+    #[test]
+    fn test_nonce_gen_vectors() {
+        let mut rand = [0u8; 32];
+        rand.copy_from_slice(&hex("0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F0F"));
+
+        let secret = private_key("0202020202020202020202020202020202020202020202020202020202020202");
+        let pk = cpoint(&hex("024D4B6CD1361032CA9BD2AEB9D900AA4D45D9EAD80AC9423374C451A7254D0766")).unwrap();
+        assert_eq!(secret.point, pk);
+        let (secnonce, pubnonce) = musig2_nonce_gen(
+            &rand,
+            &pk,
+            Some(&secret),
+            Some(&hex("0707070707070707070707070707070707070707070707070707070707070707")),
+            Some(&hex("0101010101010101010101010101010101010101010101010101010101010101")),
+            Some(&hex("0808080808080808080808080808080808080808080808080808080808080808")),
+        )
+        .unwrap();
+        let mut serialized = secnonce.k1.value.to_be_bytes().to_vec();
+        serialized.extend_from_slice(&secnonce.k2.value.to_be_bytes());
+        serialized.extend_from_slice(&secnonce.pk);
+        assert_eq!(serialized, hex("B114E502BEAA4E301DD08A50264172C84E41650E6CB726B410C0694D59EFFB6495B5CAF28D045B973D63E3C99A44B807BDE375FD6CB39E46DC4A511708D0E9D2024D4B6CD1361032CA9BD2AEB9D900AA4D45D9EAD80AC9423374C451A7254D0766"));
+        assert_eq!(pubnonce.serialize(), hex("02F7BE7089E8376EB355272368766B17E88E7DB72047D05E56AA881EA52B3B35DF02C29C8046FDD0DED4C7E55869137200FBDBFE2EB654267B6D7013602CAED3115A"));
+
+        // 秘密鍵・集約公開鍵・メッセージ・追加の入力が無い場合
+        let pk = cpoint(&hex("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9")).unwrap();
+        let (secnonce, pubnonce) = musig2_nonce_gen(&rand, &pk, None, None, None, None).unwrap();
+        let mut serialized = secnonce.k1.value.to_be_bytes().to_vec();
+        serialized.extend_from_slice(&secnonce.k2.value.to_be_bytes());
+        serialized.extend_from_slice(&secnonce.pk);
+        assert_eq!(serialized, hex("89BDD787D0284E5E4D5FC572E49E316BAB7E21E3B1830DE37DFE80156FA41A6D0B17AE8D024C53679699A6FD7944D9C4A366B514BAF43088E0708B1023DD289702F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"));
+        assert_eq!(pubnonce.serialize(), hex("02C96E7CB1E8AA5DAC64D872947914198F607D90ECDE5200DE52978AD5DED63C000299EC5117C2D29EDEE8A2092587C3909BE694D5CFF0667D6C02EA4059F7CD9786"));
+    }
+
+    #[test]
+    fn test_nonce_agg_vectors() {
+        let nonces = [
+            "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E66603BA47FBC1834437B3212E89A84D8425E7BF12E0245D98262268EBDCB385D50641",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B833",
+            "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E6660279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60379BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        ];
+        let nonces: Vec<MuSig2PubNonce> = nonces.iter().map(|n| MuSig2PubNonce::parse(&hex(n)).unwrap()).collect();
+        assert_eq!(
+            musig2_nonce_agg(&nonces[..2]).serialize(),
+            hex("035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B024725377345BDE0E9C33AF3C43C0A29A9249F2F2956FA8CFEB55C8573D0262DC8")
+        );
+        // R2の和が無限遠点になる
+        let aggnonce = musig2_nonce_agg(&nonces[2..]);
+        assert_eq!(
+            aggnonce.serialize(),
+            hex("035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B000000000000000000000000000000000000000000000000000000000000000000")
+        );
+        assert_eq!(MuSig2AggNonce::parse(&aggnonce.serialize()), Some(aggnonce));
+
+        // 不正なプレフィックス・曲線上に無い点・x >= p
+        for invalid in [
+            "04FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B833",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B831",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A602FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+        ]
+        .iter()
+        {
+            assert!(MuSig2PubNonce::parse(&hex(invalid)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_sign_verify_vectors() {
+        let secret = private_key("7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671");
+        let keys = points(&[
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+        ]);
+        assert_eq!(secret.point, keys[0]);
+        let secnonce = "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F703935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9";
+        let pubnonces: Vec<MuSig2PubNonce> = [
+            "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+            "0237C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0387BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+        ]
+        .iter()
+        .map(|n| MuSig2PubNonce::parse(&hex(n)).unwrap())
+        .collect();
+        let aggnonces = [
+            "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9",
+            "000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        ];
+        assert_eq!(musig2_nonce_agg(&pubnonces[..3]).serialize(), hex(aggnonces[0]));
+        assert_eq!(
+            musig2_nonce_agg(&[pubnonces[0].clone(), pubnonces[3].clone()]).serialize(),
+            hex(aggnonces[1])
+        );
+        let msg = hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+
+        // (公開鍵, 集約ノンス, 期待する部分署名)
+        let cases: [(&[usize], usize, &str); 4] = [
+            (&[0, 1, 2], 0, "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB"),
+            (&[1, 0, 2], 0, "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52"),
+            (&[1, 2, 0], 0, "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900"),
+            // 集約ノンスが無限遠点の場合
+            (&[0, 1], 1, "AE386064B26105404798F75DE2EB9AF5EDA5387B064B83D049CB7C5E08879531"),
+        ];
+        for (indices, aggnonce_index, expected) in cases.iter() {
+            let pubkeys: Vec<Secp256k1Point> = indices.iter().map(|i| keys[*i].clone()).collect();
+            let ctx = musig2_key_agg(&pubkeys).unwrap();
+            let aggnonce = MuSig2AggNonce::parse(&hex(aggnonces[*aggnonce_index])).unwrap();
+            let session = new_musig2session(&ctx, &aggnonce, &msg);
+            let psig = session
+                .partial_sign(MuSig2SecNonce::parse(&hex(secnonce)).unwrap(), &secret)
+                .unwrap();
+            assert_eq!(psig.serialize(), hex(expected));
+            assert!(session.partial_sig_verify(&psig, &pubnonces[0], &keys[0]));
+        }
+
+        let ctx = musig2_key_agg(&keys).unwrap();
+        let aggnonce = MuSig2AggNonce::parse(&hex(aggnonces[0])).unwrap();
+        let session = new_musig2session(&ctx, &aggnonce, &msg);
+        // 署名者の公開鍵が鍵集約に含まれていない
+        let other = new_musig2session(&musig2_key_agg(&keys[1..]).unwrap(), &aggnonce, &msg);
+        assert!(other
+            .partial_sign(MuSig2SecNonce::parse(&hex(secnonce)).unwrap(), &secret)
+            .is_none());
+        // 秘密ノンスが0
+        assert!(MuSig2SecNonce::parse(&[0u8; 97]).is_none());
+        // 不正な集約ノンス
+        for invalid in [
+            "048465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9",
+            "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61020000000000000000000000000000000000000000000000000000000000000009",
+            "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD6102FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+        ]
+        .iter()
+        {
+            assert!(MuSig2AggNonce::parse(&hex(invalid)).is_none());
+        }
+
+        // 符号を反転した部分署名・別の署名者として検証・n以上の部分署名
+        let negated = MuSig2PartialSignature::parse(&hex(
+            "FED54434AD4CFE953FC527DC6A5E5BE8F6234907B7C187559557CE87A0541C46",
+        ))
+        .unwrap();
+        assert!(!session.partial_sig_verify(&negated, &pubnonces[0], &keys[0]));
+        let psig = MuSig2PartialSignature::parse(&hex(
+            "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+        ))
+        .unwrap();
+        assert!(session.partial_sig_verify(&psig, &pubnonces[0], &keys[0]));
+        assert!(!session.partial_sig_verify(&psig, &pubnonces[1], &keys[1]));
+        assert!(MuSig2PartialSignature::parse(&hex(
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141"
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn test_tweak_vectors() {
+        let secret = private_key("7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671");
+        let secnonce = "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F703935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9";
+        let aggnonce = MuSig2AggNonce::parse(&hex("028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9")).unwrap();
+        let msg = hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+        let keys = points(&[
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+        ]);
+        let pubnonce = MuSig2PubNonce::parse(&hex("0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480")).unwrap();
+        let tweaks = [
+            "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+            "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+            "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+            "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+        ];
+        // (tweak, x-onlyかどうか, 期待する部分署名)
+        let cases: [(&[usize], &[bool], &str); 5] = [
+            (&[0], &[true], "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91"),
+            (&[0], &[false], "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D"),
+            (&[0, 1], &[false, true], "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408"),
+            (&[0, 1, 2, 3], &[false, false, true, true], "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435"),
+            (&[0, 1, 2, 3], &[true, false, true, false], "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239"),
+        ];
+        for (indices, xonly, expected) in cases.iter() {
+            let mut ctx = musig2_key_agg(&keys).unwrap();
+            for (i, is_xonly) in indices.iter().zip(xonly.iter()) {
+                ctx = ctx.apply_tweak(&hex(tweaks[*i]), *is_xonly).unwrap();
+            }
+            let session = new_musig2session(&ctx, &aggnonce, &msg);
+            let psig = session
+                .partial_sign(MuSig2SecNonce::parse(&hex(secnonce)).unwrap(), &secret)
+                .unwrap();
+            assert_eq!(psig.serialize(), hex(expected));
+            assert!(session.partial_sig_verify(&psig, &pubnonce, &secret.point));
+        }
+    }
+
+    #[test]
+    fn test_sig_agg_vectors() {
+        let keys = points(&[
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02D2DC6F5DF7C56ACF38C7FA0AE7A759AE30E19B37359DFDE015872324C7EF6E05",
+            "03C7FB101D97FF930ACD0C6760852EF64E69083DE0B06AC6335724754BB4B0522C",
+            "02352433B21E7E05D3B452B81CAE566E06D2E003ECE16D1074AABA4289E0E3D581",
+        ]);
+        let tweaks = [
+            "B511DA492182A91B0FFB9A98020D55F260AE86D7ECBD0399C7383D59A5F2AF7C",
+            "A815FE049EE3C5AAB66310477FBC8BCCCAC2F3395F59F921C364ACD78A2F48DC",
+            "75448A87274B056468B977BE06EB1E9F657577B7320B0A3376EA51FD420D18A8",
+        ];
+        let psigs = [
+            "B15D2CD3C3D22B04DAE438CE653F6B4ECF042F42CFDED7C41B64AAF9B4AF53FB",
+            "6193D6AC61B354E9105BBDC8937A3454A6D705B6D57322A5A472A02CE99FCB64",
+            "9A87D3B79EC67228CB97878B76049B15DBD05B8158D17B5B9114D3C226887505",
+            "66F82EA90923689B855D36C6B7E032FB9970301481B99E01CDB4D6AC7C347A15",
+            "4F5AEE41510848A6447DCD1BBC78457EF69024944C87F40250D3EF2C25D33EFE",
+            "DDEF427BBB847CC027BEFF4EDB01038148917832253EBC355FC33F4A8E2FCCE4",
+            "97B890A26C981DA8102D3BC294159D171D72810FDF7C6A691DEF02F0F7AF3FDC",
+            "53FA9E08BA5243CBCB0D797C5EE83BC6728E539EB76C2D0BF0F971EE4E909971",
+        ];
+        let msg = hex("599C67EA410D005B9DA90817CF03ED3B1C868E4DA4EDF00A5880B0082C237869");
+        // (公開鍵, tweak, x-onlyかどうか, 集約ノンス, 部分署名, 期待する署名)
+        type Case = (&'static [usize], &'static [usize], &'static [bool], &'static str, &'static [usize], &'static str);
+        let cases: [Case; 4] = [
+            (&[0, 1], &[], &[], "0341432722C5CD0268D829C702CF0D1CBCE57033EED201FD335191385227C3210C03D377F2D258B64AADC0E16F26462323D701D286046A2EA93365656AFD9875982B", &[0, 1], "041DA22223CE65C92C9A0D6C2CAC828AAF1EEE56304FEC371DDF91EBB2B9EF0912F1038025857FEDEB3FF696F8B99FA4BB2C5812F6095A2E0004EC99CE18DE1E"),
+            (&[0, 2], &[], &[], "0224AFD36C902084058B51B5D36676BBA4DC97C775873768E58822F87FE437D792028CB15929099EEE2F5DAE404CD39357591BA32E9AF4E162B8D3E7CB5EFE31CB20", &[2, 3], "1069B67EC3D2F3C7C08291ACCB17A9C9B8F2819A52EB5DF8726E17E7D6B52E9F01800260A7E9DAC450F4BE522DE4CE12BA91AEAF2B4279219EF74BE1D286ADD9"),
+            (&[0, 2], &[0], &[false], "0208C5C438C710F4F96A61E9FF3C37758814B8C3AE12BFEA0ED2C87FF6954FF186020B1816EA104B4FCA2D304D733E0E19CEAD51303FF6420BFD222335CAA402916D", &[4, 5], "5C558E1DCADE86DA0B2F02626A512E30A22CF5255CAEA7EE32C38E9A71A0E9148BA6C0E6EC7683B64220F0298696F1B878CD47B107B81F7188812D593971E0CC"),
+            (&[0, 3], &[0, 1, 2], &[true, false, true], "02B5AD07AFCD99B6D92CB433FBD2A28FDEB98EAE2EB09B6014EF0F8197CD58403302E8616910F9293CF692C49F351DB86B25E352901F0E237BAFDA11F1C1CEF29FFD", &[6, 7], "839B08820B681DBA8DAF4CC7B104E8F2638F9388F8D7A555DC17B6E6971D7426CE07BF6AB01F1DB50E4E33719295F4094572B79868E440FB3DEFD3FAC1DB589E"),
+        ];
+        for (key_indices, tweak_indices, xonly, aggnonce, psig_indices, expected) in cases.iter() {
+            let pubkeys: Vec<Secp256k1Point> = key_indices.iter().map(|i| keys[*i].clone()).collect();
+            let mut ctx = musig2_key_agg(&pubkeys).unwrap();
+            for (i, is_xonly) in tweak_indices.iter().zip(xonly.iter()) {
+                ctx = ctx.apply_tweak(&hex(tweaks[*i]), *is_xonly).unwrap();
+            }
+            let session = new_musig2session(&ctx, &MuSig2AggNonce::parse(&hex(aggnonce)).unwrap(), &msg);
+            let psigs: Vec<MuSig2PartialSignature> = psig_indices
+                .iter()
+                .map(|i| MuSig2PartialSignature::parse(&hex(psigs[*i])).unwrap())
+                .collect();
+            let sig = session.partial_sig_agg(&psigs);
+            assert_eq!(sig.serialize(), hex(expected));
+            assert!(ctx.aggregate_key().verify_schnorr(&msg, &sig));
+        }
+    }
+
+    #[test]
+    fn test_full_signing_session() {
+        let msg = [0x42u8; 32];
+        for n in [1usize, 2, 3].iter() {
+            let keys: Vec<Secp256k1PrivateKey> = (0..*n)
+                .map(|i| new_secp_256k1privatekey_from_i32(1000 + i as i32))
+                .collect();
+            let pubkeys = musig2_key_sort(&keys.iter().map(|k| k.point.clone()).collect::<Vec<_>>());
+            // BIP341のように、集約公開鍵をtaprootの出力鍵にtweakして使う
+            for tweak in [None, Some([0x11u8; 32])].iter() {
+                let mut ctx = musig2_key_agg(&pubkeys).unwrap();
+                if let Some(tweak) = tweak {
+                    ctx = ctx.apply_tweak(tweak, true).unwrap();
+                }
+                // 1ラウンド目: 各自がノンスを生成して公開ノンスを交換する
+                let mut secnonces = vec![];
+                let mut pubnonces = vec![];
+                for (i, key) in keys.iter().enumerate() {
+                    let rand = [i as u8 + 1; 32];
+                    let (secnonce, pubnonce) = musig2_nonce_gen(
+                        &rand,
+                        &key.point,
+                        Some(key),
+                        Some(&ctx.aggregate_key().x_only()),
+                        Some(&msg),
+                        None,
+                    )
+                    .unwrap();
+                    secnonces.push(secnonce);
+                    pubnonces.push(pubnonce);
+                }
+                let aggnonce = musig2_nonce_agg(&pubnonces);
+                // 2ラウンド目: 部分署名を交換し、検証してから集約する
+                let session = new_musig2session(&ctx, &aggnonce, &msg);
+                let mut psigs = vec![];
+                for ((key, secnonce), pubnonce) in keys.iter().zip(secnonces).zip(pubnonces.iter()) {
+                    let psig = session.partial_sign(secnonce, key).unwrap();
+                    assert!(session.partial_sig_verify(&psig, pubnonce, &key.point));
+                    psigs.push(psig);
+                }
+                let sig = session.partial_sig_agg(&psigs);
+                assert!(ctx.aggregate_key().verify_schnorr(&msg, &sig));
+                assert!(!ctx.aggregate_key().verify_schnorr(&[0x43u8; 32], &sig));
+                if *n > 1 {
+                    // 1人分の部分署名が欠けると正しい署名にならない
+                    let partial = session.partial_sig_agg(&psigs[1..]);
+                    assert!(!ctx.aggregate_key().verify_schnorr(&msg, &partial));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// アドレスのプレフィックスやHRPを決めるためのネットワークの種類
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl Network {
+    // BIP173のHRP。signetはtestnetと同じ"tb"を使う
+    pub fn bech32_hrp(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+
+    // HRPからネットワークを決める。"tb"はtestnetとsignetで区別できないのでTestnetにする
+    #[allow(dead_code)]
+    pub fn from_bech32_hrp(hrp: &str) -> Option<Network> {
+        match hrp {
+            "bc" => Some(Network::Mainnet),
+            "tb" => Some(Network::Testnet),
+            "bcrt" => Some(Network::Regtest),
+            _ => None,
+        }
+    }
+
+    // mainnet以外はtestnetと同じプレフィックスを使う
+    #[allow(dead_code)]
+    pub fn is_testnet(&self) -> bool {
+        *self != Network::Mainnet
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ecc::weierstrass::{new_weierstrass_point_unchecked, PrimeField, WeierstrassCurve, WeierstrassPoint};
+use num_bigint::BigUint;
+use num_traits::{Num, Zero};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Sub};
+
+// NIST P-256 (secp256r1)。
+// pは2^256 - cの形ではないのでU256のModulusは使えず、BigUintで計算する
+const P256_P: &str = "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff";
+const P256_B: &str = "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b";
+const P256_GX: &str = "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+const P256_GY: &str = "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+#[allow(dead_code)]
+pub const P256_N: &str = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+
+fn prime() -> BigUint {
+    BigUint::from_str_radix(P256_P, 16).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct P256Element {
+    // 常に [0, p) に収まっている
+    pub(crate) num: BigUint,
+}
+
+impl Add for P256Element {
+    type Output = P256Element;
+
+    fn add(self, rhs: P256Element) -> P256Element {
+        new_p256element(self.num + rhs.num)
+    }
+}
+
+impl Sub for P256Element {
+    type Output = P256Element;
+
+    fn sub(self, rhs: P256Element) -> P256Element {
+        new_p256element(self.num + prime() - rhs.num)
+    }
+}
+
+impl Mul for P256Element {
+    type Output = P256Element;
+
+    fn mul(self, rhs: P256Element) -> P256Element {
+        new_p256element(self.num * rhs.num)
+    }
+}
+
+// フェルマーの小定理からインバースを実装する
+impl PrimeField for P256Element {
+    fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+    fn inv(&self) -> P256Element {
+        let p = prime();
+        new_p256element(self.num.modpow(&(p.clone() - BigUint::from(2u64)), &p))
+    }
+}
+
+impl Display for P256Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.num)
+    }
+}
+
+pub fn new_p256element(num: BigUint) -> P256Element {
+    P256Element { num: num % prime() }
+}
+
+// y^2 = x^3 - 3x + b
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct P256Curve {}
+
+impl WeierstrassCurve for P256Curve {
+    type Field = P256Element;
+
+    fn a(&self) -> P256Element {
+        new_p256element(prime() - BigUint::from(3u64))
+    }
+    fn b(&self) -> P256Element {
+        new_p256element(BigUint::from_str_radix(P256_B, 16).unwrap())
+    }
+    fn element(&self, v: u64) -> P256Element {
+        new_p256element(BigUint::from(v))
+    }
+}
+
+pub type P256Point = WeierstrassPoint<P256Curve>;
+
+#[allow(dead_code)]
+pub fn new_p256point_g() -> P256Point {
+    new_weierstrass_point_unchecked(
+        P256Curve {},
+        new_p256element(BigUint::from_str_radix(P256_GX, 16).unwrap()),
+        new_p256element(BigUint::from_str_radix(P256_GY, 16).unwrap()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::weierstrass::new_weierstrass_point;
+
+    #[test]
+    fn test_p256_generator() {
+        let g = new_p256point_g();
+        assert!(g.is_on_curve());
+
+        let x = BigUint::from_str_radix("7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978", 16).unwrap();
+        let y = BigUint::from_str_radix("07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1", 16).unwrap();
+        let expected = new_weierstrass_point(P256Curve {}, new_p256element(x), new_p256element(y)).unwrap();
+        assert_eq!(g.double(), expected);
+        assert_eq!(g.clone() + g.clone(), expected);
+        assert_eq!(g.mul(2), expected);
+
+        // nG は無限遠点、(n-1)G は -G
+        let n = BigUint::from_str_radix(P256_N, 16).unwrap();
+        assert!(g.mul_big_uint(&n).is_infinity());
+        assert_eq!(g.mul_big_uint(&(n - BigUint::from(1u64))), g.neg());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::ecc::secp256k1_ecmult::multi_mul;
+use crate::ecc::secp256k1_jacobian_point::Secp256k1JacobianPoint;
+use crate::ecc::secp256k1_point::{new_secp256k1point_g, Secp256k1Point};
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, new_secp256k1scalarelement_from_u64,
+    Secp256k1ScalarElement,
+};
+use crate::ecc::secp256k1_signature::Secp256k1Signature;
+use std::thread;
+
+// 検証する (公開鍵, 署名ハッシュz, 署名) の組
+#[derive(Clone)]
+pub struct Secp256k1VerifyItem {
+    pub(crate) point: Secp256k1Point,
+    pub(crate) z: Secp256k1ScalarElement,
+    pub(crate) sig: Secp256k1Signature,
+}
+
+pub fn new_secp256k1verifyitem(
+    point: Secp256k1Point,
+    z: Secp256k1ScalarElement,
+    sig: Secp256k1Signature,
+) -> Secp256k1VerifyItem {
+    Secp256k1VerifyItem { point, z, sig }
+}
+
+// 全ての署名が正しければOk、そうでなければ失敗した署名の添字をErrで返す。
+// sの逆元と、u*G + v*P をアフィン座標に戻すための逆元はそれぞれMontgomeryのトリックで1回にまとめる。
+#[allow(dead_code)]
+pub fn batch_verify(items: &[Secp256k1VerifyItem]) -> Result<(), Vec<usize>> {
+    let one = new_secp256k1scalarelement_from_u64(1);
+    let mut failures: Vec<usize> = vec![];
+    // 検証するまでもなく不正なものは先に弾き、逆元の計算には1を代わりに入れておく
+    let mut valid = vec![true; items.len()];
+    let mut s_values: Vec<Secp256k1ScalarElement> = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        if item.point.is_infinity() || item.sig.r.is_zero() || item.sig.s.is_zero() {
+            valid[i] = false;
+            s_values.push(one.clone());
+        } else {
+            s_values.push(item.sig.s.clone());
+        }
+    }
+    let s_inv = batch_inv(&s_values);
+
+    let g = Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g());
+    let mut r_points: Vec<Secp256k1JacobianPoint> = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        if !valid[i] {
+            r_points.push(Secp256k1JacobianPoint::infinity());
+            continue;
+        }
+        let u = item.z.clone() * s_inv[i].clone();
+        let v = item.sig.r.clone() * s_inv[i].clone();
+        let p = Secp256k1JacobianPoint::from_affine(&item.point);
+        r_points.push(multi_mul(&[g, p], &[u.value, v.value]));
+    }
+    let r_points = Secp256k1JacobianPoint::batch_to_affine(&r_points);
+
+    for (i, item) in items.iter().enumerate() {
+        if !valid[i] || r_points[i].is_infinity() {
+            failures.push(i);
+            continue;
+        }
+        if new_secp256k1scalarelement_from_u256(r_points[i].x.value) != item.sig.r {
+            failures.push(i);
+        }
+    }
+    if failures.is_empty() {
+        return Ok(());
+    }
+    Err(failures)
+}
+
+// itemsをthreads個に分けて並列に検証する
+#[allow(dead_code)]
+pub fn batch_verify_parallel(
+    items: &[Secp256k1VerifyItem],
+    threads: usize,
+) -> Result<(), Vec<usize>> {
+    if threads <= 1 || items.len() <= 1 {
+        return batch_verify(items);
+    }
+    let chunk_size = items.len().div_ceil(threads);
+    let mut failures: Vec<usize> = vec![];
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || batch_verify(chunk)))
+            .collect();
+        for (n, handle) in handles.into_iter().enumerate() {
+            if let Err(indices) = handle.join().unwrap() {
+                failures.extend(indices.iter().map(|i| i + n * chunk_size));
+            }
+        }
+    });
+    if failures.is_empty() {
+        return Ok(());
+    }
+    Err(failures)
+}
+
+// 全要素の逆元を、逆元の計算1回と乗算約3回/要素で求める。要素に0が含まれていてはいけない
+fn batch_inv(values: &[Secp256k1ScalarElement]) -> Vec<Secp256k1ScalarElement> {
+    let mut prefix: Vec<Secp256k1ScalarElement> = Vec::with_capacity(values.len());
+    let mut acc = new_secp256k1scalarelement_from_u64(1);
+    for v in values {
+        acc = acc * v.clone();
+        prefix.push(acc.clone());
+    }
+    let mut inv = acc.inv();
+    let mut result = vec![new_secp256k1scalarelement_from_u64(0); values.len()];
+    for i in (0..values.len()).rev() {
+        if i == 0 {
+            result[0] = inv.clone();
+            break;
+        }
+        result[i] = inv.clone() * prefix[i - 1].clone();
+        inv = inv * values[i].clone();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey_from_i32;
+
+    fn items(count: usize) -> Vec<Secp256k1VerifyItem> {
+        (0..count)
+            .map(|i| {
+                let key = new_secp_256k1privatekey_from_i32(1000 + i as i32);
+                let z = new_secp256k1scalarelement_from_u64(0xabcdef + i as u64);
+                let sig = key.clone().sign(z.clone());
+                new_secp256k1verifyitem(key.point.clone(), z, sig)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_inv() {
+        let values: Vec<Secp256k1ScalarElement> = (1..10)
+            .map(|i| new_secp256k1scalarelement_from_u64(i * 12345))
+            .collect();
+        for (v, inv) in values.iter().zip(batch_inv(&values).iter()) {
+            assert_eq!(v.clone().inv(), *inv);
+        }
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let mut items = items(8);
+        assert_eq!(batch_verify(&items), Ok(()));
+        assert_eq!(batch_verify_parallel(&items, 3), Ok(()));
+        assert_eq!(batch_verify(&[]), Ok(()));
+        // zをずらす・別の公開鍵にする・sを0にする
+        items[1].z = new_secp256k1scalarelement_from_u64(1);
+        items[4].point = items[5].point.clone();
+        items[6].sig.s = new_secp256k1scalarelement_from_u64(0);
+        assert_eq!(batch_verify(&items), Err(vec![1, 4, 6]));
+        assert_eq!(batch_verify_parallel(&items, 3), Err(vec![1, 4, 6]));
+        for (i, item) in items.iter().enumerate() {
+            let single = item.point.clone().verify(item.z.clone(), item.sig.clone());
+            assert_eq!(single, ![1, 4, 6].contains(&i));
+        }
+    }
+}
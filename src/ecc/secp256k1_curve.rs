@@ -1,6 +1,9 @@
+use crate::ecc::secp256k1_field::{new_secp256k1element_from_u256, Secp256k1Element};
+use crate::ecc::u256::U256;
+use crate::ecc::weierstrass::WeierstrassCurve;
 use num_bigint::BigUint;
 
-#[derive(Debug, Clone,Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Secp256k1Curve {
  }
 
@@ -15,6 +18,21 @@ impl Secp256k1Curve {
     }
 }
 
+// y^2 = x^3 + 7
+impl WeierstrassCurve for Secp256k1Curve {
+    type Field = Secp256k1Element;
+
+    fn a(&self) -> Secp256k1Element {
+        self.element(0)
+    }
+    fn b(&self) -> Secp256k1Element {
+        self.element(7)
+    }
+    fn element(&self, v: u64) -> Secp256k1Element {
+        new_secp256k1element_from_u256(U256::from_u64(v))
+    }
+}
+
 pub fn new_secp256k1curve() -> Secp256k1Curve {
     return Secp256k1Curve{}
 }
@@ -0,0 +1,344 @@
+use crate::ecc::secp256k1_field::new_secp256k1element_from_i32;
+use crate::ecc::secp256k1_jacobian_point::Secp256k1JacobianPoint;
+use crate::ecc::secp256k1_point::{new_secp256k1point_g, Secp256k1Point};
+use crate::ecc::secp256k1_projective_point::Secp256k1ProjectivePoint;
+use crate::ecc::u256::{ct_eq_mask, U256};
+use once_cell::sync::Lazy;
+
+// 生成元Gのテーブルのウィンドウ幅(bit)
+const G_WINDOW: usize = 4;
+const G_WINDOWS: usize = 256 / G_WINDOW;
+const G_TABLE_SIZE: usize = (1 << G_WINDOW) - 1;
+
+// 任意の点に対するwNAFのウィンドウ幅
+const WNAF_WINDOW: usize = 5;
+
+// G_TABLE[i][j - 1] = j * 16^i * G (アフィン座標)
+// k*G は k の4bitずつを添字にしてテーブルを引いて足すだけで求まり、2倍算が不要になる。
+static G_TABLE: Lazy<Vec<Vec<Secp256k1Point>>> = Lazy::new(|| {
+    let mut jacobian: Vec<Secp256k1JacobianPoint> = Vec::with_capacity(G_WINDOWS * G_TABLE_SIZE);
+    let mut base = Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g());
+    for _ in 0..G_WINDOWS {
+        let mut p = base;
+        for _ in 0..G_TABLE_SIZE {
+            jacobian.push(p);
+            p = p.add(&base);
+        }
+        // 次のウィンドウは 16 倍した点から始まる
+        base = p;
+    }
+    let affine = Secp256k1JacobianPoint::batch_to_affine(&jacobian);
+    affine
+        .chunks(G_TABLE_SIZE)
+        .map(|chunk| chunk.to_vec())
+        .collect()
+});
+
+// k * G を事前計算テーブルで求める
+pub(crate) fn mul_g(k: &U256) -> Secp256k1JacobianPoint {
+    let mut result = Secp256k1JacobianPoint::infinity();
+    for i in 0..G_WINDOWS {
+        let digit = k.bits_at(i * G_WINDOW, G_WINDOW) as usize;
+        if digit != 0 {
+            result = result.add_affine(&G_TABLE[i][digit - 1]);
+        }
+    }
+    result
+}
+
+// 秘密のkに対する k * G。
+// テーブルの全要素を舐めて分岐無しで選び、完全加算公式で足すので、kの値によって処理時間が変わらない。
+pub(crate) fn mul_g_ct(k: &U256) -> Secp256k1ProjectivePoint {
+    let one = new_secp256k1element_from_i32(1);
+    let mut result = Secp256k1ProjectivePoint::identity();
+    for i in 0..G_WINDOWS {
+        let digit = k.bits_at(i * G_WINDOW, G_WINDOW);
+        // digitが0の時は無限遠点を足す
+        let mut entry = Secp256k1ProjectivePoint::identity();
+        for (j, p) in G_TABLE[i].iter().enumerate() {
+            let mask = ct_eq_mask(digit, (j + 1) as u64);
+            let candidate = Secp256k1ProjectivePoint {
+                x: p.x,
+                y: p.y,
+                z: one,
+            };
+            entry = Secp256k1ProjectivePoint::select(mask, &candidate, &entry);
+        }
+        result = result.add(&entry);
+    }
+    result
+}
+
+// 秘密のkに対する任意の点のスカラー倍。4bitの固定ウィンドウで上位から計算する
+pub(crate) fn mul_ct(p: &Secp256k1ProjectivePoint, k: &U256) -> Secp256k1ProjectivePoint {
+    // table[j] = j * P (table[0]は無限遠点)
+    let mut table = [Secp256k1ProjectivePoint::identity(); 1 << G_WINDOW];
+    for j in 1..table.len() {
+        table[j] = table[j - 1].add(p);
+    }
+    let mut result = Secp256k1ProjectivePoint::identity();
+    for i in (0..G_WINDOWS).rev() {
+        for _ in 0..G_WINDOW {
+            result = result.double();
+        }
+        let digit = k.bits_at(i * G_WINDOW, G_WINDOW);
+        let mut entry = Secp256k1ProjectivePoint::identity();
+        for (j, candidate) in table.iter().enumerate() {
+            let mask = ct_eq_mask(digit, j as u64);
+            entry = Secp256k1ProjectivePoint::select(mask, candidate, &entry);
+        }
+        result = result.add(&entry);
+    }
+    result
+}
+
+// kのwidth-w NAF表現(下位から順)を返す。
+// 各桁は0か、絶対値が2^(w-1)未満の奇数で、0でない桁の間には少なくともw-1個の0が入る。
+pub(crate) fn wnaf(k: &U256, w: usize) -> Vec<i32> {
+    let mut digits: Vec<i32> = Vec::with_capacity(257);
+    let mut k = *k;
+    let window = 1i64 << w;
+    while !k.is_zero() {
+        if k.is_odd() {
+            let mut d = k.low_bits(w) as i64;
+            if d >= window / 2 {
+                d -= window;
+            }
+            if d > 0 {
+                k = k.overflowing_sub(&U256::from_u64(d as u64)).0;
+            } else {
+                // n < 2^256 - 2^128 なので桁あふれはしない
+                k = k.overflowing_add(&U256::from_u64((-d) as u64)).0;
+            }
+            digits.push(d as i32);
+        } else {
+            digits.push(0);
+        }
+        k = k.shr1();
+    }
+    digits
+}
+
+// P, 3P, 5P, ..., (2^(w-1) - 1)P
+pub(crate) fn odd_multiples(p: &Secp256k1JacobianPoint, w: usize) -> Vec<Secp256k1JacobianPoint> {
+    let size = 1 << (w - 2);
+    let double = p.double();
+    let mut table = Vec::with_capacity(size);
+    table.push(*p);
+    for i in 1..size {
+        let next = table[i - 1].add(&double);
+        table.push(next);
+    }
+    table
+}
+
+// wNAF表現の桁に対応する点を足し引きする
+pub(crate) fn add_wnaf_digit(
+    acc: &Secp256k1JacobianPoint,
+    table: &[Secp256k1JacobianPoint],
+    digit: i32,
+) -> Secp256k1JacobianPoint {
+    if digit > 0 {
+        return acc.add(&table[(digit as usize) / 2]);
+    }
+    if digit < 0 {
+        return acc.add(&table[((-digit) as usize) / 2].neg());
+    }
+    *acc
+}
+
+// 任意の点のスカラー倍をwNAFで求める
+pub(crate) fn mul_wnaf(p: &Secp256k1JacobianPoint, k: &U256) -> Secp256k1JacobianPoint {
+    let table = odd_multiples(p, WNAF_WINDOW);
+    let digits = wnaf(k, WNAF_WINDOW);
+    let mut result = Secp256k1JacobianPoint::infinity();
+    for digit in digits.iter().rev() {
+        result = result.double();
+        result = add_wnaf_digit(&result, &table, *digit);
+    }
+    result
+}
+
+// この数以上の項はPippengerで計算する
+const PIPPENGER_THRESHOLD: usize = 64;
+
+// sum(scalars[i] * points[i]) を求める。
+// 項が少ないときはStrauss(Shamirのトリックの一般化)、多いときはPippengerを使う。
+pub(crate) fn multi_mul(points: &[Secp256k1JacobianPoint], scalars: &[U256]) -> Secp256k1JacobianPoint {
+    assert_eq!(points.len(), scalars.len());
+    if points.len() >= PIPPENGER_THRESHOLD {
+        return pippenger(points, scalars);
+    }
+    strauss(points, scalars)
+}
+
+// 全ての項のwNAFを並べて、2倍算を共有しながら上位の桁から足し込む
+pub(crate) fn strauss(points: &[Secp256k1JacobianPoint], scalars: &[U256]) -> Secp256k1JacobianPoint {
+    let tables: Vec<Vec<Secp256k1JacobianPoint>> = points
+        .iter()
+        .map(|p| odd_multiples(p, WNAF_WINDOW))
+        .collect();
+    let digits: Vec<Vec<i32>> = scalars.iter().map(|k| wnaf(k, WNAF_WINDOW)).collect();
+    let len = digits.iter().map(|d| d.len()).max().unwrap_or(0);
+    let mut result = Secp256k1JacobianPoint::infinity();
+    for i in (0..len).rev() {
+        result = result.double();
+        for (table, d) in tables.iter().zip(digits.iter()) {
+            if i < d.len() {
+                result = add_wnaf_digit(&result, table, d[i]);
+            }
+        }
+    }
+    result
+}
+
+// バケット法。スカラーをcビットずつに区切り、各区間で同じ値を持つ点をバケットにまとめて足す
+pub(crate) fn pippenger(points: &[Secp256k1JacobianPoint], scalars: &[U256]) -> Secp256k1JacobianPoint {
+    let c = pippenger_window(points.len());
+    let affine = Secp256k1JacobianPoint::batch_to_affine(points);
+    let windows = 256_usize.div_ceil(c);
+    let mut result = Secp256k1JacobianPoint::infinity();
+    for w in (0..windows).rev() {
+        for _ in 0..c {
+            result = result.double();
+        }
+        let mut buckets = vec![Secp256k1JacobianPoint::infinity(); (1 << c) - 1];
+        for (p, k) in affine.iter().zip(scalars.iter()) {
+            let width = c.min(256 - w * c);
+            let digit = k.bits_at(w * c, width) as usize;
+            if digit != 0 {
+                buckets[digit - 1] = buckets[digit - 1].add_affine(p);
+            }
+        }
+        // sum(j * bucket[j]) を累積和2回で求める
+        let mut running = Secp256k1JacobianPoint::infinity();
+        let mut sum = Secp256k1JacobianPoint::infinity();
+        for bucket in buckets.iter().rev() {
+            running = running.add(bucket);
+            sum = sum.add(&running);
+        }
+        result = result.add(&sum);
+    }
+    result
+}
+
+// 項数nに対しておおよそ log2(n) - 2 程度のウィンドウ幅が最適になる
+fn pippenger_window(n: usize) -> usize {
+    let log2 = (usize::BITS - n.leading_zeros()) as usize;
+    if log2 < 6 {
+        return 4;
+    }
+    (log2 - 2).min(12)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_scalar_element::{
+        new_secp256k1scalarelement_from_hex_str, SECP256K1_N,
+    };
+    use num_bigint::{BigInt, BigUint};
+    use num_traits::Num;
+
+    fn samples() -> Vec<U256> {
+        let mut v = vec![
+            U256::from_u64(1),
+            U256::from_u64(2),
+            U256::from_u64(0xdeadbeef),
+            SECP256K1_N.overflowing_sub(&U256::ONE).0,
+        ];
+        for hex in [
+            "c7207fee197d27c618aea621406f6bf5ef6fca38681d82b2f06fddbdce6feab6",
+            "8000000000000000000000000000000000000000000000000000000000000000",
+            "ffffffffffffffffffffffffffffffff",
+        ]
+        .iter()
+        {
+            v.push(new_secp256k1scalarelement_from_hex_str(hex).unwrap().value);
+        }
+        v
+    }
+
+    #[test]
+    fn test_wnaf_digits() {
+        for k in samples() {
+            let digits = wnaf(&k, WNAF_WINDOW);
+            let mut sum = BigInt::from(0);
+            for d in digits.iter().rev() {
+                sum = sum * 2 + BigInt::from(*d);
+            }
+            assert_eq!(sum, BigInt::from(k.to_biguint()));
+            let mut last_nonzero: Option<usize> = None;
+            for (i, d) in digits.iter().enumerate() {
+                if *d != 0 {
+                    assert!(d % 2 != 0);
+                    assert!(d.abs() < 1 << (WNAF_WINDOW - 1));
+                    if let Some(last) = last_nonzero {
+                        assert!(i - last >= WNAF_WINDOW);
+                    }
+                    last_nonzero = Some(i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_g_and_wnaf_match_double_and_add() {
+        let g = new_secp256k1point_g();
+        let jg = Secp256k1JacobianPoint::from_affine(&g);
+        let p = jg.mul(&U256::from_u64(12345)).to_affine();
+        let jp = Secp256k1JacobianPoint::from_affine(&p);
+        for k in samples() {
+            assert_eq!(mul_g(&k).to_affine(), jg.mul(&k).to_affine());
+            assert_eq!(mul_wnaf(&jp, &k).to_affine(), jp.mul(&k).to_affine());
+        }
+        assert!(mul_g(&U256::ZERO).is_infinity());
+        assert!(mul_wnaf(&jp, &U256::ZERO).is_infinity());
+        let n_minus_1 = BigUint::from_str_radix(
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364140",
+            16,
+        )
+        .unwrap();
+        // (n - 1) * G = -G
+        assert_eq!(
+            mul_g(&U256::from_biguint(&n_minus_1).unwrap()).to_affine(),
+            jg.neg().to_affine()
+        );
+    }
+
+    #[test]
+    fn test_constant_time_mul_matches() {
+        let jg = Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g());
+        let p = jg.mul(&U256::from_u64(12345));
+        let pp = Secp256k1ProjectivePoint::from_affine(&p.to_affine());
+        let mut scalars = samples();
+        scalars.push(U256::ZERO);
+        for k in scalars {
+            assert_eq!(mul_g_ct(&k).to_affine(), mul_g(&k).to_affine());
+            assert_eq!(mul_ct(&pp, &k).to_affine(), p.mul(&k).to_affine());
+        }
+    }
+
+    #[test]
+    fn test_multi_mul_matches_sum() {
+        let jg = Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g());
+        let scalars = samples();
+        let points: Vec<Secp256k1JacobianPoint> = (0..scalars.len())
+            .map(|i| jg.mul(&U256::from_u64(i as u64 * 7 + 3)))
+            .collect();
+        let mut expected = Secp256k1JacobianPoint::infinity();
+        for (p, k) in points.iter().zip(scalars.iter()) {
+            expected = expected.add(&p.mul(k));
+        }
+        let expected = expected.to_affine();
+        assert_eq!(strauss(&points, &scalars).to_affine(), expected);
+        assert_eq!(pippenger(&points, &scalars).to_affine(), expected);
+        assert_eq!(multi_mul(&points, &scalars).to_affine(), expected);
+        assert!(multi_mul(&[], &[]).is_infinity());
+        // kP + (n-k)P = O
+        let n_minus_k = SECP256K1_N.overflowing_sub(&scalars[2]).0;
+        assert!(strauss(&[points[1], points[1]], &[scalars[2], n_minus_k]).is_infinity());
+        assert!(pippenger(&[points[1], points[1]], &[scalars[2], n_minus_k]).is_infinity());
+    }
+}
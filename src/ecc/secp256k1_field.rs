@@ -1,27 +1,44 @@
 use std::fmt::{Display, Formatter};
 use std::fmt;
-use std::ops::{Sub, Add, Mul, Rem, Div};
+use std::ops::{Sub, Add, Mul, Rem, Div, Neg};
 use num_bigint::BigUint;
-use num_traits::{One, FromPrimitive, Zero, Num};
+use num_traits::{One, FromPrimitive, Num};
+use crate::ecc::u256::{Modulus, U256};
+use crate::ecc::weierstrass::PrimeField;
+
+// p = 2^256 - 2^32 - 977
+pub(crate) const SECP256K1_P: U256 = U256::from_limbs([
+    0xFFFFFFFEFFFFFC2F,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+]);
+const FIELD: Modulus = Modulus::new(SECP256K1_P, U256::from_limbs([0x1000003D1, 0, 0, 0]));
+// (p + 1) / 4
+const SQRT_EXP: U256 = U256::from_limbs([
+    0xFFFFFFFFBFFFFF0C,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+    0x3FFFFFFFFFFFFFFF,
+]);
 
 fn prime() -> BigUint {
-    return BigUint::from(2u32).pow(256)
-        - BigUint::from_u8(2u8).unwrap().pow(32u32)
-        - (BigUint::from(1u8) * 977u32)
+    SECP256K1_P.to_biguint()
 }
 
 
 // Secp256k1Elementと共通化したい・・・
 // Debugの自動実装
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub struct Secp256k1Element {
-    pub num: BigUint,
+    // 常に [0, p) に収まっている
+    pub(crate) value: U256,
 }
 
-// P.5 練習問題1
+// SECP256K1_P.5 練習問題1
 impl PartialEq for Secp256k1Element {
     fn eq(&self, other: &Self) -> bool {
-        return self.num == other.num;
+        self.value == other.value
     }
 }
 
@@ -31,7 +48,7 @@ impl Add for Secp256k1Element {
     // 左側のprimeに依存させる。
     fn add(self, rhs: Secp256k1Element) -> Secp256k1Element {
         Self::Output{
-            num: (self.num+rhs.num) % prime()
+            value: FIELD.add(&self.value, &rhs.value)
         }
     }
 }
@@ -41,7 +58,7 @@ impl Sub for Secp256k1Element {
 
     fn sub(self, rhs: Secp256k1Element) -> Secp256k1Element {
         Self::Output{
-            num: (self.num  + prime() - rhs.num) % prime()
+            value: FIELD.sub(&self.value, &rhs.value)
         }
     }
 }
@@ -51,74 +68,101 @@ impl Mul for Secp256k1Element {
 
     fn mul(self, rhs: Secp256k1Element) -> Secp256k1Element {
         Self::Output{
-            num: (self.num * rhs.num) % prime()
+            value: FIELD.mul(&self.value, &rhs.value)
         }
     }
 }
 
-impl Rem for Secp256k1Element {
+impl Neg for Secp256k1Element {
     type Output = Secp256k1Element;
 
-    fn rem(self, rhs: Secp256k1Element) -> Secp256k1Element {
+    fn neg(self) -> Secp256k1Element {
         Self::Output{
-            num: (self.num % rhs.num) % prime()
+            value: FIELD.neg(&self.value)
         }
     }
 }
 
+impl Rem for Secp256k1Element {
+    type Output = Secp256k1Element;
+
+    fn rem(self, rhs: Secp256k1Element) -> Secp256k1Element {
+        new_secp256k1element(self.num() % rhs.num())
+    }
+}
+
 
 // TODO: どうにかして実装したい。
 impl Secp256k1Element {
 
+    #[allow(dead_code)]
     pub fn prime() -> Secp256k1Element {
-        let p =  BigUint::from(2u32).pow(256)
-            - BigUint::from_u8(2u8).unwrap().pow(32u32)
-            - (BigUint::from(1u8) * 977u32);
-        new_secp256k1element(p)
+        new_secp256k1element(prime())
     }
 
-    fn inner_pow(self,f: Secp256k1Element,exp: BigUint) -> Secp256k1Element {
-        if exp.clone() == BigUint::zero() {
-            return Secp256k1Element{
-                num: One::one(),
-            }
-        }
-        if exp.clone() % BigUint::from(2u32) == BigUint::zero() {
-            return self.inner_pow(Secp256k1Element{
-                num: (f.num.clone() * f.num.clone()) % prime(),
-            },exp.clone() / BigUint::from(2u32));
-        }
-        f.clone() * f.clone().inner_pow(Secp256k1Element{
-            num: (f.num.clone() * f.num.clone()) % prime(),
-        },(exp.clone()-BigUint::one())/BigUint::from(2u32))
+    pub fn num(&self) -> BigUint {
+        self.value.to_biguint()
     }
+
+    #[allow(dead_code)]
     // rem_euclidを使って負数でもよしなに整数値に変更する。
     pub fn pow(self, exp: BigUint) -> Secp256k1Element {
-        self.clone().inner_pow(self, exp % (prime() - BigUint::one()))
+        let exp = exp % (prime() - BigUint::one());
+        let exp = U256::from_biguint(&exp).unwrap();
+        Secp256k1Element{
+            value: FIELD.pow(&self.value, &exp)
+        }
     }
 
     // フェルマーの小定理からインバースを実装する。 位数が素数で無い場合は正しく動作しない
     pub fn inv(self) -> Secp256k1Element {
-        return self.pow(prime() - BigUint::from(2u32))
+        Secp256k1Element{
+            value: FIELD.inv(&self.value)
+        }
     }
 
+    // p ≡ 3 (mod 4) なので (p+1)/4 乗で平方根が求まる
     pub fn sqrt(self) -> Secp256k1Element {
-        let m = prime() + BigUint::one();
-        let d = BigUint::from(4u8);
-        return self.pow(m / d);
+        Secp256k1Element{
+            value: FIELD.pow(&self.value, &SQRT_EXP)
+        }
     }
 
-    #[inline]
-    pub fn to_32_bytes_be(&self) -> Option<Vec<u8>> {
-        let mut bin = self.num.to_bytes_be();
-        if bin.len() > 32 {
-            return None;
+    #[allow(dead_code)]
+    pub fn square(&self) -> Secp256k1Element {
+        Secp256k1Element{
+            value: FIELD.square(&self.value)
         }
-        // FIXME: より効率的に
-        while bin.len() != 32 {
-            bin.insert(0,0u8);
+    }
+
+    #[allow(dead_code)]
+    pub fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+
+    // mask が全ビット1なら a, 0なら b を分岐無しで選ぶ
+    pub fn select(mask: u64, a: &Secp256k1Element, b: &Secp256k1Element) -> Secp256k1Element {
+        Secp256k1Element{
+            value: U256::select(mask, &a.value, &b.value)
         }
-        Some(bin)
+    }
+
+    pub fn is_even(&self) -> bool {
+        !self.value.is_odd()
+    }
+
+    #[inline]
+    pub fn to_32_bytes_be(self) -> Option<Vec<u8>> {
+        Some(self.value.to_be_bytes().to_vec())
+    }
+}
+
+impl PrimeField for Secp256k1Element {
+    fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+    fn inv(&self) -> Secp256k1Element {
+        Secp256k1Element::inv(*self)
     }
 }
 
@@ -132,20 +176,36 @@ impl Div for Secp256k1Element {
 
 impl Display for Secp256k1Element {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f,"{}",self.num)
+        write!(f,"{}",self.num())
     }
 }
 
 #[allow(dead_code)]
 pub fn new_secp256k1element(num: BigUint) -> Secp256k1Element {
     Secp256k1Element{
-        num: num % prime()
+        value: U256::from_biguint(&(num % prime())).unwrap()
     }
 }
+
+// 256bitの値から作る。p以上の値はpで割った余りになる
+#[allow(dead_code)]
+pub fn new_secp256k1element_from_u256(value: U256) -> Secp256k1Element {
+    Secp256k1Element{
+        value: FIELD.reduce(&value)
+    }
+}
+
+// 32byte big endianから作る。p以上の値はpで割った余りになる
+#[allow(dead_code)]
+pub fn new_secp256k1element_from_bytes_be(bytes: &[u8; 32]) -> Secp256k1Element {
+    new_secp256k1element_from_u256(U256::from_be_bytes(bytes))
+}
+
 #[allow(dead_code)]
 pub fn new_secp256k1element_from_i32(num: i32) -> Secp256k1Element {
+    let num = BigUint::from_i32(num).unwrap();
     Secp256k1Element{
-        num: BigUint::from_i32(num).unwrap(),
+        value: U256::from_biguint(&num).unwrap(),
     }
 }
 
@@ -184,5 +244,19 @@ mod tests {
             println!("a % b = {}",a.clone() % b.clone());
         }
     }
+
+    #[test]
+    fn test_secp256k1_field_inv_and_sqrt() {
+        assert_eq!(SQRT_EXP.to_biguint(), (prime() + BigUint::one()) / BigUint::from(4u8));
+        let a = new_secp256k1element_from_hex_str(
+            "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        ).unwrap();
+        assert_eq!(a * a.inv(), new_secp256k1element_from_i32(1));
+        let square = a.square();
+        let root = square.sqrt();
+        assert_eq!(root.square(), square);
+        assert_eq!(-a + a, new_secp256k1element_from_i32(0));
+        assert_eq!(Secp256k1Element::prime() - a, -a);
+    }
 }
 
@@ -0,0 +1,228 @@
+use crate::ecc::secp256k1_field::{new_secp256k1element_from_i32, Secp256k1Element};
+use crate::ecc::secp256k1_point::{
+    new_secp256k1point_from_element, new_secp256k1point_infinity, Secp256k1Point,
+};
+use crate::ecc::u256::U256;
+
+// ヤコビアン座標 (X, Y, Z) で表した点。アフィン座標では (X/Z^2, Y/Z^3) に対応する。
+// 加算・2倍算で逆元を計算しなくて良いので、スカラー倍の途中はこちらで計算する。
+// Z = 0 の時は無限遠点を表す。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Secp256k1JacobianPoint {
+    pub(crate) x: Secp256k1Element,
+    pub(crate) y: Secp256k1Element,
+    pub(crate) z: Secp256k1Element,
+}
+
+impl Secp256k1JacobianPoint {
+    pub fn infinity() -> Secp256k1JacobianPoint {
+        Secp256k1JacobianPoint {
+            x: new_secp256k1element_from_i32(1),
+            y: new_secp256k1element_from_i32(1),
+            z: new_secp256k1element_from_i32(0),
+        }
+    }
+
+    pub fn from_affine(p: &Secp256k1Point) -> Secp256k1JacobianPoint {
+        if p.is_infinity() {
+            return Secp256k1JacobianPoint::infinity();
+        }
+        Secp256k1JacobianPoint {
+            x: p.x,
+            y: p.y,
+            z: new_secp256k1element_from_i32(1),
+        }
+    }
+
+    // 逆元を1回だけ計算してアフィン座標に戻す
+    pub fn to_affine(self) -> Secp256k1Point {
+        if self.is_infinity() {
+            return new_secp256k1point_infinity();
+        }
+        let z_inv = self.z.inv();
+        let z_inv2 = z_inv.square();
+        let z_inv3 = z_inv2 * z_inv;
+        new_secp256k1point_from_element(self.x * z_inv2, self.y * z_inv3)
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    #[allow(dead_code)]
+    pub fn neg(&self) -> Secp256k1JacobianPoint {
+        Secp256k1JacobianPoint {
+            x: self.x,
+            y: -self.y,
+            z: self.z,
+        }
+    }
+
+    // dbl-2009-l (a = 0)
+    pub fn double(&self) -> Secp256k1JacobianPoint {
+        if self.is_infinity() || self.y.is_zero() {
+            return Secp256k1JacobianPoint::infinity();
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let xb = self.x + b;
+        let d = xb.square() - a - c;
+        let d = d + d;
+        let e = a + a + a;
+        let f = e.square();
+        let x3 = f - d - d;
+        let c8 = c + c;
+        let c8 = c8 + c8;
+        let c8 = c8 + c8;
+        let y3 = e * (d - x3) - c8;
+        let yz = self.y * self.z;
+        let z3 = yz + yz;
+        Secp256k1JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    // add-1998-cmo-2
+    pub fn add(&self, rhs: &Secp256k1JacobianPoint) -> Secp256k1JacobianPoint {
+        if self.is_infinity() {
+            return *rhs;
+        }
+        if rhs.is_infinity() {
+            return *self;
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = rhs.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = rhs.x * z1z1;
+        let s1 = self.y * z2z2 * rhs.z;
+        let s2 = rhs.y * z1z1 * self.z;
+        self.add_inner(u1, u2, s1, s2, self.z * rhs.z)
+    }
+
+    // アフィン座標の点(Z=1)との加算。乗算の回数が少なくて済む
+    pub fn add_affine(&self, rhs: &Secp256k1Point) -> Secp256k1JacobianPoint {
+        if rhs.is_infinity() {
+            return *self;
+        }
+        if self.is_infinity() {
+            return Secp256k1JacobianPoint::from_affine(rhs);
+        }
+        let z1z1 = self.z.square();
+        let u2 = rhs.x * z1z1;
+        let s2 = rhs.y * z1z1 * self.z;
+        self.add_inner(self.x, u2, self.y, s2, self.z)
+    }
+
+    fn add_inner(
+        &self,
+        u1: Secp256k1Element,
+        u2: Secp256k1Element,
+        s1: Secp256k1Element,
+        s2: Secp256k1Element,
+        z1z2: Secp256k1Element,
+    ) -> Secp256k1JacobianPoint {
+        let h = u2 - u1;
+        let r = s2 - s1;
+        if h.is_zero() {
+            // x座標が一致する場合は同じ点(2倍算)か、加法逆元(無限遠点)
+            if r.is_zero() {
+                return self.double();
+            }
+            return Secp256k1JacobianPoint::infinity();
+        }
+        let h2 = h.square();
+        let h3 = h2 * h;
+        let u1h2 = u1 * h2;
+        let x3 = r.square() - h3 - u1h2 - u1h2;
+        let y3 = r * (u1h2 - x3) - s1 * h3;
+        let z3 = h * z1z2;
+        Secp256k1JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    // 複数の点をまとめてアフィン座標に戻す。
+    // Montgomeryのトリックで逆元の計算を1回にまとめる。
+    pub fn batch_to_affine(points: &[Secp256k1JacobianPoint]) -> Vec<Secp256k1Point> {
+        let one = new_secp256k1element_from_i32(1);
+        // prefix[i] = z_0 * z_1 * ... * z_i (無限遠点は1として扱う)
+        let mut prefix: Vec<Secp256k1Element> = Vec::with_capacity(points.len());
+        let mut acc = one;
+        for p in points {
+            if !p.is_infinity() {
+                acc = acc * p.z;
+            }
+            prefix.push(acc);
+        }
+        let mut inv = acc.inv();
+        let mut result = vec![new_secp256k1point_infinity(); points.len()];
+        for i in (0..points.len()).rev() {
+            let p = &points[i];
+            if p.is_infinity() {
+                continue;
+            }
+            let before = if i == 0 { one } else { prefix[i - 1] };
+            let z_inv = inv * before;
+            inv = inv * p.z;
+            let z_inv2 = z_inv.square();
+            result[i] = new_secp256k1point_from_element(p.x * z_inv2, p.y * z_inv2 * z_inv);
+        }
+        result
+    }
+
+    // 上位ビットから順に2倍算と加算を行う。テストでwNAFなどの結果と突き合わせるのに使う
+    #[allow(dead_code)]
+    pub fn mul(&self, k: &U256) -> Secp256k1JacobianPoint {
+        let mut result = Secp256k1JacobianPoint::infinity();
+        for i in (0..k.bits()).rev() {
+            result = result.double();
+            if k.bit(i) {
+                result = result.add(self);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_point::new_secp256k1point_g;
+
+    #[test]
+    fn test_jacobian_matches_affine() {
+        let g = new_secp256k1point_g();
+        let jg = Secp256k1JacobianPoint::from_affine(&g);
+        let g2 = jg.double();
+        assert_eq!(g2.to_affine(), g.clone() + g.clone());
+        let g3 = g2.add(&jg);
+        assert_eq!(g3.to_affine(), g2.add_affine(&g).to_affine());
+        assert_eq!(g3.to_affine(), g.clone().mul_from_u32(3));
+        // P + (-P) = O
+        assert!(g3.add(&g3.neg()).is_infinity());
+        // P + P は2倍算になる
+        assert_eq!(g3.add(&g3).to_affine(), g3.double().to_affine());
+        assert_eq!(jg.mul(&U256::from_u64(6)).to_affine(), g3.double().to_affine());
+    }
+
+    #[test]
+    fn test_batch_to_affine() {
+        let jg = Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g());
+        let points = vec![
+            jg.double(),
+            Secp256k1JacobianPoint::infinity(),
+            jg.double().add(&jg),
+            jg,
+        ];
+        let affine = Secp256k1JacobianPoint::batch_to_affine(&points);
+        for (j, a) in points.iter().zip(affine.iter()) {
+            assert_eq!(j.to_affine(), *a);
+        }
+    }
+}
@@ -1,37 +1,45 @@
 use crate::ecc::secp256k1_curve::{new_secp256k1curve, Secp256k1Curve};
 use crate::ecc::secp256k1_field::{
-    new_secp256k1element, new_secp256k1element_from_i32, Secp256k1Element,
+    new_secp256k1element, new_secp256k1element_from_u256, Secp256k1Element, SECP256K1_P,
 };
 use num_bigint::BigUint;
-use num_traits::{FromPrimitive, Num, One, Zero};
+use num_traits::{FromPrimitive, Num};
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, Div, Rem};
 
 use crate::ecc::encode::encode_base58_checksum;
-use crate::ecc::secp256k1_scalar_element::Secp256k1ScalarElement;
+use crate::ecc::network::Network;
+use crate::ecc::segwit_address::encode_segwit_address;
+use crate::ecc::secp256k1_ecmult::{mul_g, mul_wnaf, multi_mul};
+use crate::ecc::secp256k1_jacobian_point::Secp256k1JacobianPoint;
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement, new_secp256k1scalarelement_from_i32,
+    new_secp256k1scalarelement_from_u256, new_secp256k1scalarelement_from_u64,
+    Secp256k1ScalarElement,
+};
+use crate::ecc::secp256k1_schnorr::{schnorr_challenge, Secp256k1SchnorrSignature};
 use crate::ecc::secp256k1_signature::Secp256k1Signature;
+use crate::ecc::taproot::tap_tweak_hash;
+use crate::ecc::u256::U256;
+use crate::ecc::weierstrass::WeierstrassPoint;
 use crate::helper::helper::hash160;
 
-#[derive(Debug, Clone)]
-pub struct Secp256k1Point {
-    pub(crate) x: Secp256k1Element,
-    pub(crate) y: Secp256k1Element,
-    is_infinity: bool,
-    curve: Secp256k1Curve,
-}
-
-impl PartialEq for Secp256k1Point {
-    fn eq(&self, other: &Self) -> bool {
-        if self.is_infinity || other.is_infinity {
-            if self.is_infinity && other.is_infinity {
-                return true;
-            }
-            return false;
-        }
-        return self.x == other.x && self.y == other.y;
-    }
-}
+// 生成元Gの座標
+const SECP256K1_GX: U256 = U256::from_limbs([
+    0x59F2815B16F81798,
+    0x029BFCDB2DCE28D9,
+    0x55A06295CE870B07,
+    0x79BE667EF9DCBBAC,
+]);
+const SECP256K1_GY: U256 = U256::from_limbs([
+    0x9C47D08FFB10D4B8,
+    0xFD17B448A6855419,
+    0x5DA4FBFC0E1108A8,
+    0x483ADA7726A3C465,
+]);
+
+// 演算は汎用のWeierstrassPointで行い、ここではsecp256k1固有の処理だけを実装する
+pub type Secp256k1Point = WeierstrassPoint<Secp256k1Curve>;
 
 impl Secp256k1Point {
     #[allow(dead_code)]
@@ -60,110 +68,193 @@ impl Secp256k1Point {
         return hash160(self.uncompressed_sec());
     }
 
+    // SEC形式(圧縮・非圧縮・ハイブリッド)とBIP340のx-only(32byte)の公開鍵を読む。
+    // 無限遠点(0x00)や曲線上に無い点はエラーにする
     #[allow(dead_code)]
-    pub fn parse(v: Vec<u8>) -> Secp256k1Point {
-        if v.len() == 65 {
-            return Secp256k1Point::parse_uncompressed_sec(v);
-        } else if v.len() == 33 {
-            return Secp256k1Point::parse_compressed_sec(v);
-        } else {
-            panic!("bad length: expect　65 or 33 but got {}", v.len())
+    pub fn parse(v: Vec<u8>) -> Result<Secp256k1Point, PublicKeyError> {
+        if v.len() == 32 {
+            return lift_x(&v, false);
+        }
+        Secp256k1Point::parse_sec(v)
+    }
+
+    // SEC形式(33byte, 65byte)だけを読む。
+    // OP_CHECKSIGなどのスクリプトの公開鍵はBitcoin Coreと同じくx-onlyを受け付けてはいけないのでこちらを使う
+    pub fn parse_sec(v: Vec<u8>) -> Result<Secp256k1Point, PublicKeyError> {
+        match v.len() {
+            1 if v[0] == 0 => Err(PublicKeyError::Infinity),
+            33 => Secp256k1Point::parse_compressed_sec(v),
+            65 => Secp256k1Point::parse_uncompressed_sec(v),
+            len => Err(PublicKeyError::InvalidLength(len)),
         }
     }
 
+    // 0x04 || x || y、もしくはyの偶奇をプレフィックスにも持つハイブリッド形式 (0x06: 偶数, 0x07: 奇数)
     #[allow(dead_code)]
-    pub fn parse_uncompressed_sec(v: Vec<u8>) -> Secp256k1Point {
+    pub fn parse_uncompressed_sec(v: Vec<u8>) -> Result<Secp256k1Point, PublicKeyError> {
         if v.len() != 65 {
-            panic!("bad length: expect　65 but got {}", v.len())
+            return Err(PublicKeyError::InvalidLength(v.len()));
         }
-        let cons = v.split_first().unwrap();
-        let pos_vec = cons.1;
-        let xy_vec: Vec<Vec<u8>> = pos_vec.chunks(32).map(|x| x.to_vec()).collect();
-        if xy_vec.len() != 2 {
-            panic!("unknown error occuered(execute chunks(32),but cant get x,y positon)");
+        let marker = v[0];
+        if marker != 0x04 && marker != 0x06 && marker != 0x07 {
+            return Err(PublicKeyError::InvalidPrefix(marker));
         }
-
-        let x = BigUint::from_bytes_be(&*xy_vec[0]);
-        let y = BigUint::from_bytes_be(&*xy_vec[1]);
-
-        return new_secp256k1point_from_big_uint(x, y);
+        let x = U256::from_be_slice(&v[1..33]).unwrap();
+        let y = U256::from_be_slice(&v[33..65]).unwrap();
+        if x >= SECP256K1_P || y >= SECP256K1_P {
+            return Err(PublicKeyError::CoordinateOutOfRange);
+        }
+        let point = new_secp256k1point_from_element(
+            new_secp256k1element_from_u256(x),
+            new_secp256k1element_from_u256(y),
+        );
+        if !point.is_on_curve() {
+            return Err(PublicKeyError::NotOnCurve);
+        }
+        if marker != 0x04 && point.y.is_even() != (marker == 0x06) {
+            return Err(PublicKeyError::HybridParityMismatch);
+        }
+        Ok(point)
     }
 
+    // 0x02 || x (yが偶数)、0x03 || x (yが奇数)
     #[allow(dead_code)]
-    pub fn parse_compressed_sec(v: Vec<u8>) -> Secp256k1Point {
+    pub fn parse_compressed_sec(v: Vec<u8>) -> Result<Secp256k1Point, PublicKeyError> {
         if v.len() != 33 {
-            panic!("bad length: expect　33 but got {}", v.len())
-        }
-        let cons = v.split_first().unwrap();
-        let marker = *cons.0;
-        let x = BigUint::from_bytes_be(cons.1);
-        let curve = new_secp256k1curve();
-        let y_square = curve.rhs(x.clone());
-        let y_square = new_secp256k1element(y_square);
-        let y_1 = y_square.sqrt();
-        let y_2 = Secp256k1Element::prime() - y_1.clone();
-        if marker == 2u8 {
-            // y is even
-            if y_1.clone().rem(new_secp256k1element_from_i32(2)).num == BigUint::zero() {
-                return new_secp256k1point_from_element(new_secp256k1element(x), y_1);
-            }
-            return new_secp256k1point_from_element(new_secp256k1element(x), y_2);
-        }
-        if marker == 3u8 {
-            // y is odd
-            if y_1.clone().rem(new_secp256k1element_from_i32(2)).num == BigUint::zero() {
-                return new_secp256k1point_from_element(new_secp256k1element(x), y_2);
-            }
-            return new_secp256k1point_from_element(new_secp256k1element(x), y_1);
-        }
-        panic!("unrecognized marker: {} marker can be only (2,3) ", marker);
-    }
-
-    fn inner_mul(self, f: Secp256k1Point, v: BigUint) -> Secp256k1Point {
-        if v == BigUint::zero() {
-            return new_secp256k1point_infinity();
-        }
-        if v.clone().rem(2u64) == BigUint::zero() {
-            let half_res = self.inner_mul(f, v.clone().div(2u32));
-            let half_res2 = half_res.clone();
-            return half_res + half_res2;
-        }
-        let cf = f.clone();
-        f.clone() + f.clone().inner_mul(cf, v - BigUint::one())
+            return Err(PublicKeyError::InvalidLength(v.len()));
+        }
+        let marker = v[0];
+        if marker != 0x02 && marker != 0x03 {
+            return Err(PublicKeyError::InvalidPrefix(marker));
+        }
+        lift_x(&v[1..], marker == 0x03)
+    }
+
+    // スカラー倍はヤコビアン座標で計算し、最後に1回だけアフィン座標に戻す。
+    // Gの場合は事前計算テーブルを、それ以外はwNAFを使う。
+    fn inner_mul(self, v: Secp256k1ScalarElement) -> Secp256k1Point {
+        if self == new_secp256k1point_g() {
+            return mul_g(&v.value).to_affine();
+        }
+        mul_wnaf(&Secp256k1JacobianPoint::from_affine(&self), &v.value).to_affine()
     }
     #[allow(dead_code)]
     pub fn mul_from_u64(self, v: u64) -> Secp256k1Point {
-        let this = self.clone();
-        self.inner_mul(this, BigUint::from(v))
+        self.inner_mul(new_secp256k1scalarelement_from_u64(v))
     }
     #[allow(dead_code)]
     pub fn mul_from_u32(self, v: u32) -> Secp256k1Point {
-        let this = self.clone();
-        self.inner_mul(this, BigUint::from(v))
+        self.inner_mul(new_secp256k1scalarelement_from_u64(v as u64))
     }
     #[allow(dead_code)]
     pub fn mul_from_i32(self, v: i32) -> Secp256k1Point {
-        let this = self.clone();
-        self.inner_mul(this, BigUint::from_i32(v).unwrap())
+        self.inner_mul(new_secp256k1scalarelement_from_i32(v))
     }
+    // 群の位数nで割った余りを掛けても結果は変わらない
     #[allow(dead_code)]
     pub fn mul_from_big_uint(self, v: BigUint) -> Secp256k1Point {
-        let this = self.clone();
-        self.inner_mul(this, v)
+        self.inner_mul(new_secp256k1scalarelement(v))
     }
     #[allow(dead_code)]
     pub fn mul_from_sec256k1scalar_element(self, v: Secp256k1ScalarElement) -> Secp256k1Point {
-        let this = self.clone();
-        self.inner_mul(this, v.num)
+        self.inner_mul(v)
+    }
+    // sum(scalars[i] * points[i]) をまとめて計算する
+    #[allow(dead_code)]
+    pub fn multi_mul(points: &[Secp256k1Point], scalars: &[Secp256k1ScalarElement]) -> Secp256k1Point {
+        let points: Vec<Secp256k1JacobianPoint> =
+            points.iter().map(Secp256k1JacobianPoint::from_affine).collect();
+        let scalars: Vec<U256> = scalars.iter().map(|s| s.value).collect();
+        multi_mul(&points, &scalars).to_affine()
     }
     #[allow(dead_code)]
     pub fn verify(self, z: Secp256k1ScalarElement, sig: Secp256k1Signature) -> bool {
         let u = z.clone() / sig.s.clone();
         let v = sig.r.clone() / sig.s.clone();
         let g = new_secp256k1point_g();
-        let r_point =
-            g.mul_from_sec256k1scalar_element(u) + self.clone().mul_from_sec256k1scalar_element(v);
-        return r_point.x.num == sig.r.num;
+        // u*G + v*P を2倍算を共有して一度に計算する
+        let r_point = Secp256k1Point::multi_mul(&[g, self], &[u, v]);
+        if r_point.is_infinity() {
+            return false;
+        }
+        // x座標をnで割った余りがrと一致すれば正しい署名
+        new_secp256k1scalarelement_from_u256(r_point.x.value) == sig.r
+    }
+
+    #[allow(dead_code)]
+    pub fn has_even_y(&self) -> bool {
+        self.y.is_even()
+    }
+
+    // BIP340のx-only公開鍵(x座標の32byte)
+    #[allow(dead_code)]
+    pub fn x_only(&self) -> Vec<u8> {
+        self.x.value.to_be_bytes().to_vec()
+    }
+
+    // x-only公開鍵から、yが偶数の点を復元する
+    #[allow(dead_code)]
+    pub fn parse_x_only(v: &[u8]) -> Option<Secp256k1Point> {
+        if v.len() != 32 {
+            return None;
+        }
+        lift_x(v, false).ok()
+    }
+
+    // BIP340の検証。公開鍵はx座標だけを使う(yが偶数の点として扱う)
+    #[allow(dead_code)]
+    pub fn verify_schnorr(&self, msg: &[u8], sig: &Secp256k1SchnorrSignature) -> bool {
+        if self.is_infinity() {
+            return false;
+        }
+        let p = match Secp256k1Point::parse_x_only(&self.x_only()) {
+            Some(p) => p,
+            None => return false,
+        };
+        let e = schnorr_challenge(&sig.r.value.to_be_bytes(), &p.x_only(), msg);
+        // R = sG - eP
+        let r_point = Secp256k1Point::multi_mul(&[new_secp256k1point_g(), p], &[sig.s.clone(), -e]);
+        if r_point.is_infinity() || !r_point.has_even_y() {
+            return false;
+        }
+        r_point.x == sig.r
+    }
+
+    // BIP341のTapTweak。内部鍵(yは偶数として扱う)に t*G を足した出力鍵と、そのyが奇数かどうかを返す
+    #[allow(dead_code)]
+    pub fn tap_tweak(&self, merkle_root: Option<&[u8]>) -> Option<(Secp256k1Point, bool)> {
+        let internal = Secp256k1Point::parse_x_only(&self.x_only())?;
+        let t = tap_tweak_hash(&internal.x_only(), merkle_root)?;
+        let output = Secp256k1Point::multi_mul(
+            &[new_secp256k1point_g(), internal],
+            &[t, new_secp256k1scalarelement_from_u64(1)],
+        );
+        if output.is_infinity() {
+            return None;
+        }
+        let odd = !output.has_even_y();
+        Some((output, odd))
+    }
+
+    // P2TRの出力に使う32byteのwitness program(出力鍵のx座標)
+    #[allow(dead_code)]
+    pub fn p2tr_witness_program(&self, merkle_root: Option<&[u8]>) -> Option<Vec<u8>> {
+        let (output, _) = self.tap_tweak(merkle_root)?;
+        Some(output.x_only())
+    }
+
+    // P2WPKHのアドレス。witness programは圧縮形式の公開鍵のhash160
+    #[allow(dead_code)]
+    pub fn p2wpkh_address(&self, network: Network) -> String {
+        let program = self.clone().hash160_by_compressed_sec();
+        encode_segwit_address(network, 0, &program).unwrap()
+    }
+
+    // P2TRのアドレス。この点を内部鍵として出力鍵を計算する
+    #[allow(dead_code)]
+    pub fn p2tr_address(&self, merkle_root: Option<&[u8]>, network: Network) -> Option<String> {
+        let program = self.p2tr_witness_program(merkle_root)?;
+        Some(encode_segwit_address(network, 1, &program).unwrap())
     }
 
     #[allow(dead_code)]
@@ -194,8 +285,7 @@ impl Secp256k1Point {
 
     #[allow(dead_code)]
     pub fn compressed_sec_str(self) -> String {
-        let is_even =
-            self.y.rem(new_secp256k1element_from_i32(2)) == new_secp256k1element_from_i32(0);
+        let is_even = self.y.is_even();
         let mut ret: String = "".to_string();
         if is_even {
             ret += "02";
@@ -211,8 +301,7 @@ impl Secp256k1Point {
     #[allow(dead_code)]
     pub fn compressed_sec(self) -> Vec<u8> {
         let mut v: Vec<u8> = vec![];
-        let is_even =
-            self.y.rem(new_secp256k1element_from_i32(2)) == new_secp256k1element_from_i32(0);
+        let is_even = self.y.is_even();
         if is_even {
             v.push(2u8);
         } else {
@@ -225,59 +314,45 @@ impl Secp256k1Point {
     }
 }
 
-impl Display for Secp256k1Point {
+// 公開鍵を読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublicKeyError {
+    // 32, 33, 65byteのいずれでもない
+    InvalidLength(usize),
+    // 長さに対して不正なプレフィックス
+    InvalidPrefix(u8),
+    // 座標がp以上
+    CoordinateOutOfRange,
+    // 曲線上に無い(圧縮形式の場合は、xに対応するyが存在しない)
+    NotOnCurve,
+    // ハイブリッド形式のプレフィックスとyの偶奇が合わない
+    HybridParityMismatch,
+    // 無限遠点は公開鍵として使えない
+    Infinity,
+}
+
+impl Display for PublicKeyError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if self.is_infinity {
-            return write!(f, "(無限遠点)");
+        match self {
+            PublicKeyError::InvalidLength(len) => write!(f, "bad public key length: {}", len),
+            PublicKeyError::InvalidPrefix(prefix) => write!(f, "bad public key prefix: {:#04x}", prefix),
+            PublicKeyError::CoordinateOutOfRange => write!(f, "public key coordinate is not less than p"),
+            PublicKeyError::NotOnCurve => write!(f, "public key is not on the curve"),
+            PublicKeyError::HybridParityMismatch => write!(f, "hybrid public key prefix does not match y"),
+            PublicKeyError::Infinity => write!(f, "public key is the point at infinity"),
         }
-        write!(f, "({},{})", self.x, self.y)
     }
 }
 
-impl Add for Secp256k1Point {
-    type Output = Secp256k1Point;
-    fn add(self, rhs: Self) -> Self::Output {
-        if self.is_infinity {
-            return rhs;
-        }
-
-        if rhs.is_infinity {
-            return self;
-        }
+impl std::error::Error for PublicKeyError {}
 
-        // 楕円曲線の座標が一致する時は接点の傾きを利用する。
-        if self == rhs {
-            let f0 = new_secp256k1element_from_i32(0);
-            let f1 = new_secp256k1element_from_i32(1);
-            let f2 = new_secp256k1element_from_i32(2);
-            let f3 = new_secp256k1element_from_i32(3);
-            if self.y == f0 && rhs.y == f0 {
-                return new_secp256k1point_infinity();
-            }
-
-            let b = new_secp256k1element(self.curve.rhs(f0.num));
-            let a = new_secp256k1element(self.curve.rhs(f1.clone().num)) - f1 - b;
-            let x = self.x.clone();
-            let y = self.y.clone();
-            let s = (f3 * x.clone() * x.clone() + a) / (f2.clone() * y.clone());
-            let x3 = s.clone() * s.clone() - (f2.clone() * x.clone());
-            let y3 = s.clone() * (x.clone() - x3.clone()) - y.clone();
-            let p = new_secp256k1point_from_element(x3.clone(), y3.clone());
-            return p;
-        }
-
-        // 加法逆元の場合、無限遠点を返す
-        let f0 = new_secp256k1element_from_i32(0);
-        if self.x.clone() == rhs.x.clone() && (self.y.clone() + rhs.y.clone()) == f0 {
-            return new_secp256k1point_infinity();
-        }
-
-        let s = (rhs.y.clone() - self.y.clone()) / (rhs.x.clone() - self.x.clone());
-        let x3 = s.clone() * s.clone() - self.x.clone() - rhs.x.clone();
-        let y3 = s.clone() * (self.x.clone() - x3.clone()) - self.y.clone();
-
-        return new_secp256k1point_from_element(x3.clone(), y3);
+// x座標(32byte)とyの偶奇から曲線上の点を復元する
+fn lift_x(x: &[u8], y_is_odd: bool) -> Result<Secp256k1Point, PublicKeyError> {
+    let x = U256::from_be_slice(x).unwrap();
+    if x >= SECP256K1_P {
+        return Err(PublicKeyError::CoordinateOutOfRange);
     }
+    new_secp256k1point_from_x(new_secp256k1element_from_u256(x), y_is_odd).ok_or(PublicKeyError::NotOnCurve)
 }
 
 #[allow(dead_code)]
@@ -322,6 +397,7 @@ fn new_secp256k1point_from_i32(x: i32, y: i32) -> Option<Secp256k1Point> {
     });
 }
 
+#[allow(dead_code)]
 fn new_secp256k1point_from_big_uint(x: BigUint, y: BigUint) -> Secp256k1Point {
     let x = new_secp256k1element(x);
     let y = new_secp256k1element(y);
@@ -333,7 +409,7 @@ fn new_secp256k1point_from_big_uint(x: BigUint, y: BigUint) -> Secp256k1Point {
     };
 }
 
-fn new_secp256k1point_from_element(x: Secp256k1Element, y: Secp256k1Element) -> Secp256k1Point {
+pub(crate) fn new_secp256k1point_from_element(x: Secp256k1Element, y: Secp256k1Element) -> Secp256k1Point {
     return Secp256k1Point {
         x,
         y,
@@ -342,7 +418,7 @@ fn new_secp256k1point_from_element(x: Secp256k1Element, y: Secp256k1Element) ->
     };
 }
 
-fn new_secp256k1point_infinity() -> Secp256k1Point {
+pub(crate) fn new_secp256k1point_infinity() -> Secp256k1Point {
     return Secp256k1Point {
         x: new_secp256k1element(BigUint::from(1u64)),
         y: new_secp256k1element(BigUint::from(1u64)),
@@ -351,18 +427,26 @@ fn new_secp256k1point_infinity() -> Secp256k1Point {
     };
 }
 
+// x座標とyの偶奇から点を復元する。xが曲線上の点のx座標でなければNone
+pub(crate) fn new_secp256k1point_from_x(x: Secp256k1Element, y_is_odd: bool) -> Option<Secp256k1Point> {
+    let seven = new_secp256k1element_from_u256(U256::from_u64(7));
+    let y_square = x * x.square() + seven;
+    let y = y_square.sqrt();
+    // pが4で割って3余る素数なので、平方剰余でない場合は二乗しても元に戻らない
+    if y.square() != y_square {
+        return None;
+    }
+    if y.is_even() == y_is_odd {
+        return Some(new_secp256k1point_from_element(x, -y));
+    }
+    Some(new_secp256k1point_from_element(x, y))
+}
+
 pub fn new_secp256k1point_g() -> Secp256k1Point {
-    let x = BigUint::from_str_radix(
-        "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
-        16,
-    )
-    .unwrap();
-    let y = BigUint::from_str_radix(
-        "483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
-        16,
+    new_secp256k1point_from_element(
+        new_secp256k1element_from_u256(SECP256K1_GX),
+        new_secp256k1element_from_u256(SECP256K1_GY),
     )
-    .unwrap();
-    return new_secp256k1point_from_big_uint(x, y);
 }
 
 fn new_secp256k1point_from_hex_str(x: &str, y: &str) -> Option<Secp256k1Point> {
@@ -399,6 +483,13 @@ mod tests {
         )
         .unwrap();
         assert_eq!(new_secp256k1point_infinity(), base.mul_from_big_uint(n));
+        // リムの定数が16進数の値と一致すること
+        let from_hex = new_secp256k1point_from_hex_str(
+            "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
+        )
+        .unwrap();
+        assert_eq!(new_secp256k1point_g(), from_hex);
     }
 
     #[test]
@@ -428,7 +519,7 @@ mod tests {
             let vp = p.clone().mul_from_sec256k1scalar_element(v.clone());
             let r_point = ug.clone() + vp.clone();
             // Rxとrが一致していれば署名は有効
-            assert_eq!(r_point.x.num, r.num);
+            assert_eq!(r_point.x.num(), r.num());
         }
         {
             // signature 1
@@ -454,7 +545,7 @@ mod tests {
             let r_point =
                 g.mul_from_sec256k1scalar_element(u) + p.mul_from_sec256k1scalar_element(v);
             // Rxとrが一致していれば署名は有効
-            assert_eq!(r_point.x.num, r.num);
+            assert_eq!(r_point.x.num(), r.num());
         }
         {
             // signature 1
@@ -553,20 +644,72 @@ mod tests {
     fn test_address_p86() {
         {
             let private = new_secp_256k1privatekey_from_i32(5002);
-            let addr = private.point.uncompressed_address(true);
+            let addr = private.point.clone().uncompressed_address(true);
             assert_eq!("mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA", addr);
         }
         {
             let se = new_secp256k1scalarelement_from_i32(2020);
             let private = new_secp_256k1privatekey(se.pow(BigUint::from(5u8)));
-            let addr = private.point.compressed_address(true);
+            let addr = private.point.clone().compressed_address(true);
             assert_eq!("mopVkxp8UhXqRYbCYJsbeE1h1fiF64jcoH", addr)
         }
         {
             let se = new_secp256k1scalarelement_from_hex_str("12345deadbeef").unwrap();
             let private = new_secp_256k1privatekey(se);
-            let addr = private.point.compressed_address(false);
+            let addr = private.point.clone().compressed_address(false);
             assert_eq!("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1", addr);
         }
     }
+
+    #[test]
+    fn test_parse_sec_forms() {
+        for secret in [1, 2, 12345].iter() {
+            let point = new_secp_256k1privatekey_from_i32(*secret).point.clone();
+            let compressed = point.clone().compressed_sec();
+            let uncompressed = point.clone().uncompressed_sec();
+            assert_eq!(Secp256k1Point::parse(compressed.clone()), Ok(point.clone()));
+            assert_eq!(Secp256k1Point::parse(uncompressed.clone()), Ok(point.clone()));
+            // ハイブリッド形式はプレフィックスとyの偶奇が一致している必要がある
+            let mut hybrid = uncompressed.clone();
+            hybrid[0] = if point.has_even_y() { 0x06 } else { 0x07 };
+            assert_eq!(Secp256k1Point::parse(hybrid.clone()), Ok(point.clone()));
+            hybrid[0] ^= 1;
+            assert_eq!(Secp256k1Point::parse(hybrid), Err(PublicKeyError::HybridParityMismatch));
+            // x-onlyはyが偶数の点になる
+            let x_only = Secp256k1Point::parse(point.x_only()).unwrap();
+            assert_eq!(x_only.x, point.x);
+            assert!(x_only.has_even_y());
+
+            let mut off_curve = uncompressed.clone();
+            off_curve[64] ^= 1;
+            assert_eq!(Secp256k1Point::parse(off_curve), Err(PublicKeyError::NotOnCurve));
+            let mut bad_prefix = compressed.clone();
+            bad_prefix[0] = 0x04;
+            assert_eq!(Secp256k1Point::parse(bad_prefix), Err(PublicKeyError::InvalidPrefix(0x04)));
+            let mut bad_prefix = uncompressed.clone();
+            bad_prefix[0] = 0x05;
+            assert_eq!(Secp256k1Point::parse(bad_prefix), Err(PublicKeyError::InvalidPrefix(0x05)));
+        }
+
+        let hex = |s: &str| crate::helper::helper::decode_hex(s).unwrap();
+        // x = 5 に対応するyは存在しない
+        assert_eq!(
+            Secp256k1Point::parse(hex("020000000000000000000000000000000000000000000000000000000000000005")),
+            Err(PublicKeyError::NotOnCurve)
+        );
+        assert_eq!(
+            Secp256k1Point::parse(hex("02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30")),
+            Err(PublicKeyError::CoordinateOutOfRange)
+        );
+        assert_eq!(Secp256k1Point::parse(vec![0u8]), Err(PublicKeyError::Infinity));
+        assert_eq!(Secp256k1Point::parse(vec![2u8; 34]), Err(PublicKeyError::InvalidLength(34)));
+        // parse_secはx-onlyを受け付けない
+        let g = new_secp256k1point_g();
+        assert_eq!(Secp256k1Point::parse_sec(g.x_only()), Err(PublicKeyError::InvalidLength(32)));
+        assert_eq!(Secp256k1Point::parse_sec(g.clone().compressed_sec()), Ok(g));
+        assert_eq!(
+            Secp256k1Point::parse_compressed_sec(vec![2u8; 65]),
+            Err(PublicKeyError::InvalidLength(65))
+        );
+    }
 }
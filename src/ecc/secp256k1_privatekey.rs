@@ -1,23 +1,31 @@
+use crate::ecc::decode::{decode_base58_checksum_with_version, Base58Error};
 use crate::ecc::encode::encode_base58_checksum;
-use crate::ecc::secp256k1_point::{new_secp256k1point_g, Secp256k1Point};
+use crate::ecc::secp256k1_ecmult::{mul_ct, mul_g_ct};
+use crate::ecc::secp256k1_point::Secp256k1Point;
+use crate::ecc::secp256k1_projective_point::Secp256k1ProjectivePoint;
+use crate::ecc::secp256k1_recoverable_signature::{
+    new_secp256k1recoverablesignature, Secp256k1RecoverableSignature,
+};
+use crate::ecc::secp256k1_schnorr::{
+    new_secp256k1schnorrsignature, schnorr_challenge, schnorr_nonce, Secp256k1SchnorrSignature,
+};
 use crate::ecc::secp256k1_scalar_element::{
-    new_secp256k1scalarelement, new_secp256k1scalarelement_from_i32, Secp256k1ScalarElement,
+    new_secp256k1scalarelement, new_secp256k1scalarelement_from_i32,
+    new_secp256k1scalarelement_from_u256, Secp256k1ScalarElement, SECP256K1_N,
 };
 use crate::ecc::secp256k1_signature::{new_secp256k1signature, Secp256k1Signature};
-use num_bigint::{BigUint, RandBigInt};
-use num_traits::{FromPrimitive, Num, One};
-use rand::thread_rng;
-
-// 楕円曲線上の加算に対しての有限体の位数はこの値となる。
-fn prime() -> BigUint {
-    return BigUint::from_str_radix(
-        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
-        16,
-    )
-    .unwrap();
-}
+use crate::ecc::taproot::tap_tweak_hash;
+use crate::ecc::u256::U256;
+use crate::ecc::zeroize::new_zeroizing;
+use crate::helper::helper::{hmac_sha256, sha256};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 
-#[derive(Debug, Clone)]
+// 秘密鍵はDebugで表示されず、ドロップ時に0で上書きされる
+#[derive(Clone)]
 pub struct Secp256k1PrivateKey {
     // 秘密鍵
     secret: Secp256k1ScalarElement,
@@ -25,78 +33,335 @@ pub struct Secp256k1PrivateKey {
     pub(crate) point: Secp256k1Point,
 }
 
+// 秘密鍵から公開鍵を求める。秘密の値を扱うので定数時間のスカラー倍を使う
+fn public_point(secret: &Secp256k1ScalarElement) -> Secp256k1Point {
+    mul_g_ct(&secret.value).to_affine()
+}
+
 #[allow(dead_code)]
 pub fn new_secp_256k1privatekey(secret: Secp256k1ScalarElement) -> Secp256k1PrivateKey {
     return Secp256k1PrivateKey {
-        secret: secret.clone(),
-        point: new_secp256k1point_g().mul_from_sec256k1scalar_element(secret),
+        point: public_point(&secret),
+        secret,
     };
 }
 
+impl Drop for Secp256k1PrivateKey {
+    fn drop(&mut self) {
+        self.secret.value.zeroize();
+    }
+}
+
+impl Debug for Secp256k1PrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secp256k1PrivateKey")
+            .field("secret", &"[REDACTED]")
+            .field("point", &self.point)
+            .finish()
+    }
+}
+
 #[allow(dead_code)]
 pub fn new_secp_256k1privatekey_from_biguint(secret: BigUint) -> Secp256k1PrivateKey {
-    return Secp256k1PrivateKey {
-        secret: new_secp256k1scalarelement(secret.clone()),
-        point: new_secp256k1point_g().mul_from_big_uint(secret),
-    };
+    new_secp_256k1privatekey(new_secp256k1scalarelement(secret))
 }
 
 #[allow(dead_code)]
 pub fn new_secp_256k1privatekey_from_i32(secret: i32) -> Secp256k1PrivateKey {
-    return Secp256k1PrivateKey {
-        secret: new_secp256k1scalarelement_from_i32(secret),
-        point: new_secp256k1point_g().mul_from_i32(secret),
-    };
+    new_secp_256k1privatekey(new_secp256k1scalarelement_from_i32(secret))
 }
 impl Secp256k1PrivateKey {
+    // OSの乱数生成器から [1, n-1] の秘密鍵を作る。範囲外の値は捨てて引き直す
+    #[allow(dead_code)]
+    pub fn generate() -> Secp256k1PrivateKey {
+        let mut bytes = new_zeroizing([0u8; 32]);
+        loop {
+            OsRng.fill_bytes(&mut *bytes);
+            let candidate = new_zeroizing(U256::from_be_bytes(&bytes));
+            if !candidate.is_zero() && *candidate < SECP256K1_N {
+                return new_secp_256k1privatekey(new_secp256k1scalarelement_from_u256(*candidate));
+            }
+        }
+    }
+
+    // 秘密鍵のスカラーをそのまま返す。呼び出し側で外に漏らさないこと
+    pub fn expose_secret(&self) -> &Secp256k1ScalarElement {
+        &self.secret
+    }
+
+    // 秘密鍵の32byte big endian表現
     #[allow(dead_code)]
-    pub fn hex(self) -> String {
-        self.secret.num.to_str_radix(16)
+    pub fn expose_secret_bytes(&self) -> [u8; 32] {
+        self.secret.value.to_be_bytes()
     }
     #[allow(dead_code)]
     // zは署名先のハッシュ(p.66)だったりメッセージだったり
+    // Bitcoin Coreと同様にrの先頭ビットが立たない(DERで32byteに収まる)kが出るまでextra_entropyを変えて署名し直す
     pub fn sign(self, z: Secp256k1ScalarElement) -> Secp256k1Signature {
-        let mut generator = thread_rng();
-        let k = if cfg!(test) {
-            BigUint::from_str_radix("83517225909146562989623428579023711666455447161572744791292937142167960758829",10).unwrap()
+        let mut sig = self.sign_with_extra_entropy(z.clone(), None);
+        let mut counter = 0u32;
+        while sig.r.to_32_bytes_be().unwrap()[0] >= 0x80 {
+            counter += 1;
+            let mut extra_entropy = [0u8; 32];
+            extra_entropy[..4].copy_from_slice(&counter.to_le_bytes());
+            sig = self.sign_with_extra_entropy(z.clone(), Some(extra_entropy));
+        }
+        sig
+    }
+
+    #[allow(dead_code)]
+    // low-Rの探索をせず、RFC6979で導出したkでそのまま署名する
+    pub fn sign_with_extra_entropy(
+        &self,
+        z: Secp256k1ScalarElement,
+        extra_entropy: Option<[u8; 32]>,
+    ) -> Secp256k1Signature {
+        self.sign_recoverable_with_extra_entropy(z, extra_entropy)
+            .signature()
+    }
+
+    #[allow(dead_code)]
+    // 公開鍵を復元できる署名を作る。
+    // Bitcoin Core の signmessage と同じく low-R の探索はしない
+    pub fn sign_recoverable(self, z: Secp256k1ScalarElement) -> Secp256k1RecoverableSignature {
+        self.sign_recoverable_with_extra_entropy(z, None)
+    }
+
+    fn sign_recoverable_with_extra_entropy(
+        &self,
+        z: Secp256k1ScalarElement,
+        extra_entropy: Option<[u8; 32]>,
+    ) -> Secp256k1RecoverableSignature {
+        let k = new_zeroizing(self.deterministic_k(z.clone(), extra_entropy));
+        // k と secret が関わる計算は全て定数時間で行う(スカラーの逆元はフェルマーの小定理なので定数時間)
+        let r_point = mul_g_ct(&k.value).to_affine();
+        let mut recovery_id = if r_point.y.is_even() { 0u8 } else { 1u8 };
+        if r_point.x.value >= SECP256K1_N {
+            recovery_id |= 2;
+        }
+        let r = new_secp256k1scalarelement_from_u256(r_point.x.value);
+        let mut s = (z + (r.clone() * self.secret.clone())) / k.clone();
+        if s.is_high() {
+            // sを反転するとRも反転したことになる
+            s = -s;
+            recovery_id ^= 1;
+        }
+        new_secp256k1recoverablesignature(new_secp256k1signature(r, s), recovery_id)
+    }
+
+    #[allow(dead_code)]
+    // BIP340 Schnorr署名。aux_randは毎回新しい乱数を使うのが望ましい(全て0でも安全性は保たれる)
+    // ノンスが0になった場合(事実上起こらない)はNone
+    pub fn sign_schnorr(&self, msg: &[u8], aux_rand: &[u8; 32]) -> Option<Secp256k1SchnorrSignature> {
+        // 公開鍵のyが奇数の場合は、yが偶数になるように秘密鍵を反転する
+        let d = new_zeroizing(if self.point.has_even_y() {
+            self.secret.clone()
         } else {
-            generator.gen_biguint_below(&(prime() - BigUint::one()))
-        };
-        #[cfg(test)]
-        println!("sign k: {}",k.clone());
-        let k = new_secp256k1scalarelement(k.clone());
-        let r = new_secp256k1point_g()
-            .mul_from_sec256k1scalar_element(k.clone())
-            .x;
-        let r = new_secp256k1scalarelement(r.num);
-        let mut s = (z + (r.clone() * self.secret)) / k;
-        if s.num > new_secp256k1scalarelement(prime() / BigUint::from_u8(2u8).unwrap()).num {
-            s = new_secp256k1scalarelement(prime()) - s;
-        }
-        #[cfg(test)]
-        println!("sign r: {}, sign s: {}",r,s);
-        return new_secp256k1signature(r, s);
+            -self.secret.clone()
+        });
+        let p_x = self.point.x_only();
+        let k = new_zeroizing(schnorr_nonce(&d, &p_x, msg, aux_rand));
+        if k.is_zero() {
+            return None;
+        }
+        let r_point = mul_g_ct(&k.value).to_affine();
+        let k = new_zeroizing(if r_point.has_even_y() { k.clone() } else { -k.clone() });
+        let r_x = r_point.x_only();
+        let e = schnorr_challenge(&r_x, &p_x, msg);
+        let s = k.clone() + e * d.clone();
+        Some(new_secp256k1schnorrsignature(r_point.x, s))
     }
+
+    #[allow(dead_code)]
+    // BIP341の鍵パスで署名するための秘密鍵。公開鍵は Secp256k1Point::tap_tweak の出力鍵になる
+    pub fn tap_tweak(&self, merkle_root: Option<&[u8]>) -> Option<Secp256k1PrivateKey> {
+        let d = new_zeroizing(if self.point.has_even_y() {
+            self.secret.clone()
+        } else {
+            -self.secret.clone()
+        });
+        let t = tap_tweak_hash(&self.point.x_only(), merkle_root)?;
+        let tweaked = new_zeroizing(d.clone() + t);
+        if tweaked.is_zero() {
+            return None;
+        }
+        Some(new_secp_256k1privatekey(tweaked.clone()))
+    }
+
+    #[allow(dead_code)]
+    // ECDHの共有秘密。libsecp256k1と同じく、共有点の圧縮形式(33byte)のsha256を返す。
+    // 相手の公開鍵が無限遠点か曲線上に無い場合はNone
+    pub fn ecdh(&self, point: &Secp256k1Point) -> Option<Vec<u8>> {
+        self.ecdh_with_hash(point, |x, y| {
+            let mut data = vec![0x02u8 | (y[31] & 1)];
+            data.extend_from_slice(x);
+            sha256(data)
+        })
+    }
+
+    #[allow(dead_code)]
+    // 共有点のx座標とy座標(それぞれ32byte)から任意のハッシュ関数で共有秘密を作る
+    pub fn ecdh_with_hash<F>(&self, point: &Secp256k1Point, hash_fn: F) -> Option<Vec<u8>>
+    where
+        F: Fn(&[u8; 32], &[u8; 32]) -> Vec<u8>,
+    {
+        if !point.is_on_curve() {
+            return None;
+        }
+        // 秘密鍵を使うので定数時間のスカラー倍で計算する
+        let shared = mul_ct(&Secp256k1ProjectivePoint::from_affine(point), &self.secret.value).to_affine();
+        if shared.is_infinity() {
+            return None;
+        }
+        Some(hash_fn(&shared.x.value.to_be_bytes(), &shared.y.value.to_be_bytes()))
+    }
+
+    #[allow(dead_code)]
+    // RFC6979 (HMAC-SHA256) で秘密鍵とzから決定的にkを導出する。
+    // extra_entropyはlibsecp256k1と同様に secret || z の後ろに連結される。
+    pub fn deterministic_k(
+        &self,
+        z: Secp256k1ScalarElement,
+        extra_entropy: Option<[u8; 32]>,
+    ) -> Secp256k1ScalarElement {
+        // k, v, seed は全て秘密鍵から作られるので、使い終わったら0で上書きする
+        let mut k = new_zeroizing(vec![0u8; 32]);
+        let mut v = new_zeroizing(vec![1u8; 32]);
+        let secret_bytes = new_zeroizing(self.secret.value.to_be_bytes());
+        // zは既にnで割った余りなのでbits2octetsはそのまま32byteにするだけで良い
+        let z_bytes = z.to_32_bytes_be().unwrap();
+        // 伸ばしたときに古い領域が残らないよう、先に必要な分だけ確保しておく
+        let mut seed = new_zeroizing(Vec::with_capacity(96));
+        seed.extend_from_slice(&secret_bytes[..]);
+        seed.extend_from_slice(&z_bytes);
+        if let Some(extra_entropy) = extra_entropy {
+            seed.extend_from_slice(&extra_entropy);
+        }
+
+        for marker in [0x00u8, 0x01u8].iter() {
+            let mut data = new_zeroizing(Vec::with_capacity(33 + seed.len()));
+            data.extend_from_slice(&v);
+            data.push(*marker);
+            data.extend_from_slice(&seed);
+            k = new_zeroizing(hmac_sha256(&k, &data));
+            v = new_zeroizing(hmac_sha256(&k, &v));
+        }
+
+        loop {
+            v = new_zeroizing(hmac_sha256(&k, &v));
+            let candidate = new_zeroizing(U256::from_be_slice(&v).unwrap());
+            if !candidate.is_zero() && *candidate < SECP256K1_N {
+                return new_secp256k1scalarelement_from_u256(*candidate);
+            }
+            let mut data = new_zeroizing(Vec::with_capacity(33));
+            data.extend_from_slice(&v);
+            data.push(0x00u8);
+            k = new_zeroizing(hmac_sha256(&k, &data));
+            v = new_zeroizing(hmac_sha256(&k, &v));
+        }
+    }
+
     #[allow(dead_code)]
     pub fn wif(self, compressed: bool, testnet: bool) -> String {
-        let mut result = self.secret.to_32_bytes_be().unwrap();
+        let mut result = new_zeroizing(Vec::with_capacity(34));
         if testnet {
-            result.insert(0, 0xefu8);
+            result.push(0xefu8);
         } else {
-            result.insert(0, 0x80u8);
+            result.push(0x80u8);
         }
+        result.extend_from_slice(&new_zeroizing(self.secret.value.to_be_bytes())[..]);
         if compressed {
             result.push(1u8);
         }
-        return encode_base58_checksum(result);
+        encode_base58_checksum(result.to_vec())
+    }
+
+    // wif()の逆。プレフィックス(0x80/0xef)と末尾の0x01から、ネットワークと圧縮形式かどうかも返す
+    #[allow(dead_code)]
+    pub fn from_wif(wif: &str) -> Result<WifPrivateKey, WifError> {
+        let (version, payload) = decode_base58_checksum_with_version(wif).map_err(WifError::Base58)?;
+        let payload = new_zeroizing(payload);
+        parse_wif_payload(version, &payload)
+    }
+}
+
+// バージョン(0x80/0xef) || secret(32byte) || 0x01(圧縮形式の場合のみ)
+fn parse_wif_payload(version: u8, payload: &[u8]) -> Result<WifPrivateKey, WifError> {
+    let testnet = match version {
+        0x80 => false,
+        0xef => true,
+        prefix => return Err(WifError::InvalidPrefix(prefix)),
+    };
+    let compressed = match payload.len() {
+        32 => false,
+        33 => {
+            if payload[32] != 0x01 {
+                return Err(WifError::InvalidCompressionFlag(payload[32]));
+            }
+            true
+        }
+        len => return Err(WifError::InvalidLength(len)),
+    };
+    let mut secret = new_zeroizing([0u8; 32]);
+    secret.copy_from_slice(&payload[..32]);
+    let value = new_zeroizing(U256::from_be_bytes(&secret));
+    if value.is_zero() || *value >= SECP256K1_N {
+        return Err(WifError::SecretOutOfRange);
     }
+    Ok(WifPrivateKey {
+        key: new_secp_256k1privatekey(new_secp256k1scalarelement_from_u256(*value)),
+        compressed,
+        testnet,
+    })
 }
 
+// WIFから読み込んだ秘密鍵と、WIFに含まれていた付加情報
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct WifPrivateKey {
+    pub(crate) key: Secp256k1PrivateKey,
+    // 公開鍵を圧縮形式で使うかどうか
+    pub(crate) compressed: bool,
+    pub(crate) testnet: bool,
+}
+
+// WIFを読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum WifError {
+    Base58(Base58Error),
+    // バージョンを除いて32byteか33byteでない
+    InvalidLength(usize),
+    // 0x80(mainnet)でも0xef(testnet)でもない
+    InvalidPrefix(u8),
+    // 34byteの場合の末尾が0x01ではない
+    InvalidCompressionFlag(u8),
+    // 秘密鍵が [1, n-1] に収まっていない
+    SecretOutOfRange,
+}
+
+impl Display for WifError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WifError::Base58(e) => write!(f, "bad WIF: {}", e),
+            WifError::InvalidLength(len) => write!(f, "bad WIF: invalid payload length {}", len),
+            WifError::InvalidPrefix(prefix) => write!(f, "bad WIF: unknown prefix 0x{:02x}", prefix),
+            WifError::InvalidCompressionFlag(flag) => {
+                write!(f, "bad WIF: invalid compression flag 0x{:02x}", flag)
+            }
+            WifError::SecretOutOfRange => write!(f, "bad WIF: secret is not in [1, n-1]"),
+        }
+    }
+}
+
+impl std::error::Error for WifError {}
+
 #[cfg(test)]
 mod tests {
     extern crate test;
     use super::*;
     use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement_from_hex_str;
+    use crate::ecc::secp256k1_point::new_secp256k1point_infinity;
+    use crate::helper::helper::decode_hex;
 
     #[test]
     fn test_sel() {
@@ -137,4 +402,169 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn test_from_wif() {
+        let wif = Secp256k1PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ").unwrap();
+        assert_eq!(
+            wif.key.expose_secret_bytes().to_vec(),
+            decode_hex("0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d").unwrap()
+        );
+        assert!(!wif.compressed);
+        assert!(!wif.testnet);
+
+        for (compressed, testnet) in [(true, true), (false, true), (true, false), (false, false)].iter() {
+            let key = new_secp_256k1privatekey_from_i32(5003);
+            let decoded = Secp256k1PrivateKey::from_wif(&key.clone().wif(*compressed, *testnet)).unwrap();
+            assert_eq!(decoded.key.expose_secret(), key.expose_secret());
+            assert_eq!(decoded.compressed, *compressed);
+            assert_eq!(decoded.testnet, *testnet);
+        }
+
+        let payload = |prefix: u8, secret: u8, suffix: &[u8]| {
+            let mut v = vec![prefix];
+            v.extend_from_slice(&[0u8; 31]);
+            v.push(secret);
+            v.extend_from_slice(suffix);
+            encode_base58_checksum(v)
+        };
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTj").unwrap_err(),
+            WifError::Base58(Base58Error::InvalidChecksum)
+        );
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyT0").unwrap_err(),
+            WifError::Base58(Base58Error::InvalidCharacter('0'))
+        );
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif(&payload(0x00, 1, &[])).unwrap_err(),
+            WifError::InvalidPrefix(0x00)
+        );
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif(&payload(0x80, 1, &[0x02])).unwrap_err(),
+            WifError::InvalidCompressionFlag(0x02)
+        );
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif(&payload(0x80, 1, &[0x01, 0x01])).unwrap_err(),
+            WifError::InvalidLength(34)
+        );
+        assert_eq!(
+            Secp256k1PrivateKey::from_wif(&payload(0xef, 0, &[0x01])).unwrap_err(),
+            WifError::SecretOutOfRange
+        );
+    }
+
+    #[test]
+    fn test_rfc6979_vectors() {
+        // (secret, message, k, r, s)
+        let vectors = [
+            (
+                "1",
+                "Satoshi Nakamoto",
+                "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15",
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+                "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            ),
+            (
+                "1",
+                "All those moments will be lost in time, like tears in rain. Time to die...",
+                "38aa22d72376b4dbc472e06c3ba403ee0a394da63fc58d88686c611aba98d6b3",
+                "8600dbd41e348fe5c9465ab92d23e3db8b98b873beecd930736488696438cb6b",
+                "547fe64427496db33bf66019dacbf0039c04199abb0122918601db38a72cfc21",
+            ),
+            (
+                "f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181",
+                "Alan Turing",
+                "525a82b70e67874398067543fd84c83d30c175fdc45fdeee082fe13b1d7cfdf1",
+                "7063ae83e7f62bbb171798131b4a0564b956930092b33b07b395615d9ec7e15c",
+                "58dfcc1e00a35e1572f366ffe34ba0fc47db1e7189759b9fb233c5b05ab388ea",
+            ),
+        ];
+        for (secret, message, k, r, s) in vectors.iter() {
+            let pk = new_secp_256k1privatekey(new_secp256k1scalarelement_from_hex_str(secret).unwrap());
+            let z = new_secp256k1scalarelement(BigUint::from_bytes_be(&sha256(message.as_bytes().to_vec())));
+            let expected_k = new_secp256k1scalarelement_from_hex_str(k).unwrap();
+            assert_eq!(pk.deterministic_k(z.clone(), None), expected_k);
+
+            let sig = pk.sign_with_extra_entropy(z, None);
+            assert_eq!(sig.r, new_secp256k1scalarelement_from_hex_str(r).unwrap());
+            assert_eq!(sig.s, new_secp256k1scalarelement_from_hex_str(s).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sign_is_deterministic_and_low_r() {
+        let pk = new_secp_256k1privatekey(new_secp256k1scalarelement_from_i32(12345));
+        for i in 0..8 {
+            let z = new_secp256k1scalarelement(BigUint::from_bytes_be(&sha256(vec![i as u8])));
+            let sig1 = pk.clone().sign(z.clone());
+            let sig2 = pk.clone().sign(z.clone());
+            assert_eq!(sig1.clone().der(), sig2.der());
+            // low-Rなのでrは32byte以下でDER署名は最大70byte
+            assert!(sig1.r.to_32_bytes_be().unwrap()[0] < 0x80);
+            assert!(sig1.clone().der().len() <= 70);
+            assert!(pk.point.clone().verify(z, sig1));
+        }
+    }
+
+    #[test]
+    fn test_redacted_and_generated_key() {
+        let key = new_secp_256k1privatekey_from_i32(12345);
+        let debug = format!("{:?}", key);
+        assert!(debug.contains("[REDACTED]"));
+        assert!(!debug.contains("12345"));
+        let mut expected = [0u8; 32];
+        expected[30..].copy_from_slice(&12345u16.to_be_bytes());
+        assert_eq!(key.expose_secret_bytes(), expected);
+        assert_eq!(key.expose_secret().num(), BigUint::from(12345u32));
+
+        for _ in 0..8 {
+            let key = Secp256k1PrivateKey::generate();
+            assert!(!key.expose_secret().is_zero());
+            assert!(key.expose_secret().value < SECP256K1_N);
+            assert_eq!(key.point, public_point(key.expose_secret()));
+        }
+
+        let mut value = U256::from_u64(12345);
+        value.zeroize();
+        assert!(value.is_zero());
+    }
+
+    #[test]
+    fn test_ecdh() {
+        let a = new_secp_256k1privatekey(
+            new_secp256k1scalarelement_from_hex_str("c90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74020bbea63b14e5c9").unwrap(),
+        );
+        let b = new_secp_256k1privatekey(
+            new_secp256k1scalarelement_from_hex_str("b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef").unwrap(),
+        );
+        let expected = decode_hex("2b569652db918eb48fa6d859e0a1080950d1d76183983513bf87f6c2fbfa4d05").unwrap();
+        assert_eq!(a.ecdh(&b.point), Some(expected.clone()));
+        assert_eq!(b.ecdh(&a.point), Some(expected));
+        // ハッシュ関数を指定する(ここではx座標をそのまま使う)
+        let x_only = |x: &[u8; 32], _: &[u8; 32]| x.to_vec();
+        assert_eq!(
+            a.ecdh_with_hash(&b.point, x_only),
+            decode_hex("ca77ad739864d3f6f599f93842f48c89d5a9a7d0c867b767d53bf96f03557627").ok()
+        );
+        assert_eq!(a.ecdh_with_hash(&b.point, x_only), b.ecdh_with_hash(&a.point, x_only));
+        assert_ne!(a.ecdh(&b.point), a.ecdh(&a.point));
+
+        assert_eq!(a.ecdh(&new_secp256k1point_infinity()), None);
+        let mut invalid = b.point.clone();
+        invalid.y = invalid.y + invalid.y;
+        assert_eq!(a.ecdh(&invalid), None);
+    }
+
+    #[test]
+    fn test_sign_grinds_for_low_r() {
+        // この鍵とメッセージではRFC6979そのままのrの先頭バイトが0x93になる
+        let pk = new_secp_256k1privatekey(new_secp256k1scalarelement_from_i32(1));
+        let z = new_secp256k1scalarelement(BigUint::from_bytes_be(&sha256(b"Satoshi Nakamoto".to_vec())));
+        let high_r = pk.sign_with_extra_entropy(z.clone(), None);
+        let low_r = pk.clone().sign(z.clone());
+        assert_ne!(high_r.r, low_r.r);
+        assert!(low_r.r.to_32_bytes_be().unwrap()[0] < 0x80);
+        assert!(pk.point.clone().verify(z, low_r));
+    }
 }
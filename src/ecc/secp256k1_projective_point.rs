@@ -0,0 +1,141 @@
+use crate::ecc::secp256k1_field::{new_secp256k1element_from_i32, Secp256k1Element};
+use crate::ecc::secp256k1_point::{
+    new_secp256k1point_from_element, new_secp256k1point_infinity, Secp256k1Point,
+};
+use crate::ecc::u256::U256;
+
+// 3 * b (b = 7)
+const B3: Secp256k1Element = Secp256k1Element {
+    value: U256::from_limbs([21, 0, 0, 0]),
+};
+
+// 斉次射影座標 (X : Y : Z) で表した点。アフィン座標では (X/Z, Y/Z) に対応する。
+// Renes-Costello-Batina の完全加算公式を使うので、無限遠点や同じ点同士でも場合分けが要らない。
+// 秘密の値を扱うスカラー倍(署名など)で分岐による時間差が出ないようにするために使う。
+// 無限遠点は (0 : 1 : 0)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Secp256k1ProjectivePoint {
+    pub(crate) x: Secp256k1Element,
+    pub(crate) y: Secp256k1Element,
+    pub(crate) z: Secp256k1Element,
+}
+
+impl Secp256k1ProjectivePoint {
+    pub fn identity() -> Secp256k1ProjectivePoint {
+        Secp256k1ProjectivePoint {
+            x: new_secp256k1element_from_i32(0),
+            y: new_secp256k1element_from_i32(1),
+            z: new_secp256k1element_from_i32(0),
+        }
+    }
+
+    pub fn from_affine(p: &Secp256k1Point) -> Secp256k1ProjectivePoint {
+        if p.is_infinity() {
+            return Secp256k1ProjectivePoint::identity();
+        }
+        Secp256k1ProjectivePoint {
+            x: p.x,
+            y: p.y,
+            z: new_secp256k1element_from_i32(1),
+        }
+    }
+
+    // 結果は公開される値なので、ここでの分岐は問題ない
+    pub fn to_affine(self) -> Secp256k1Point {
+        if self.z.is_zero() {
+            return new_secp256k1point_infinity();
+        }
+        let z_inv = self.z.inv();
+        new_secp256k1point_from_element(self.x * z_inv, self.y * z_inv)
+    }
+
+    // mask が全ビット1なら a, 0なら b
+    pub fn select(
+        mask: u64,
+        a: &Secp256k1ProjectivePoint,
+        b: &Secp256k1ProjectivePoint,
+    ) -> Secp256k1ProjectivePoint {
+        Secp256k1ProjectivePoint {
+            x: Secp256k1Element::select(mask, &a.x, &b.x),
+            y: Secp256k1Element::select(mask, &a.y, &b.y),
+            z: Secp256k1Element::select(mask, &a.z, &b.z),
+        }
+    }
+
+    // RCB16 Algorithm 7 (a = 0)
+    pub fn add(&self, rhs: &Secp256k1ProjectivePoint) -> Secp256k1ProjectivePoint {
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (rhs.x, rhs.y, rhs.z);
+        let t0 = x1 * x2;
+        let t1 = y1 * y2;
+        let t2 = z1 * z2;
+        let t3 = (x1 + y1) * (x2 + y2);
+        let t3 = t3 - (t0 + t1);
+        let t4 = (y1 + z1) * (y2 + z2);
+        let t4 = t4 - (t1 + t2);
+        let y3 = (x1 + z1) * (x2 + z2);
+        let y3 = y3 - (t0 + t2);
+        let t0 = t0 + t0 + t0;
+        let t2 = B3 * t2;
+        let z3 = t1 + t2;
+        let t1 = t1 - t2;
+        let y3 = B3 * y3;
+        let x3 = t3 * t1 - t4 * y3;
+        let y3 = t1 * z3 + y3 * t0;
+        let z3 = z3 * t4 + t0 * t3;
+        Secp256k1ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    // RCB16 Algorithm 9 (a = 0)
+    pub fn double(&self) -> Secp256k1ProjectivePoint {
+        let (x, y, z) = (self.x, self.y, self.z);
+        let t0 = y.square();
+        let z3 = t0 + t0;
+        let z3 = z3 + z3;
+        let z3 = z3 + z3;
+        let t1 = y * z;
+        let t2 = B3 * z.square();
+        let x3 = t2 * z3;
+        let y3 = t0 + t2;
+        let z3 = t1 * z3;
+        let t2 = t2 + t2 + t2;
+        let t0 = t0 - t2;
+        let y3 = x3 + t0 * y3;
+        let x3 = t0 * (x * y);
+        let x3 = x3 + x3;
+        Secp256k1ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_point::new_secp256k1point_g;
+
+    #[test]
+    fn test_complete_addition() {
+        let g = new_secp256k1point_g();
+        let pg = Secp256k1ProjectivePoint::from_affine(&g);
+        let o = Secp256k1ProjectivePoint::identity();
+        // 場合分け無しで同じ点の加算・無限遠点との加算・逆元との加算が計算できる
+        assert_eq!(pg.add(&pg).to_affine(), g.clone().mul_from_u32(2));
+        assert_eq!(pg.double().to_affine(), g.clone().mul_from_u32(2));
+        assert_eq!(pg.add(&o).to_affine(), g);
+        assert_eq!(o.add(&pg).to_affine(), g);
+        assert!(o.double().to_affine().is_infinity());
+        let neg = Secp256k1ProjectivePoint::from_affine(&new_secp256k1point_from_element(g.x, -g.y));
+        assert!(pg.add(&neg).to_affine().is_infinity());
+        let p3 = pg.double().add(&pg);
+        assert_eq!(p3.to_affine(), g.clone().mul_from_u32(3));
+        assert_eq!(p3.double().add(&p3).to_affine(), g.mul_from_u32(9));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::ecc::secp256k1_ecmult::multi_mul;
+use crate::ecc::secp256k1_field::{new_secp256k1element_from_u256, SECP256K1_P};
+use crate::ecc::secp256k1_jacobian_point::Secp256k1JacobianPoint;
+use crate::ecc::secp256k1_point::{new_secp256k1point_from_x, new_secp256k1point_g, Secp256k1Point};
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, Secp256k1ScalarElement, SECP256K1_N,
+};
+use crate::ecc::secp256k1_signature::{new_secp256k1signature, Secp256k1Signature};
+use crate::ecc::u256::U256;
+
+// compact形式の先頭1byteは 27 + recovery_id (+4 圧縮公開鍵の場合)
+const COMPACT_HEADER_BASE: u8 = 27;
+
+// 公開鍵を復元できる署名。
+// recovery_id の下位ビットはRのyの偶奇、上位ビットはRのx座標がn以上だったかどうかを表す。
+#[derive(Clone)]
+pub struct Secp256k1RecoverableSignature {
+    pub(crate) sig: Secp256k1Signature,
+    pub(crate) recovery_id: u8,
+}
+
+pub fn new_secp256k1recoverablesignature(
+    sig: Secp256k1Signature,
+    recovery_id: u8,
+) -> Secp256k1RecoverableSignature {
+    Secp256k1RecoverableSignature { sig, recovery_id }
+}
+
+impl Secp256k1RecoverableSignature {
+    #[allow(dead_code)]
+    pub fn signature(self) -> Secp256k1Signature {
+        self.sig
+    }
+
+    // 65byte: header || r(32byte) || s(32byte)
+    #[allow(dead_code)]
+    pub fn compact(&self, compressed: bool) -> Vec<u8> {
+        let mut header = COMPACT_HEADER_BASE + self.recovery_id;
+        if compressed {
+            header += 4;
+        }
+        let mut result = vec![header];
+        result.extend_from_slice(&self.sig.r.value.to_be_bytes());
+        result.extend_from_slice(&self.sig.s.value.to_be_bytes());
+        result
+    }
+
+    // compact形式を読む。2つ目の値は公開鍵が圧縮形式かどうか
+    #[allow(dead_code)]
+    pub fn parse_compact(v: &[u8]) -> Option<(Secp256k1RecoverableSignature, bool)> {
+        if v.len() != 65 {
+            return None;
+        }
+        let header = v[0];
+        if !(COMPACT_HEADER_BASE..COMPACT_HEADER_BASE + 8).contains(&header) {
+            return None;
+        }
+        let compressed = header >= COMPACT_HEADER_BASE + 4;
+        let recovery_id = (header - COMPACT_HEADER_BASE) & 3;
+        let r = U256::from_be_slice(&v[1..33]).unwrap();
+        let s = U256::from_be_slice(&v[33..65]).unwrap();
+        if r >= SECP256K1_N || s >= SECP256K1_N {
+            return None;
+        }
+        let sig = new_secp256k1signature(
+            new_secp256k1scalarelement_from_u256(r),
+            new_secp256k1scalarelement_from_u256(s),
+        );
+        Some((new_secp256k1recoverablesignature(sig, recovery_id), compressed))
+    }
+
+    // 署名ハッシュzから公開鍵 Q = r^-1 (sR - zG) を復元する
+    #[allow(dead_code)]
+    pub fn recover(&self, z: Secp256k1ScalarElement) -> Option<Secp256k1Point> {
+        let r = self.sig.r.clone();
+        let s = self.sig.s.clone();
+        if r.is_zero() || s.is_zero() || self.recovery_id > 3 {
+            return None;
+        }
+        // Rのx座標はr、もしくはr + n (x座標がn以上でnで割った余りを取られていた場合)
+        let mut x = r.value;
+        if self.recovery_id & 2 != 0 {
+            let (sum, carry) = x.overflowing_add(&SECP256K1_N);
+            if carry != 0 {
+                return None;
+            }
+            x = sum;
+        }
+        if x >= SECP256K1_P {
+            return None;
+        }
+        let r_point = new_secp256k1point_from_x(
+            new_secp256k1element_from_u256(x),
+            self.recovery_id & 1 == 1,
+        )?;
+        let r_inv = r.inv();
+        let u1 = -(z * r_inv.clone());
+        let u2 = s * r_inv;
+        let q = multi_mul(
+            &[
+                Secp256k1JacobianPoint::from_affine(&new_secp256k1point_g()),
+                Secp256k1JacobianPoint::from_affine(&r_point),
+            ],
+            &[u1.value, u2.value],
+        );
+        if q.is_infinity() {
+            return None;
+        }
+        Some(q.to_affine())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey_from_i32;
+    use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement_from_u64;
+
+    #[test]
+    fn test_recover() {
+        for secret in [1, 2, 12345, 0x7fffffff].iter() {
+            let key = new_secp_256k1privatekey_from_i32(*secret);
+            for i in 0..8u64 {
+                let z = new_secp256k1scalarelement_from_u64(0x1234567 * i + 1);
+                let sig = key.clone().sign_recoverable(z.clone());
+                assert_eq!(sig.recover(z.clone()), Some(key.point.clone()));
+                // compact形式を経由しても復元できる
+                let (parsed, compressed) =
+                    Secp256k1RecoverableSignature::parse_compact(&sig.compact(true)).unwrap();
+                assert!(compressed);
+                assert_eq!(parsed.recovery_id, sig.recovery_id);
+                assert_eq!(parsed.recover(z.clone()), Some(key.point.clone()));
+                // 別のzでは別の公開鍵になる
+                assert_ne!(sig.recover(z + new_secp256k1scalarelement_from_u64(1)), Some(key.point.clone()));
+                assert!(key.point.clone().verify(
+                    new_secp256k1scalarelement_from_u64(0x1234567 * i + 1),
+                    sig.signature()
+                ));
+            }
+        }
+        assert!(Secp256k1RecoverableSignature::parse_compact(&[27u8; 64]).is_none());
+        assert!(Secp256k1RecoverableSignature::parse_compact(&[26u8; 65]).is_none());
+    }
+}
@@ -1,27 +1,52 @@
+use crate::ecc::u256::{Modulus, U256};
 use num_bigint::BigUint;
-use num_traits::{FromPrimitive, Num, One, Zero};
+use num_traits::{FromPrimitive, Num, One};
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
 
 // 楕円曲線上の加算に対しての有限体の位数はこの値となる。
+pub(crate) const SECP256K1_N: U256 = U256::from_limbs([
+    0xBFD25E8CD0364141,
+    0xBAAEDCE6AF48A03B,
+    0xFFFFFFFFFFFFFFFE,
+    0xFFFFFFFFFFFFFFFF,
+]);
+// 2^256 - n
+const SCALAR: Modulus = Modulus::new(
+    SECP256K1_N,
+    U256::from_limbs([0x402DA1732FC9BEBF, 0x4551231950B75FC4, 0x1, 0]),
+);
+// n / 2 (これより大きいsはhigh-s)
+const HALF_N: U256 = U256::from_limbs([
+    0xDFE92F46681B20A0,
+    0x5D576E7357A4501D,
+    0xFFFFFFFFFFFFFFFF,
+    0x7FFFFFFFFFFFFFFF,
+]);
+
 fn prime() -> BigUint {
-    return BigUint::from_str_radix(
-        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
-        16,
-    )
-    .unwrap();
+    SECP256K1_N.to_biguint()
 }
 
-// Debugの自動実装
-#[derive(Debug, Clone)]
+// 秘密鍵やnonceにも使うので、Debugでは値を出さない。値が必要ならDisplayかnum()を使う
+#[derive(Clone)]
 pub struct Secp256k1ScalarElement {
-    pub num: BigUint,
+    // 常に [0, n) に収まっている
+    pub(crate) value: U256,
+}
+
+impl fmt::Debug for Secp256k1ScalarElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secp256k1ScalarElement")
+            .field("value", &"[REDACTED]")
+            .finish()
+    }
 }
 
 impl PartialEq for Secp256k1ScalarElement {
     fn eq(&self, other: &Self) -> bool {
-        return self.num == other.num;
+        self.value == other.value
     }
 }
 
@@ -31,7 +56,7 @@ impl Add for Secp256k1ScalarElement {
     // 左側のprimeに依存させる。
     fn add(self, rhs: Secp256k1ScalarElement) -> Secp256k1ScalarElement {
         Self::Output {
-            num: (self.num + rhs.num) % prime(),
+            value: SCALAR.add(&self.value, &rhs.value),
         }
     }
 }
@@ -41,7 +66,7 @@ impl Sub for Secp256k1ScalarElement {
 
     fn sub(self, rhs: Secp256k1ScalarElement) -> Secp256k1ScalarElement {
         Self::Output {
-            num: (self.num + prime() - rhs.num) % prime(),
+            value: SCALAR.sub(&self.value, &rhs.value),
         }
     }
 }
@@ -51,78 +76,81 @@ impl Mul for Secp256k1ScalarElement {
 
     fn mul(self, rhs: Secp256k1ScalarElement) -> Secp256k1ScalarElement {
         Self::Output {
-            num: (self.num * rhs.num) % prime(),
+            value: SCALAR.mul(&self.value, &rhs.value),
         }
     }
 }
 
-impl Rem for Secp256k1ScalarElement {
+impl Neg for Secp256k1ScalarElement {
     type Output = Secp256k1ScalarElement;
 
-    fn rem(self, rhs: Secp256k1ScalarElement) -> Secp256k1ScalarElement {
+    fn neg(self) -> Secp256k1ScalarElement {
         Self::Output {
-            num: (self.num % rhs.num) % prime(),
+            value: SCALAR.neg(&self.value),
         }
     }
 }
 
+impl Rem for Secp256k1ScalarElement {
+    type Output = Secp256k1ScalarElement;
+
+    fn rem(self, rhs: Secp256k1ScalarElement) -> Secp256k1ScalarElement {
+        new_secp256k1scalarelement(self.num() % rhs.num())
+    }
+}
+
 // TODO: どうにかして実装したい。
 impl Secp256k1ScalarElement {
-    fn inner_pow(self, f: Secp256k1ScalarElement, exp: BigUint) -> Secp256k1ScalarElement {
-        if exp.clone() == BigUint::zero() {
-            return Secp256k1ScalarElement { num: One::one() };
-        }
-        if exp.clone() % BigUint::from(2u32) == BigUint::zero() {
-            return self.inner_pow(
-                Secp256k1ScalarElement {
-                    num: (f.num.clone() * f.num.clone()) % prime(),
-                },
-                exp.clone() / BigUint::from(2u32),
-            );
-        }
-        f.clone()
-            * f.clone().inner_pow(
-                Secp256k1ScalarElement {
-                    num: (f.num.clone() * f.num.clone()) % prime(),
-                },
-                (exp.clone() - BigUint::one()) / BigUint::from(2u32),
-            )
+    pub fn num(&self) -> BigUint {
+        self.value.to_biguint()
     }
+
+    #[allow(dead_code)]
     // rem_euclidを使って負数でもよしなに整数値に変更する。
     pub fn pow(self, exp: BigUint) -> Secp256k1ScalarElement {
-        self.clone()
-            .inner_pow(self, exp % (prime() - BigUint::one()))
+        let exp = exp % (prime() - BigUint::one());
+        let exp = U256::from_biguint(&exp).unwrap();
+        Secp256k1ScalarElement {
+            value: SCALAR.pow(&self.value, &exp),
+        }
     }
 
     // フェルマーの小定理からインバースを実装する。 位数が素数で無い場合は正しく動作しない
+    // 指数n-2は公開値なので、実行時間はselfの値に依存しない(署名のk^-1に使っている)
     pub fn inv(self) -> Secp256k1ScalarElement {
-        return self.pow(prime() - BigUint::from(2u32));
+        Secp256k1ScalarElement {
+            value: SCALAR.inv(&self.value),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+
+    // n/2 より大きいかどうか (low-s 判定用)
+    pub fn is_high(&self) -> bool {
+        self.value > HALF_N
     }
 
     #[inline]
     pub fn to_32_bytes_be(&self) -> Option<Vec<u8>> {
-        let mut bin = self.num.to_bytes_be();
-        if bin.len() > 32 {
-            return None;
-        }
-        // FIXME: より効率的に
-        while bin.len() != 32 {
-            bin.insert(0, 0u8);
-        }
-        Some(bin)
+        Some(self.value.to_be_bytes().to_vec())
     }
 
     #[inline]
+    #[allow(dead_code)]
     pub fn to_n_bytes_be(&self, n: usize) -> Option<Vec<u8>> {
-        let mut bin = self.num.to_bytes_be();
-        if bin.len() > n {
-            return None;
+        let bin = self.value.to_be_bytes();
+        if n >= 32 {
+            let mut ret = vec![0u8; n - 32];
+            ret.extend_from_slice(&bin);
+            return Some(ret);
         }
-        // FIXME: より効率的に
-        while bin.len() != n {
-            bin.insert(0, 0u8);
+        if bin[..32 - n].iter().any(|b| *b != 0) {
+            return None;
         }
-        Some(bin)
+        Some(bin[32 - n..].to_vec())
     }
 }
 
@@ -136,23 +164,39 @@ impl Div for Secp256k1ScalarElement {
 
 impl Display for Secp256k1ScalarElement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.num)
+        write!(f, "{}", self.num())
     }
 }
 
 pub fn new_secp256k1scalarelement(num: BigUint) -> Secp256k1ScalarElement {
-    Secp256k1ScalarElement { num: num % prime() }
+    Secp256k1ScalarElement {
+        value: U256::from_biguint(&(num % prime())).unwrap(),
+    }
+}
+
+// 256bitの値から作る。n以上の値はnで割った余りになる
+pub fn new_secp256k1scalarelement_from_u256(value: U256) -> Secp256k1ScalarElement {
+    Secp256k1ScalarElement {
+        value: SCALAR.reduce(&value),
+    }
+}
+
+// 32byte big endianから作る。n以上の値はnで割った余りになる
+#[allow(dead_code)]
+pub fn new_secp256k1scalarelement_from_bytes_be(bytes: &[u8; 32]) -> Secp256k1ScalarElement {
+    new_secp256k1scalarelement_from_u256(U256::from_be_bytes(bytes))
 }
 
 pub fn new_secp256k1scalarelement_from_i32(num: i32) -> Secp256k1ScalarElement {
+    let num = BigUint::from_i32(num).unwrap();
     Secp256k1ScalarElement {
-        num: BigUint::from_i32(num).unwrap(),
+        value: U256::from_biguint(&num).unwrap(),
     }
 }
 
 pub fn new_secp256k1scalarelement_from_u64(num: u64) -> Secp256k1ScalarElement {
     Secp256k1ScalarElement {
-        num: BigUint::from_u64(num).unwrap(),
+        value: U256::from_u64(num),
     }
 }
 
@@ -190,4 +234,29 @@ mod tests {
             println!("a % b = {}", a.clone() % b.clone());
         }
     }
+
+    #[test]
+    fn test_secp256k1_scalar_constants() {
+        assert_eq!(HALF_N.to_biguint(), prime() / BigUint::from(2u8));
+        let a = new_secp256k1scalarelement_from_hex_str(
+            "c7207fee197d27c618aea621406f6bf5ef6fca38681d82b2f06fddbdce6feab6",
+        )
+        .unwrap();
+        assert_eq!(a.clone() / a.clone(), new_secp256k1scalarelement_from_i32(1));
+        assert!(a.is_high());
+        assert!(!(-a).is_high());
+        assert_eq!(
+            new_secp256k1scalarelement_from_i32(5).to_n_bytes_be(25).unwrap()[24],
+            5
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_scalar_debug_redacted() {
+        let a = new_secp256k1scalarelement(BigUint::from(12345u32));
+        let debug = format!("{:?}", a);
+        assert!(debug.contains("[REDACTED]"));
+        assert!(!debug.contains("12345"));
+        assert_eq!(a.to_string(), "12345");
+    }
 }
@@ -0,0 +1,125 @@
+use crate::ecc::secp256k1_field::{new_secp256k1element_from_u256, Secp256k1Element, SECP256K1_P};
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, Secp256k1ScalarElement, SECP256K1_N,
+};
+use crate::ecc::u256::U256;
+use crate::helper::helper::tagged_hash;
+
+// BIP340 Schnorr署名 (R.x(32byte) || s(32byte))
+#[derive(Clone, Debug, PartialEq)]
+pub struct Secp256k1SchnorrSignature {
+    // Rのx座標 (Rのyは常に偶数)
+    pub(crate) r: Secp256k1Element,
+    pub(crate) s: Secp256k1ScalarElement,
+}
+
+pub fn new_secp256k1schnorrsignature(
+    r: Secp256k1Element,
+    s: Secp256k1ScalarElement,
+) -> Secp256k1SchnorrSignature {
+    Secp256k1SchnorrSignature { r, s }
+}
+
+impl Secp256k1SchnorrSignature {
+    #[allow(dead_code)]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = self.r.value.to_be_bytes().to_vec();
+        result.extend_from_slice(&self.s.value.to_be_bytes());
+        result
+    }
+
+    // r >= p, s >= n の場合は不正な署名としてNoneを返す
+    #[allow(dead_code)]
+    pub fn parse(v: &[u8]) -> Option<Secp256k1SchnorrSignature> {
+        if v.len() != 64 {
+            return None;
+        }
+        let r = U256::from_be_slice(&v[..32]).unwrap();
+        let s = U256::from_be_slice(&v[32..]).unwrap();
+        if r >= SECP256K1_P || s >= SECP256K1_N {
+            return None;
+        }
+        Some(new_secp256k1schnorrsignature(
+            new_secp256k1element_from_u256(r),
+            new_secp256k1scalarelement_from_u256(s),
+        ))
+    }
+}
+
+pub(crate) fn hash_to_scalar(tag: &str, data: &[u8]) -> Secp256k1ScalarElement {
+    let hash = tagged_hash(tag, data);
+    new_secp256k1scalarelement_from_u256(U256::from_be_slice(&hash).unwrap())
+}
+
+// e = hash_BIP0340/challenge(R.x || P.x || m) mod n
+pub(crate) fn schnorr_challenge(r_x: &[u8], p_x: &[u8], msg: &[u8]) -> Secp256k1ScalarElement {
+    let mut data = r_x.to_vec();
+    data.extend_from_slice(p_x);
+    data.extend_from_slice(msg);
+    hash_to_scalar("BIP0340/challenge", &data)
+}
+
+// k' = hash_BIP0340/nonce(d xor hash_BIP0340/aux(a) || P.x || m) mod n
+pub(crate) fn schnorr_nonce(
+    d: &Secp256k1ScalarElement,
+    p_x: &[u8],
+    msg: &[u8],
+    aux_rand: &[u8; 32],
+) -> Secp256k1ScalarElement {
+    let aux = tagged_hash("BIP0340/aux", aux_rand);
+    let mut data: Vec<u8> = d
+        .value
+        .to_be_bytes()
+        .iter()
+        .zip(aux.iter())
+        .map(|(a, b)| a ^ b)
+        .collect();
+    data.extend_from_slice(p_x);
+    data.extend_from_slice(msg);
+    hash_to_scalar("BIP0340/nonce", &data)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_point::Secp256k1Point;
+    use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey;
+    use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement_from_bytes_be;
+    use crate::helper::helper::decode_hex;
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+    const BIP340_TEST_VECTORS: &str = include_str!("testdata/bip340_test_vectors.csv");
+
+    #[test]
+    fn test_bip340_vectors() {
+        for line in BIP340_TEST_VECTORS.lines().skip(1) {
+            let cols: Vec<&str> = line.splitn(8, ',').collect();
+            let index = cols[0];
+            let public_key = decode_hex(cols[2]).unwrap();
+            let message = decode_hex(cols[4]).unwrap();
+            let signature = decode_hex(cols[5]).unwrap();
+            let expected = cols[6] == "TRUE";
+
+            if !cols[1].is_empty() {
+                let mut secret = [0u8; 32];
+                secret.copy_from_slice(&decode_hex(cols[1]).unwrap());
+                let mut aux_rand = [0u8; 32];
+                aux_rand.copy_from_slice(&decode_hex(cols[3]).unwrap());
+                let key = new_secp_256k1privatekey(new_secp256k1scalarelement_from_bytes_be(&secret));
+                assert_eq!(key.point.x_only(), public_key, "public key of index {}", index);
+                let sig = key.sign_schnorr(&message, &aux_rand).unwrap();
+                assert_eq!(sig.serialize(), signature, "signature of index {}", index);
+            }
+
+            let valid = match (
+                Secp256k1Point::parse_x_only(&public_key),
+                Secp256k1SchnorrSignature::parse(&signature),
+            ) {
+                (Some(point), Some(sig)) => point.verify_schnorr(&message, &sig),
+                _ => false,
+            };
+            assert_eq!(valid, expected, "verification of index {}", index);
+        }
+    }
+}
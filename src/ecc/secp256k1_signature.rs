@@ -1,16 +1,13 @@
 use crate::ecc::secp256k1_scalar_element::{
-    new_secp256k1scalarelement, new_secp256k1scalarelement_from_hex_str, Secp256k1ScalarElement,
+    new_secp256k1scalarelement_from_hex_str, new_secp256k1scalarelement_from_u256,
+    Secp256k1ScalarElement, SECP256K1_N,
 };
+use crate::ecc::u256::U256;
 
 use crate::helper::helper::{biguint_to_32_bytes_be, lstip_bytes};
 
-use num_bigint::BigUint;
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::io::{Cursor, Read};
-
-#[cfg(test)]
-use crate::helper::helper::u8vec_to_str;
 
 #[derive(Clone)]
 pub struct Secp256k1Signature {
@@ -34,68 +31,70 @@ impl Secp256k1Signature {
         return ret;
     }
 
+    // BIP66の厳格なDERとして読む。r, s は [1, n-1] に収まっていなければならない。
+    // der_signatureの末尾にsighashの1byteは含めない
     #[allow(dead_code)]
-    pub fn parse(der_signature: Vec<u8>) -> Secp256k1Signature {
-        #[cfg(test)]
-        println!("SIGNATURE PARSE: {}",u8vec_to_str(der_signature.clone()));
-        let mut s = Cursor::new(der_signature.clone());
-
-        let mut bytes = [0u8; 1];
-        s.read(&mut bytes);
-        let compound = bytes[0];
-
-        if compound != 0x30 {
-            panic!("Bad Signature")
+    pub fn parse(der_signature: Vec<u8>) -> Result<Secp256k1Signature, DerSignatureError> {
+        let sig = &der_signature;
+        // 0x30 len 0x02 rlen r 0x02 slen s (r, s はそれぞれ1〜33byte)
+        if sig.len() < 8 || sig.len() > 72 {
+            return Err(DerSignatureError::InvalidLength);
         }
-
-        s.read(&mut bytes);
-        let length = bytes[0];
-        if (length + 2) as usize != der_signature.len() {
-            panic!(
-                "Bad Signature Length, expected: (len+2): {} , but got: {}",
-                length + 2,
-                der_signature.len()
-            )
+        if sig[0] != 0x30 {
+            return Err(DerSignatureError::InvalidSequenceTag);
         }
-
-        s.read(&mut bytes);
-        let marker = bytes[0];
-        if marker != 0x02 {
-            panic!("Bad Signature")
+        if sig[1] as usize != sig.len() - 2 {
+            return Err(DerSignatureError::InvalidLength);
         }
-
-        s.read(&mut bytes);
-        let rlength = bytes[0];
-
-        let mut sret: Vec<u8> = vec![0u8; rlength as usize];
-        s.read(&mut *sret);
-        let r = BigUint::from_bytes_be(&*sret);
-        let r = new_secp256k1scalarelement(r);
-
-        s.read(&mut bytes);
-        let marker = bytes[0];
-        if marker != 0x02 {
-            panic!("Bad Signature")
+        let rlength = sig[3] as usize;
+        if 5 + rlength >= sig.len() {
+            return Err(DerSignatureError::InvalidLength);
         }
+        let slength = sig[5 + rlength] as usize;
+        if rlength + slength + 6 != sig.len() {
+            return Err(DerSignatureError::InvalidLength);
+        }
+        check_strict_integer(&sig[2..4 + rlength])?;
+        check_strict_integer(&sig[4 + rlength..])?;
+        let r = &sig[4..4 + rlength];
+        let s = &sig[6 + rlength..];
+        new_secp256k1signature_from_der_integers(r, s)
+    }
 
-        s.read(&mut bytes);
-        let slength = bytes[0];
-
-        let mut sret: Vec<u8> = vec![0u8; slength as usize];
-        s.read(&mut *sret);
-        let s = BigUint::from_bytes_be(&*sret);
-        let s = new_secp256k1scalarelement(s);
-        #[cfg(test)]
-        println!("SIG_PARSE r: {}, s: {}",r.clone(),s.clone());
-        return new_secp256k1signature(r, s);
+    // OpenSSL時代の緩いDERとして読む (Bitcoin Coreの ecdsa_signature_parse_der_lax と同じ)。
+    // BIP66以前のトランザクションの検証に使う。長さの不一致・負の数・余分な0・後ろのゴミは許容する
+    #[allow(dead_code)]
+    pub fn parse_lax(der_signature: &[u8]) -> Result<Secp256k1Signature, DerSignatureError> {
+        let input = der_signature;
+        let mut pos = 0usize;
+        if pos == input.len() || input[pos] != 0x30 {
+            return Err(DerSignatureError::InvalidSequenceTag);
+        }
+        pos += 1;
+        // シーケンスの長さは読み飛ばす
+        if pos == input.len() {
+            return Err(DerSignatureError::InvalidLength);
+        }
+        let length = input[pos] as usize;
+        pos += 1;
+        if length & 0x80 != 0 {
+            let length = length - 0x80;
+            if length > input.len() - pos {
+                return Err(DerSignatureError::InvalidLength);
+            }
+            pos += length;
+        }
+        let (r, next) = read_lax_integer(input, pos)?;
+        let (s, _) = read_lax_integer(input, next)?;
+        new_secp256k1signature_from_der_integers(r, s)
     }
 
     #[allow(dead_code)]
     pub fn der(self) -> Vec<u8> {
         let prefix_marker = 0x30u8;
-        let mut rbin = biguint_to_32_bytes_be(self.clone().r.num).to_vec();
+        let mut rbin = biguint_to_32_bytes_be(self.clone().r.num()).to_vec();
         #[cfg(test)]
-        println!("r: {}",self.r.clone().num.clone());
+        println!("r: {}",self.r.clone().num().clone());
         rbin = lstip_bytes(rbin, 0);
         if (rbin[0] & 0x80u8) > 0 {
             rbin.insert(0, 0);
@@ -103,7 +102,7 @@ impl Secp256k1Signature {
         let mut result: Vec<u8> = vec![2, rbin.clone().len() as u8];
         result.append(&mut rbin);
 
-        let mut sbin = biguint_to_32_bytes_be(self.s.num).to_vec();
+        let mut sbin = biguint_to_32_bytes_be(self.s.num()).to_vec();
         sbin = lstip_bytes(sbin, 0);
         if (sbin[0] & 0x80u8) > 0 {
             sbin.insert(0, 0);
@@ -120,6 +119,113 @@ impl Secp256k1Signature {
     }
 }
 
+// DER署名を読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum DerSignatureError {
+    // 全体の長さ、もしくはシーケンスや整数の長さが合わない
+    InvalidLength,
+    // 先頭が0x30(シーケンス)ではない
+    InvalidSequenceTag,
+    // 整数のタグが0x02ではない
+    InvalidIntegerTag,
+    // 長さ0の整数
+    ZeroLengthInteger,
+    // 先頭ビットが立っている(負の数)
+    NegativeInteger,
+    // 不要な0x00が先頭に付いている
+    ExcessivePadding,
+    // r, s が [1, n-1] に収まっていない
+    OutOfRange,
+}
+
+impl Display for DerSignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            DerSignatureError::InvalidLength => "invalid length",
+            DerSignatureError::InvalidSequenceTag => "expected a DER sequence (0x30)",
+            DerSignatureError::InvalidIntegerTag => "expected a DER integer (0x02)",
+            DerSignatureError::ZeroLengthInteger => "zero-length integer",
+            DerSignatureError::NegativeInteger => "negative integer",
+            DerSignatureError::ExcessivePadding => "integer has excessive padding",
+            DerSignatureError::OutOfRange => "r or s is not in [1, n-1]",
+        };
+        write!(f, "bad DER signature: {}", message)
+    }
+}
+
+impl std::error::Error for DerSignatureError {}
+
+// 0x02 len value の形の整数がBIP66の規則を満たしているか確認する
+fn check_strict_integer(v: &[u8]) -> Result<(), DerSignatureError> {
+    if v[0] != 0x02 {
+        return Err(DerSignatureError::InvalidIntegerTag);
+    }
+    let value = &v[2..];
+    if value.is_empty() {
+        return Err(DerSignatureError::ZeroLengthInteger);
+    }
+    if value[0] & 0x80 != 0 {
+        return Err(DerSignatureError::NegativeInteger);
+    }
+    // 次のbyteの先頭ビットが立っていないのに0x00を付けるのは冗長
+    if value.len() > 1 && value[0] == 0 && value[1] & 0x80 == 0 {
+        return Err(DerSignatureError::ExcessivePadding);
+    }
+    Ok(())
+}
+
+// posから 0x02 len value を読み、valueと次の位置を返す。長さは長形式(0x8X)も受け付ける
+fn read_lax_integer(input: &[u8], mut pos: usize) -> Result<(&[u8], usize), DerSignatureError> {
+    if pos == input.len() || input[pos] != 0x02 {
+        return Err(DerSignatureError::InvalidIntegerTag);
+    }
+    pos += 1;
+    if pos == input.len() {
+        return Err(DerSignatureError::InvalidLength);
+    }
+    let mut length = input[pos] as usize;
+    pos += 1;
+    if length & 0x80 != 0 {
+        let mut length_bytes = length - 0x80;
+        if length_bytes > input.len() - pos {
+            return Err(DerSignatureError::InvalidLength);
+        }
+        while length_bytes > 0 && input[pos] == 0 {
+            pos += 1;
+            length_bytes -= 1;
+        }
+        if length_bytes >= std::mem::size_of::<usize>() {
+            return Err(DerSignatureError::InvalidLength);
+        }
+        length = 0;
+        while length_bytes > 0 {
+            length = (length << 8) + input[pos] as usize;
+            pos += 1;
+            length_bytes -= 1;
+        }
+    }
+    if length > input.len() - pos {
+        return Err(DerSignatureError::InvalidLength);
+    }
+    Ok((&input[pos..pos + length], pos + length))
+}
+
+// 先頭の0を取り除いたビッグエンディアンの r, s から署名を作る
+fn new_secp256k1signature_from_der_integers(r: &[u8], s: &[u8]) -> Result<Secp256k1Signature, DerSignatureError> {
+    let mut values = vec![];
+    for v in [r, s].iter() {
+        let first = v.iter().position(|b| *b != 0).unwrap_or(v.len());
+        let value = U256::from_be_slice(&v[first..]).ok_or(DerSignatureError::OutOfRange)?;
+        if value.is_zero() || value >= SECP256K1_N {
+            return Err(DerSignatureError::OutOfRange);
+        }
+        values.push(new_secp256k1scalarelement_from_u256(value));
+    }
+    let s = values.pop().unwrap();
+    let r = values.pop().unwrap();
+    Ok(new_secp256k1signature(r, s))
+}
+
 #[allow(dead_code)]
 pub fn new_secp256k1signature_from_str(r: &str, s: &str) -> Option<Secp256k1Signature> {
     let r = new_secp256k1scalarelement_from_hex_str(r);
@@ -149,6 +255,7 @@ mod tests {
         new_secp256k1scalarelement, new_secp256k1scalarelement_from_i32,
         new_secp256k1scalarelement_from_u64,
     };
+    use crate::helper::helper::decode_hex;
     use crypto_hash::{digest, hex_digest, Algorithm};
 
     #[test]
@@ -159,22 +266,22 @@ mod tests {
         let z = new_secp256k1scalarelement_from_hex_str(&*z).unwrap();
         let k = new_secp256k1scalarelement_from_u64(1234567890u64);
         let g = new_secp256k1point_g().mul_from_sec256k1scalar_element(k.clone());
-        let r = new_secp256k1scalarelement(g.clone().x.num);
+        let r = new_secp256k1scalarelement(g.clone().x.num());
         let s = (z.clone() + (r.clone() * e)) / k.clone();
-        println!("z: {}", z.num.to_str_radix(16));
+        println!("z: {}", z.num().to_str_radix(16));
         assert_eq!(
             "969f6056aa26f7d2795fd013fe88868d09c9f6aed96965016e1936ae47060d48",
-            z.num.to_str_radix(16)
+            z.num().to_str_radix(16)
         );
-        println!("r: {}", r.num.to_str_radix(16));
+        println!("r: {}", r.num().to_str_radix(16));
         assert_eq!(
             "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
-            r.num.to_str_radix(16)
+            r.num().to_str_radix(16)
         );
-        println!("s: {}", s.num.to_str_radix(16));
+        println!("s: {}", s.num().to_str_radix(16));
         assert_eq!(
             "1dbc63bfef4416705e602a7b564161167076d8b20990a0f26f316cff2cb0bc1a",
-            s.num.to_str_radix(16)
+            s.num().to_str_radix(16)
         );
     }
 
@@ -191,4 +298,65 @@ mod tests {
         let sig = new_secp256k1signature(r, s);
         println!("sig: {}", sig.der_str());
     }
+
+    #[test]
+    fn test_parse_der_strict_and_lax() {
+        let r = "37206a0610995c58074999cb9767b87af4c4978db68c06e8e6e81d282047a7c6";
+        let s = "8ca63759c1157ebeaec0d03cecca119fc9a75bf8e6d0fa65c841c8e2738cdaec";
+        let expected = new_secp256k1signature_from_str(r, s).unwrap();
+        let der = expected.clone().der();
+        let check = |sig: Secp256k1Signature| {
+            assert_eq!(sig.r, expected.r);
+            assert_eq!(sig.s, expected.s);
+        };
+        check(Secp256k1Signature::parse(der.clone()).unwrap());
+        check(Secp256k1Signature::parse_lax(&der).unwrap());
+
+        // (DER, 厳格なDERでのエラー, 緩いDERで読めるか)
+        let hex = |v: String| decode_hex(&v).unwrap();
+        let cases = vec![
+            // 後ろにゴミがある
+            (hex(format!("3045022037206a{}022100{}01", &r[6..], s)), DerSignatureError::InvalidLength, true),
+            // シーケンスの長さが合わない
+            (hex(format!("3046022037206a{}022100{}", &r[6..], s)), DerSignatureError::InvalidLength, true),
+            // シーケンスの長さが長形式
+            (hex(format!("308145022037206a{}022100{}", &r[6..], s)), DerSignatureError::InvalidLength, true),
+            // rに不要な0x00が付いている
+            (hex(format!("3046022100{}022100{}", r, s)), DerSignatureError::ExcessivePadding, true),
+            // sが負の数
+            (hex(format!("30440220{}0220{}", r, s)), DerSignatureError::NegativeInteger, true),
+            // 長さ0の整数
+            (hex("300702000203010000".to_string()), DerSignatureError::ZeroLengthInteger, false),
+            // シーケンスではない
+            (hex(format!("3145022037206a{}022100{}", &r[6..], s)), DerSignatureError::InvalidSequenceTag, false),
+            // 整数のタグが違う
+            (hex(format!("3045032037206a{}022100{}", &r[6..], s)), DerSignatureError::InvalidIntegerTag, false),
+            // 途中で切れている
+            (der[..der.len() - 1].to_vec(), DerSignatureError::InvalidLength, false),
+            (vec![], DerSignatureError::InvalidLength, false),
+        ];
+        for (i, (der, error, lax)) in cases.into_iter().enumerate() {
+            assert_eq!(Secp256k1Signature::parse(der.clone()).err(), Some(error), "case {}", i);
+            match Secp256k1Signature::parse_lax(&der) {
+                Ok(sig) => {
+                    assert!(lax, "case {}", i);
+                    if i != 4 {
+                        check(sig);
+                    }
+                }
+                Err(_) => assert!(!lax, "case {}", i),
+            }
+        }
+
+        // r = 0, s = n はどちらの形式でも範囲外
+        let zero_r = hex("3006020100020101".to_string());
+        assert_eq!(Secp256k1Signature::parse(zero_r.clone()).err(), Some(DerSignatureError::OutOfRange));
+        assert_eq!(Secp256k1Signature::parse_lax(&zero_r).err(), Some(DerSignatureError::OutOfRange));
+        let n_s = hex(format!(
+            "3026020101022100{}",
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+        ));
+        assert_eq!(Secp256k1Signature::parse(n_s.clone()).err(), Some(DerSignatureError::OutOfRange));
+        assert_eq!(Secp256k1Signature::parse_lax(&n_s).err(), Some(DerSignatureError::OutOfRange));
+    }
 }
@@ -0,0 +1,203 @@
+use crate::ecc::bech32::{bech32_decode, bech32_encode, convert_bits, Bech32Error, Bech32Variant};
+use crate::ecc::network::Network;
+use crate::helper::helper::sha256;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+// BIP173/BIP350 のSegWitアドレス (witness version + witness program)
+
+// SegWitアドレスを作れなかった、もしくは読めなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegwitAddressError {
+    Bech32(Bech32Error),
+    // bc, tb, bcrt 以外のHRP
+    UnknownHrp(String),
+    // データ部が空
+    EmptyData,
+    // witness version が16を超える
+    InvalidWitnessVersion(u8),
+    // witness program が2〜40byteでない、またはversion 0で20byteでも32byteでもない
+    InvalidProgramLength(usize),
+    // version 0 は Bech32、version 1以降は Bech32m でなければならない
+    InvalidVariant,
+    // 5bitから8bitに詰め直した時に余りのbitが不正
+    InvalidPadding,
+}
+
+impl Display for SegwitAddressError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SegwitAddressError::Bech32(e) => write!(f, "bad segwit address: {}", e),
+            SegwitAddressError::UnknownHrp(hrp) => write!(f, "bad segwit address: unknown hrp {:?}", hrp),
+            SegwitAddressError::EmptyData => write!(f, "bad segwit address: empty data"),
+            SegwitAddressError::InvalidWitnessVersion(v) => {
+                write!(f, "bad segwit address: invalid witness version {}", v)
+            }
+            SegwitAddressError::InvalidProgramLength(len) => {
+                write!(f, "bad segwit address: invalid witness program length {}", len)
+            }
+            SegwitAddressError::InvalidVariant => {
+                write!(f, "bad segwit address: wrong checksum variant for the witness version")
+            }
+            SegwitAddressError::InvalidPadding => write!(f, "bad segwit address: invalid padding"),
+        }
+    }
+}
+
+impl std::error::Error for SegwitAddressError {}
+
+fn check_program(version: u8, program: &[u8]) -> Result<(), SegwitAddressError> {
+    if version > 16 {
+        return Err(SegwitAddressError::InvalidWitnessVersion(version));
+    }
+    if program.len() < 2 || program.len() > 40 || (version == 0 && program.len() != 20 && program.len() != 32) {
+        return Err(SegwitAddressError::InvalidProgramLength(program.len()));
+    }
+    Ok(())
+}
+
+pub fn encode_segwit_address(network: Network, version: u8, program: &[u8]) -> Result<String, SegwitAddressError> {
+    check_program(version, program)?;
+    let variant = if version == 0 {
+        Bech32Variant::Bech32
+    } else {
+        Bech32Variant::Bech32m
+    };
+    let mut data = vec![version];
+    data.append(&mut convert_bits(program, 8, 5, true).unwrap());
+    bech32_encode(network.bech32_hrp(), &data, variant).map_err(SegwitAddressError::Bech32)
+}
+
+// (ネットワーク, witness version, witness program) を返す。signetのアドレスはTestnetになる
+#[allow(dead_code)]
+pub fn decode_segwit_address(addr: &str) -> Result<(Network, u8, Vec<u8>), SegwitAddressError> {
+    let (hrp, data, variant) = bech32_decode(addr).map_err(SegwitAddressError::Bech32)?;
+    let network = Network::from_bech32_hrp(&hrp).ok_or(SegwitAddressError::UnknownHrp(hrp))?;
+    if data.is_empty() {
+        return Err(SegwitAddressError::EmptyData);
+    }
+    let version = data[0];
+    let program = convert_bits(&data[1..], 5, 8, false).ok_or(SegwitAddressError::InvalidPadding)?;
+    check_program(version, &program)?;
+    let expected = if version == 0 {
+        Bech32Variant::Bech32
+    } else {
+        Bech32Variant::Bech32m
+    };
+    if variant != expected {
+        return Err(SegwitAddressError::InvalidVariant);
+    }
+    Ok((network, version, program))
+}
+
+// P2WSHのアドレス。witness programはwitness scriptのsha256
+#[allow(dead_code)]
+pub fn p2wsh_address(witness_script: &[u8], network: Network) -> String {
+    encode_segwit_address(network, 0, &sha256(witness_script.to_vec())).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_point::Secp256k1Point;
+    use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey_from_i32;
+    use crate::helper::helper::decode_hex;
+
+    #[test]
+    fn test_bip350_addresses() {
+        // (アドレス, scriptPubKey)
+        let valid = [
+            ("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            ("BC1SW50QGDZ25J", "6002751e"),
+            ("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", "5210751e76e8199196d454941c45d1b3a323"),
+            (
+                "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy",
+                "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+            (
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+                "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+        ];
+        for (addr, script) in valid.iter() {
+            let (network, version, program) = decode_segwit_address(addr).unwrap();
+            let script = decode_hex(script).unwrap();
+            // OP_0 または OP_1〜OP_16 (0x51〜0x60)
+            let expected_version = if script[0] == 0 { 0 } else { script[0] - 0x50 };
+            assert_eq!(version, expected_version, "{}", addr);
+            assert_eq!(program, script[2..].to_vec(), "{}", addr);
+            assert_eq!(encode_segwit_address(network, version, &program).unwrap(), addr.to_lowercase());
+        }
+
+        let invalid = [
+            "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+            "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+            "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+            "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+            "bc1pw5dgrnzv",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
+            "bc1gmk9yu",
+        ];
+        for addr in invalid.iter() {
+            assert!(decode_segwit_address(addr).is_err(), "{}", addr);
+        }
+        assert_eq!(
+            decode_segwit_address(invalid[0]).unwrap_err(),
+            SegwitAddressError::UnknownHrp("tc".to_string())
+        );
+        assert_eq!(decode_segwit_address(invalid[1]).unwrap_err(), SegwitAddressError::InvalidVariant);
+        assert_eq!(decode_segwit_address(invalid[8]).unwrap_err(), SegwitAddressError::InvalidProgramLength(1));
+    }
+
+    #[test]
+    fn test_addresses_for_each_network() {
+        // 秘密鍵1の公開鍵 (BIP173の例と同じ)
+        let point = new_secp_256k1privatekey_from_i32(1).point.clone();
+        assert_eq!(point.p2wpkh_address(Network::Mainnet), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(point.p2wpkh_address(Network::Testnet), "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx");
+        assert_eq!(point.p2wpkh_address(Network::Signet), point.p2wpkh_address(Network::Testnet));
+        let regtest = point.p2wpkh_address(Network::Regtest);
+        assert!(regtest.starts_with("bcrt1q"));
+        assert_eq!(decode_segwit_address(&regtest).unwrap().0, Network::Regtest);
+
+        // witness script = <pubkey> OP_CHECKSIG
+        let mut script = vec![0x21u8];
+        script.append(&mut point.clone().compressed_sec());
+        script.push(0xac);
+        assert_eq!(
+            p2wsh_address(&script, Network::Testnet),
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"
+        );
+
+        // BIP86 の最初の鍵 (m/86'/0'/0'/0/0)
+        let internal = Secp256k1Point::parse_x_only(
+            &decode_hex("cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            internal.p2tr_address(None, Network::Mainnet).unwrap(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::ecc::secp256k1_privatekey::Secp256k1PrivateKey;
+use crate::ecc::secp256k1_recoverable_signature::Secp256k1RecoverableSignature;
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_bytes_be, Secp256k1ScalarElement,
+};
+use crate::helper::helper::{encode_varint, hash256};
+
+// Bitcoin Core の signmessage / verifymessage と互換のメッセージ署名
+
+const SIGNED_MESSAGE_PREFIX: &str = "Bitcoin Signed Message:\n";
+
+// hash256(varint(len(prefix)) || prefix || varint(len(message)) || message)
+pub fn signed_message_hash(message: &str) -> Vec<u8> {
+    let mut data = encode_varint(SIGNED_MESSAGE_PREFIX.len() as u128);
+    data.extend_from_slice(SIGNED_MESSAGE_PREFIX.as_bytes());
+    data.append(&mut encode_varint(message.len() as u128));
+    data.extend_from_slice(message.as_bytes());
+    hash256(data)
+}
+
+fn signed_message_z(message: &str) -> Secp256k1ScalarElement {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&signed_message_hash(message));
+    new_secp256k1scalarelement_from_bytes_be(&hash)
+}
+
+// 65byteのcompact形式の署名をbase64にしたものを返す
+#[allow(dead_code)]
+pub fn sign_message(private_key: Secp256k1PrivateKey, message: &str, compressed: bool) -> String {
+    let sig = private_key.sign_recoverable(signed_message_z(message));
+    base64::encode(sig.compact(compressed))
+}
+
+// P2PKHのアドレス(mainnet/testnet)に対して署名を検証する
+#[allow(dead_code)]
+pub fn verify_message(address: &str, signature: &str, message: &str) -> bool {
+    let bytes = match base64::decode(signature) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let (sig, compressed) = match Secp256k1RecoverableSignature::parse_compact(&bytes) {
+        Some(parsed) => parsed,
+        None => return false,
+    };
+    let point = match sig.recover(signed_message_z(message)) {
+        Some(point) => point,
+        None => return false,
+    };
+    for testnet in [false, true].iter() {
+        let recovered = if compressed {
+            point.clone().compressed_address(*testnet)
+        } else {
+            point.clone().uncompressed_address(*testnet)
+        };
+        if recovered == address {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_privatekey::{
+        new_secp_256k1privatekey, new_secp_256k1privatekey_from_i32,
+    };
+    use crate::helper::helper::decode_hex;
+
+    #[test]
+    fn test_signed_message_hash() {
+        assert_eq!(
+            signed_message_hash("test"),
+            decode_hex("9ce428d58e8e4caf619dc6fc7b2c2c28f0561654d1f80f322c038ad5e67ff8a6").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_message_vector() {
+        // rust-bitcoin の sign_message のテストで使われている鍵と署名
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&base64::decode("UuOGDsfLPr4HIMKQX0ipjJeRaj1geCq3yPUF2COP5ME=").unwrap());
+        let key = new_secp_256k1privatekey(new_secp256k1scalarelement_from_bytes_be(&secret));
+        assert_eq!(
+            base64::encode(key.point.clone().compressed_sec()),
+            "A1FTfMEntPpAty3qkEo0q2Dc1FEycI10a3jmwEFy+Qr6"
+        );
+        let message = "rust-bitcoin MessageSignature test";
+        let signature = sign_message(key.clone(), message, true);
+        assert_eq!(
+            signature,
+            "IAM2qX24tYx/bdBTIgVLhD8QEAjrPlJpmjB4nZHdRYGIBa4DmVulAcwjPnWe6Q5iEwXH6F0pUCJP/ZeHPWS1h1o="
+        );
+        let address = key.point.clone().compressed_address(false);
+        assert!(verify_message(&address, &signature, message));
+        assert!(!verify_message(&address, &signature, "a different message from what was signed"));
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let key = new_secp_256k1privatekey_from_i32(12345);
+        let message = "Programming Bitcoin!";
+        let uncompressed = sign_message(key.clone(), message, false);
+        let compressed = sign_message(key.clone(), message, true);
+        let address = key.point.clone().compressed_address(true);
+        let uncompressed_address = key.point.clone().uncompressed_address(false);
+        assert!(verify_message(&address, &compressed, message));
+        assert!(verify_message(&uncompressed_address, &uncompressed, message));
+        // 圧縮形式のフラグが違うと別のアドレスになる
+        assert!(!verify_message(&address, &uncompressed, message));
+        assert!(!verify_message(&address, "not base64!", message));
+        let other = new_secp_256k1privatekey_from_i32(54321).point.clone().compressed_address(true);
+        assert!(!verify_message(&other, &compressed, message));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::ecc::secp256k1_scalar_element::{
+    new_secp256k1scalarelement_from_u256, Secp256k1ScalarElement, SECP256K1_N,
+};
+use crate::ecc::u256::U256;
+use crate::helper::helper::{encode_varint, tagged_hash};
+
+// BIP341 (Taproot)
+
+// tapscriptのリーフバージョン
+#[allow(dead_code)]
+pub const TAPROOT_LEAF_TAPSCRIPT: u8 = 0xc0;
+
+// t = hash_TapTweak(P.x || merkle_root)。n以上の場合は鍵として使えないのでNone
+pub(crate) fn tap_tweak_hash(
+    internal_x: &[u8],
+    merkle_root: Option<&[u8]>,
+) -> Option<Secp256k1ScalarElement> {
+    let mut data = internal_x.to_vec();
+    if let Some(merkle_root) = merkle_root {
+        data.extend_from_slice(merkle_root);
+    }
+    let t = U256::from_be_slice(&tagged_hash("TapTweak", &data)).unwrap();
+    if t >= SECP256K1_N {
+        return None;
+    }
+    Some(new_secp256k1scalarelement_from_u256(t))
+}
+
+// hash_TapLeaf(leaf_version || compact_size(len(script)) || script)
+#[allow(dead_code)]
+pub fn tap_leaf_hash(script: &[u8], leaf_version: u8) -> Vec<u8> {
+    let mut data = vec![leaf_version];
+    data.append(&mut encode_varint(script.len() as u128));
+    data.extend_from_slice(script);
+    tagged_hash("TapLeaf", &data)
+}
+
+// 2つの子ノードのハッシュを辞書順に並べて結合する
+#[allow(dead_code)]
+pub fn tap_branch_hash(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut data = vec![];
+    if a <= b {
+        data.extend_from_slice(a);
+        data.extend_from_slice(b);
+    } else {
+        data.extend_from_slice(b);
+        data.extend_from_slice(a);
+    }
+    tagged_hash("TapBranch", &data)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::secp256k1_point::Secp256k1Point;
+    use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey_from_i32;
+    use crate::helper::helper::decode_hex;
+    use crate::scripts::script::new_script_p2tr_locking;
+
+    #[test]
+    fn test_bip341_wallet_vectors() {
+        // BIP341 wallet-test-vectors.json の scriptPubKey[0] (スクリプトツリー無し)
+        let internal = Secp256k1Point::parse_x_only(
+            &decode_hex("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            tap_tweak_hash(&internal.x_only(), None).unwrap().value.to_be_bytes().to_vec(),
+            decode_hex("b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70").unwrap()
+        );
+        let (output, _) = internal.tap_tweak(None).unwrap();
+        assert_eq!(
+            output.x_only(),
+            decode_hex("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343").unwrap()
+        );
+        let script_pubkey = new_script_p2tr_locking(internal.p2tr_witness_program(None).unwrap());
+        assert_eq!(
+            script_pubkey.raw_serialize(),
+            decode_hex("512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343").unwrap()
+        );
+
+        // scriptPubKey[1] (リーフが1つだけのスクリプトツリー)
+        let internal = Secp256k1Point::parse_x_only(
+            &decode_hex("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27").unwrap(),
+        )
+        .unwrap();
+        let script = decode_hex("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac").unwrap();
+        let merkle_root = tap_leaf_hash(&script, TAPROOT_LEAF_TAPSCRIPT);
+        let (output, _) = internal.tap_tweak(Some(&merkle_root)).unwrap();
+        assert_eq!(
+            output.x_only(),
+            decode_hex("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tweaked_private_key_signs_for_output_key() {
+        let merkle_root = tap_leaf_hash(&[0x51], TAPROOT_LEAF_TAPSCRIPT);
+        for secret in [1, 2, 3, 12345].iter() {
+            let key = new_secp_256k1privatekey_from_i32(*secret);
+            for root in [None, Some(merkle_root.as_slice())].iter() {
+                let (output, odd) = key.point.tap_tweak(*root).unwrap();
+                let tweaked = key.tap_tweak(*root).unwrap();
+                // 鍵パスで使う秘密鍵の公開鍵は、出力鍵と同じ点になる
+                assert_eq!(tweaked.point, output);
+                assert_eq!(odd, !output.has_even_y());
+                let msg = [7u8; 32];
+                let sig = tweaked.sign_schnorr(&msg, &[0u8; 32]).unwrap();
+                let program = key.point.p2tr_witness_program(*root).unwrap();
+                let output_key = Secp256k1Point::parse_x_only(&program).unwrap();
+                assert!(output_key.verify_schnorr(&msg, &sig));
+            }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use num_bigint::BigUint;
+use std::cmp::Ordering;
+
+// 64bit x 4 のリム(リトルエンディアン)で表した256bit符号なし整数
+// Secp256k1Element, Secp256k1ScalarElement の内部表現に使う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct U256 {
+    pub(crate) limbs: [u64; 4],
+}
+
+// a + b + carry を (結果, 桁上がり) で返す
+#[inline(always)]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = (a as u128) + (b as u128) + (carry as u128);
+    (t as u64, (t >> 64) as u64)
+}
+
+// a - b - borrow を (結果, 桁借り(0 or 1)) で返す
+#[inline(always)]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub((b as u128) + (borrow as u128));
+    (t as u64, ((t >> 64) as u64) & 1)
+}
+
+// a + b * c + carry を (結果, 桁上がり) で返す
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = (a as u128) + (b as u128) * (c as u128) + (carry as u128);
+    (t as u64, (t >> 64) as u64)
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256 { limbs: [0, 0, 0, 0] };
+    pub const ONE: U256 = U256 { limbs: [1, 0, 0, 0] };
+
+    pub const fn from_limbs(limbs: [u64; 4]) -> U256 {
+        U256 { limbs }
+    }
+
+    pub fn from_u64(v: u64) -> U256 {
+        U256 { limbs: [v, 0, 0, 0] }
+    }
+
+    // 32byteを超える入力は受け付けない
+    pub fn from_be_slice(bytes: &[u8]) -> Option<U256> {
+        if bytes.len() > 32 {
+            return None;
+        }
+        let mut buf = [0u8; 32];
+        buf[32 - bytes.len()..].copy_from_slice(bytes);
+        Some(U256::from_be_bytes(&buf))
+    }
+
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&bytes[(3 - i) * 8..(4 - i) * 8]);
+            *limb = u64::from_be_bytes(word);
+        }
+        U256 { limbs }
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut ret = [0u8; 32];
+        for (i, limb) in self.limbs.iter().enumerate() {
+            ret[(3 - i) * 8..(4 - i) * 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        ret
+    }
+
+    pub fn from_biguint(num: &BigUint) -> Option<U256> {
+        U256::from_be_slice(&num.to_bytes_be())
+    }
+
+    pub fn to_biguint(self) -> BigUint {
+        BigUint::from_bytes_be(&self.to_be_bytes())
+    }
+
+    // 秘密の値を消すために使う。最適化で書き込みが消されないようにvolatileで0を書く
+    pub fn zeroize(&mut self) {
+        for limb in self.limbs.iter_mut() {
+            unsafe { std::ptr::write_volatile(limb, 0) };
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+
+    pub fn is_zero(&self) -> bool {
+        (self.limbs[0] | self.limbs[1] | self.limbs[2] | self.limbs[3]) == 0
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.limbs[0] & 1 == 1
+    }
+
+    // i番目(0が最下位)のビット
+    pub fn bit(&self, i: usize) -> bool {
+        (self.limbs[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    // 最上位の立っているビット位置+1 (0なら0)
+    pub fn bits(&self) -> usize {
+        for i in (0..4).rev() {
+            if self.limbs[i] != 0 {
+                return i * 64 + 64 - self.limbs[i].leading_zeros() as usize;
+            }
+        }
+        0
+    }
+
+    pub fn overflowing_add(&self, rhs: &U256) -> (U256, u64) {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (v, c) = adc(self.limbs[i], rhs.limbs[i], carry);
+            *limb = v;
+            carry = c;
+        }
+        (U256 { limbs }, carry)
+    }
+
+    pub fn overflowing_sub(&self, rhs: &U256) -> (U256, u64) {
+        let mut limbs = [0u64; 4];
+        let mut borrow = 0;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (v, b) = sbb(self.limbs[i], rhs.limbs[i], borrow);
+            *limb = v;
+            borrow = b;
+        }
+        (U256 { limbs }, borrow)
+    }
+
+    // 512bitの積を返す
+    pub fn mul_wide(&self, rhs: &U256) -> [u64; 8] {
+        let mut ret = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0;
+            for j in 0..4 {
+                let (v, c) = mac(ret[i + j], self.limbs[i], rhs.limbs[j], carry);
+                ret[i + j] = v;
+                carry = c;
+            }
+            ret[i + 4] = carry;
+        }
+        ret
+    }
+
+    // 1bit右シフト
+    pub fn shr1(&self) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let upper = if i == 3 { 0 } else { self.limbs[i + 1] << 63 };
+            *limb = (self.limbs[i] >> 1) | upper;
+        }
+        U256 { limbs }
+    }
+
+    // 下位 n(<64) bit を取り出す
+    pub fn low_bits(&self, n: usize) -> u64 {
+        self.limbs[0] & ((1u64 << n) - 1)
+    }
+
+    // i bit目から w(<64) bit 分を取り出す
+    pub fn bits_at(&self, i: usize, w: usize) -> u64 {
+        let limb = i / 64;
+        let offset = i % 64;
+        let mut v = self.limbs[limb] >> offset;
+        if offset + w > 64 && limb < 3 {
+            v |= self.limbs[limb + 1] << (64 - offset);
+        }
+        v & ((1u64 << w) - 1)
+    }
+
+    // mask が全ビット1なら a, 0なら b を分岐無しで選ぶ
+    #[inline(always)]
+    pub fn select(mask: u64, a: &U256, b: &U256) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = (a.limbs[i] & mask) | (b.limbs[i] & !mask);
+        }
+        U256 { limbs }
+    }
+}
+
+// a == b なら全ビット1、そうでなければ0を分岐無しで返す
+#[inline(always)]
+pub fn ct_eq_mask(a: u64, b: u64) -> u64 {
+    let x = a ^ b;
+    // x が0の時だけ最上位ビットが0になる
+    let nonzero = (x | x.wrapping_neg()) >> 63;
+    0u64.wrapping_sub(nonzero ^ 1)
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for i in (0..4).rev() {
+            match self.limbs[i].cmp(&other.limbs[i]) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+// 2^256 - c の形をした法。secp256k1のp, nはどちらもcが小さいので
+// 上位256bitにcを掛けて下位に足し込む操作を繰り返すだけで剰余が取れる。
+#[derive(Debug, Clone, Copy)]
+pub struct Modulus {
+    pub(crate) m: U256,
+    // 2^256 - m
+    pub(crate) c: U256,
+}
+
+impl Modulus {
+    pub const fn new(m: U256, c: U256) -> Modulus {
+        Modulus { m, c }
+    }
+
+    // 512bitの値をmで割った余りにする。
+    // cが130bit以下であれば4回畳み込めば必ず上位が0になるので、回数は固定にしている。
+    pub fn reduce_wide(&self, wide: &[u64; 8]) -> U256 {
+        let mut w = *wide;
+        for _ in 0..4 {
+            let hi = U256 {
+                limbs: [w[4], w[5], w[6], w[7]],
+            };
+            let mut t = hi.mul_wide(&self.c);
+            let mut carry = 0;
+            for i in 0..8 {
+                let lo = if i < 4 { w[i] } else { 0 };
+                let (v, c) = adc(t[i], lo, carry);
+                t[i] = v;
+                carry = c;
+            }
+            w = t;
+        }
+        let lo = U256 {
+            limbs: [w[0], w[1], w[2], w[3]],
+        };
+        self.reduce_once(&lo, 0)
+    }
+
+    // (carry * 2^256 + v) が 2m 未満のとき、mを引くかどうかを分岐無しで決める
+    #[inline(always)]
+    fn reduce_once(&self, v: &U256, carry: u64) -> U256 {
+        let (d, borrow) = v.overflowing_sub(&self.m);
+        // carryが立っているか、引き算で桁借りしなかったらdを採用する
+        let use_d = carry | (borrow ^ 1);
+        U256::select(0u64.wrapping_sub(use_d), &d, v)
+    }
+
+    // 256bitの任意の値をmで割った余りにする (m > 2^255 なので1回引けば十分)
+    pub fn reduce(&self, v: &U256) -> U256 {
+        self.reduce_once(v, 0)
+    }
+
+    pub fn add(&self, a: &U256, b: &U256) -> U256 {
+        let (s, carry) = a.overflowing_add(b);
+        self.reduce_once(&s, carry)
+    }
+
+    pub fn sub(&self, a: &U256, b: &U256) -> U256 {
+        let (d, borrow) = a.overflowing_sub(b);
+        let mask = 0u64.wrapping_sub(borrow);
+        let correction = U256::select(mask, &self.m, &U256::ZERO);
+        d.overflowing_add(&correction).0
+    }
+
+    pub fn neg(&self, a: &U256) -> U256 {
+        self.sub(&U256::ZERO, a)
+    }
+
+    pub fn mul(&self, a: &U256, b: &U256) -> U256 {
+        self.reduce_wide(&a.mul_wide(b))
+    }
+
+    pub fn square(&self, a: &U256) -> U256 {
+        self.mul(a, a)
+    }
+
+    // 指数は公開値(p-2, (p+1)/4 など)を想定している。
+    pub fn pow(&self, base: &U256, exp: &U256) -> U256 {
+        let mut result = U256::ONE;
+        for i in (0..exp.bits()).rev() {
+            result = self.square(&result);
+            if exp.bit(i) {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+
+    // フェルマーの小定理によるインバース
+    pub fn inv(&self, a: &U256) -> U256 {
+        let exp = self.m.overflowing_sub(&U256::from_u64(2)).0;
+        self.pow(a, &exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use num_traits::Num;
+
+    fn n() -> BigUint {
+        BigUint::from_str_radix(
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            16,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let v = BigUint::from_str_radix(
+            "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            16,
+        )
+        .unwrap();
+        let u = U256::from_biguint(&v).unwrap();
+        assert_eq!(u.to_biguint(), v);
+        assert_eq!(U256::from_be_bytes(&u.to_be_bytes()), u);
+        assert_eq!(u.bits(), 255);
+        assert!(U256::from_be_slice(&[1u8; 33]).is_none());
+    }
+
+    #[test]
+    fn test_modulus_matches_biguint() {
+        let n_big = n();
+        let m = U256::from_biguint(&n_big).unwrap();
+        let c = U256::ZERO.overflowing_sub(&m).0;
+        let modulus = Modulus::new(m, c);
+        let samples = [
+            n_big.clone() - BigUint::from(1u8),
+            BigUint::from(2u8).pow(255) + BigUint::from(12345u32),
+            BigUint::from_str_radix(
+                "c7207fee197d27c618aea621406f6bf5ef6fca38681d82b2f06fddbdce6feab6",
+                16,
+            )
+            .unwrap(),
+            BigUint::from(7u8),
+        ];
+        for a in samples.iter() {
+            for b in samples.iter() {
+                let ua = U256::from_biguint(a).unwrap();
+                let ub = U256::from_biguint(b).unwrap();
+                assert_eq!(
+                    modulus.mul(&ua, &ub).to_biguint(),
+                    (a * b) % n_big.clone()
+                );
+                assert_eq!(
+                    modulus.add(&ua, &ub).to_biguint(),
+                    (a + b) % n_big.clone()
+                );
+                assert_eq!(
+                    modulus.sub(&ua, &ub).to_biguint(),
+                    (a + n_big.clone() - b) % n_big.clone()
+                );
+            }
+            let ua = U256::from_biguint(a).unwrap();
+            assert_eq!(
+                modulus.mul(&modulus.inv(&ua), &ua),
+                U256::ONE
+            );
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use num_bigint::BigUint;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, Mul, Sub};
+
+// 素体の元。四則演算と逆元があれば曲線上の点の計算には十分
+pub trait PrimeField:
+    Clone + Debug + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    fn is_zero(&self) -> bool;
+    fn inv(&self) -> Self;
+}
+
+// y^2 = x^3 + ax + b の形の曲線。
+// FieldElementのように法を実行時に持つ体もあるので、定数は曲線側から作る
+pub trait WeierstrassCurve: Clone + Debug + PartialEq {
+    type Field: PrimeField;
+
+    fn a(&self) -> Self::Field;
+    fn b(&self) -> Self::Field;
+    fn element(&self, v: u64) -> Self::Field;
+}
+
+#[derive(Debug, Clone)]
+pub struct WeierstrassPoint<C: WeierstrassCurve> {
+    pub(crate) x: C::Field,
+    pub(crate) y: C::Field,
+    pub(crate) is_infinity: bool,
+    pub(crate) curve: C,
+}
+
+impl<C: WeierstrassCurve> PartialEq for WeierstrassPoint<C> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.curve != other.curve {
+            return false;
+        }
+        if self.is_infinity || other.is_infinity {
+            return self.is_infinity && other.is_infinity;
+        }
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl<C: WeierstrassCurve> WeierstrassPoint<C> {
+    pub fn is_infinity(&self) -> bool {
+        self.is_infinity
+    }
+
+    // y^2 = x^3 + ax + b を満たすかどうか。無限遠点はfalse
+    pub fn is_on_curve(&self) -> bool {
+        if self.is_infinity {
+            return false;
+        }
+        let x = self.x.clone();
+        let rhs = x.clone() * x.clone() * x.clone() + self.curve.a() * x + self.curve.b();
+        self.y.clone() * self.y.clone() == rhs
+    }
+
+    #[allow(dead_code)]
+    pub fn neg(&self) -> WeierstrassPoint<C> {
+        if self.is_infinity {
+            return self.clone();
+        }
+        let y = self.curve.element(0) - self.y.clone();
+        new_weierstrass_point_unchecked(self.curve.clone(), self.x.clone(), y)
+    }
+
+    // 接線の傾き s = (3x^2 + a) / 2y を使う。y = 0 なら接線が垂直なので無限遠点
+    pub fn double(&self) -> WeierstrassPoint<C> {
+        if self.is_infinity || self.y.is_zero() {
+            return new_weierstrass_point_infinity(self.curve.clone());
+        }
+        let x = self.x.clone();
+        let y = self.y.clone();
+        let s = (self.curve.element(3) * x.clone() * x.clone() + self.curve.a())
+            * (self.curve.element(2) * y.clone()).inv();
+        let x3 = s.clone() * s.clone() - self.curve.element(2) * x.clone();
+        let y3 = s * (x - x3.clone()) - y;
+        new_weierstrass_point_unchecked(self.curve.clone(), x3, y3)
+    }
+
+    // バイナリ法。小さい曲線の練習問題向けなので定数時間ではない
+    #[allow(dead_code)]
+    pub fn mul(&self, v: u128) -> WeierstrassPoint<C> {
+        let mut result = new_weierstrass_point_infinity(self.curve.clone());
+        for i in (0..(128 - v.leading_zeros())).rev() {
+            result = result.double();
+            if (v >> i) & 1 == 1 {
+                result = result + self.clone();
+            }
+        }
+        result
+    }
+
+    #[allow(dead_code)]
+    pub fn mul_big_uint(&self, v: &BigUint) -> WeierstrassPoint<C> {
+        let mut result = new_weierstrass_point_infinity(self.curve.clone());
+        for i in (0..v.bits()).rev() {
+            result = result.double();
+            if v.bit(i) {
+                result = result + self.clone();
+            }
+        }
+        result
+    }
+}
+
+impl<C: WeierstrassCurve> Add for WeierstrassPoint<C> {
+    type Output = WeierstrassPoint<C>;
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.curve != rhs.curve {
+            panic!("curve mismatch. can't add {:?} and {:?}", self.curve, rhs.curve);
+        }
+        if self.is_infinity {
+            return rhs;
+        }
+        if rhs.is_infinity {
+            return self;
+        }
+        if self.x == rhs.x {
+            // 同じ点なら2倍算、加法逆元なら無限遠点
+            if self.y == rhs.y {
+                return self.double();
+            }
+            return new_weierstrass_point_infinity(self.curve);
+        }
+
+        let s = (rhs.y - self.y.clone()) * (rhs.x.clone() - self.x.clone()).inv();
+        let x3 = s.clone() * s.clone() - self.x.clone() - rhs.x;
+        let y3 = s * (self.x - x3.clone()) - self.y;
+        new_weierstrass_point_unchecked(self.curve, x3, y3)
+    }
+}
+
+impl<C: WeierstrassCurve> Display for WeierstrassPoint<C>
+where
+    C::Field: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_infinity {
+            return write!(f, "(無限遠点)");
+        }
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+#[allow(dead_code)]
+pub fn new_weierstrass_point<C: WeierstrassCurve>(
+    curve: C,
+    x: C::Field,
+    y: C::Field,
+) -> Result<WeierstrassPoint<C>, &'static str> {
+    let p = new_weierstrass_point_unchecked(curve, x, y);
+    if !p.is_on_curve() {
+        return Err("point not on curve");
+    }
+    Ok(p)
+}
+
+// 呼び出し側で曲線上にあることが分かっている場合に使う
+pub(crate) fn new_weierstrass_point_unchecked<C: WeierstrassCurve>(
+    curve: C,
+    x: C::Field,
+    y: C::Field,
+) -> WeierstrassPoint<C> {
+    WeierstrassPoint {
+        x,
+        y,
+        is_infinity: false,
+        curve,
+    }
+}
+
+pub fn new_weierstrass_point_infinity<C: WeierstrassCurve>(curve: C) -> WeierstrassPoint<C> {
+    WeierstrassPoint {
+        x: curve.element(0),
+        y: curve.element(0),
+        is_infinity: true,
+        curve,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::field_element::{new_field_element, FieldElement};
+    use crate::ecc::field_graph::new_field_planar_graph;
+    use crate::ecc::secp256k1_point::new_secp256k1point_g;
+    use crate::ecc::secp256k1_scalar_element::SECP256K1_N;
+
+    #[test]
+    fn test_same_code_for_f223_and_secp256k1() {
+        // F223 y^2 = x^3 + 7 上の (47,71) は位数21 (P.58)
+        let lhs = |y: FieldElement| y * y;
+        let rhs = |x: FieldElement| x * x * x + new_field_element(7, x.prime);
+        let graph = new_field_planar_graph(lhs, rhs, 223, "y^2 = x^3 + 7");
+        assert_eq!(graph.a(), new_field_element(0, 223));
+        assert_eq!(graph.b(), new_field_element(7, 223));
+        let p = new_weierstrass_point(graph.clone(), new_field_element(47, 223), new_field_element(71, 223)).unwrap();
+        let expected = new_weierstrass_point(graph.clone(), new_field_element(36, 223), new_field_element(111, 223)).unwrap();
+        assert_eq!(p.double(), expected);
+        assert!(p.mul(21).is_infinity());
+        assert_eq!(p.mul(20), p.neg());
+        assert!(new_weierstrass_point(graph, new_field_element(200, 223), new_field_element(119, 223)).is_err());
+
+        // secp256k1も同じ実装で計算でき、ヤコビアン座標の高速な実装と結果が一致する
+        let g = new_secp256k1point_g();
+        assert!(g.is_on_curve());
+        assert_eq!(g.double(), g.clone().mul_from_u64(2));
+        assert_eq!(g.mul(12345), g.clone().mul_from_u64(12345));
+        assert!(g.mul_big_uint(&SECP256K1_N.to_biguint()).is_infinity());
+    }
+}
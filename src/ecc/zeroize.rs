@@ -0,0 +1,115 @@
+use crate::ecc::secp256k1_scalar_element::Secp256k1ScalarElement;
+use crate::ecc::u256::U256;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{compiler_fence, Ordering};
+
+// 秘密の値を0で上書きできる型。最適化で書き込みが消されないようにvolatileで0を書く
+pub trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+impl Zeroize for [u8] {
+    fn zeroize(&mut self) {
+        for b in self.iter_mut() {
+            unsafe { std::ptr::write_volatile(b, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<const N: usize> Zeroize for [u8; N] {
+    fn zeroize(&mut self) {
+        self[..].zeroize();
+    }
+}
+
+impl Zeroize for Vec<u8> {
+    // lenより後ろの確保済みの領域にも以前の値が残っていることがあるので、そこも消す
+    fn zeroize(&mut self) {
+        self.as_mut_slice().zeroize();
+        for b in self.spare_capacity_mut().iter_mut() {
+            unsafe { std::ptr::write_volatile(b, MaybeUninit::new(0)) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Zeroize for String {
+    // 同じ領域をVec<u8>として消す。消した後は空の文字列になる
+    fn zeroize(&mut self) {
+        let mut bytes = std::mem::take(self).into_bytes();
+        bytes.zeroize();
+    }
+}
+
+impl Zeroize for U256 {
+    fn zeroize(&mut self) {
+        U256::zeroize(self);
+    }
+}
+
+impl Zeroize for Secp256k1ScalarElement {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+// ドロップ時に中身を0で上書きする入れ物。秘密の値の一時的なコピーはこれに入れておく
+pub struct Zeroizing<T: Zeroize>(T);
+
+pub fn new_zeroizing<T: Zeroize>(value: T) -> Zeroizing<T> {
+    Zeroizing(value)
+}
+
+impl<T: Zeroize> Deref for Zeroizing<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Zeroizing<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Zeroizing<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+
+    #[test]
+    fn test_zeroize() {
+        let mut v = Vec::with_capacity(8);
+        v.extend_from_slice(&[1u8, 2, 3, 4]);
+        v.truncate(2);
+        v.zeroize();
+        unsafe { v.set_len(4) };
+        assert_eq!(v, vec![0u8; 4]);
+
+        let mut a = [0xffu8; 32];
+        a.zeroize();
+        assert_eq!(a, [0u8; 32]);
+
+        let mut value = U256::from_u64(12345);
+        Zeroize::zeroize(&mut value);
+        assert!(value.is_zero());
+
+        let mut s = String::from("secret");
+        s.zeroize();
+        assert!(s.is_empty());
+
+        let mut buf = new_zeroizing(vec![1u8, 2, 3]);
+        buf.push(4);
+        assert_eq!(&buf[..], &[1u8, 2, 3, 4]);
+    }
+}
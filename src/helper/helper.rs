@@ -1,5 +1,8 @@
 use crypto::digest::Digest as D;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
 use crypto::sha1::Sha1;
+use crypto::sha2::{Sha256, Sha512};
 use num_bigint::BigUint;
 use ripemd160::{Digest, Ripemd160};
 use std::io::{Cursor, Read};
@@ -39,6 +42,30 @@ pub fn sha1(v: Vec<u8>) -> Vec<u8> {
     return ret;
 }
 
+// BIP340のタグ付きハッシュ sha256(sha256(tag) || sha256(tag) || data)
+#[allow(dead_code)]
+pub fn tagged_hash(tag: &str, data: &[u8]) -> Vec<u8> {
+    let tag_hash = sha256(tag.as_bytes().to_vec());
+    let mut v = tag_hash.clone();
+    v.extend_from_slice(&tag_hash);
+    v.extend_from_slice(data);
+    sha256(v)
+}
+
+#[allow(dead_code)]
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::new(Sha256::new(), key);
+    mac.input(data);
+    mac.result().code().to_vec()
+}
+
+#[allow(dead_code)]
+pub fn hmac_sha512(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::new(Sha512::new(), key);
+    mac.input(data);
+    mac.result().code().to_vec()
+}
+
 // for test
 #[allow(dead_code)]
 pub fn u8vec_to_str(v: Vec<u8>) -> String {
@@ -1,13 +1,13 @@
 #![feature(test)]
 
-use crate::ecc::decode::address_decode_base58;
 use crate::ecc::secp256k1_privatekey::{new_secp_256k1privatekey, Secp256k1PrivateKey};
 use crate::ecc::secp256k1_scalar_element::{
     new_secp256k1scalarelement,
 };
 use crate::helper::helper::{hash256};
+use crate::scripts::address::Address;
 use crate::scripts::script::{
-    new_script, new_script_p2pkh_locking, Cmd, Script,
+    new_script, Cmd, Script,
 };
 use crate::tx::tx::{Sighash, Tx};
 
@@ -38,7 +38,7 @@ fn generate_testnet_transaction_test() {
     // address = "mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK";
     let private_key = fetch_private_key();
 
-    let my_address = private_key.clone().point.compressed_address(true);
+    let my_address = private_key.point.clone().compressed_address(true);
     let target = "mwJn1YPMq7y5F8J3LkC5Hxg9PHyZ5K4cFv";
 
     let faucet_tx_id = "a9fec37e7f852872b89e7a9aa7dae546a28b87173b0ab17eaaa784783f70b625";
@@ -50,30 +50,14 @@ fn generate_testnet_transaction_test() {
     let version = 1;
     let lock_time = 0;
 
-    let my_address = address_decode_base58(my_address.to_string()).unwrap();
-    let target = address_decode_base58(target.to_string()).unwrap();
-
-    let change_script = new_script_p2pkh_locking(my_address);
-    let target_script = new_script_p2pkh_locking(target);
-
-    let target_out = TxOut {
-        amount: send_amount,
-        script_pub_key: target_script,
-    };
-    let change_out = TxOut {
-        amount: change_amount,
-        script_pub_key: change_script,
-    };
+    let my_address = Address::parse(&my_address).unwrap();
+    let target = Address::parse(target).unwrap();
 
     let txin = TxIn::new(faucet_tx_id, 1);
 
-    let mut create_tx = Tx::new(
-        version,
-        vec![txin.clone()].clone(),
-        vec![change_out.clone(), target_out.clone()].clone(),
-        lock_time,
-        true,
-    );
+    let mut create_tx = Tx::new(version, vec![txin.clone()], vec![], lock_time, true);
+    create_tx.add_output(&my_address, change_amount).unwrap();
+    create_tx.add_output(&target, send_amount).unwrap();
 
     let z = create_tx.sig_hash(0, true); // 署名するinputについて呼び出す。今回はinputは一個なのでidx: 0
     let z = new_secp256k1scalarelement(z);
@@ -81,7 +65,7 @@ fn generate_testnet_transaction_test() {
     let mut sig = sig.der();
     // <signature>はDER署名+sighash(01) で表す
     sig.append(&mut (Sighash::All as u8).to_be_bytes().to_vec());
-    let sec = private_key.clone().point.compressed_sec();
+    let sec = private_key.point.clone().compressed_sec();
     let script_sig = new_script(vec![Cmd::Element(sig), Cmd::Element(sec)]);
 
     create_tx.tx_ins[0].script_sig = script_sig.clone();
@@ -95,13 +79,10 @@ fn generate_testnet_transaction_two_input_test() {
     let private_key = fetch_private_key();
 
     let my_address = "mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK".to_string();
-    let my_address = address_decode_base58(my_address.to_string()).unwrap();
+    let change_script = Address::parse(&my_address).unwrap().script_pubkey();
 
     let target_address = "mwJn1YPMq7y5F8J3LkC5Hxg9PHyZ5K4cFv".to_string();
-    let target_address = address_decode_base58(target_address.to_string()).unwrap();
-
-    let change_script = new_script_p2pkh_locking(my_address);
-    let target_script = new_script_p2pkh_locking(target_address);
+    let target_script = Address::parse(&target_address).unwrap().script_pubkey();
 
     let send_amount = 50000;
     let change_amount = 20000;
@@ -131,7 +112,7 @@ fn generate_testnet_transaction_two_input_test() {
         true,
     );
 
-    let sec = private_key.clone().point.compressed_sec();
+    let sec = private_key.point.clone().compressed_sec();
 
     // zの値はどっちもあってそう。kの値を固定してみてトランザクションのチェックを行う
     let z1 = create_tx.sig_hash(0,true);
@@ -172,7 +153,7 @@ mod tests {
     use super::*;
     #[test]
     fn generate_testnet_transaction_two_input() {
-        let expected_tx_str_raw = "0100000002c9ee07574a6477248d8dd2436cb2aab39fe004a9bad8530df3a15a60c49599ec010000006a47304402207af5f77e06684c937b513e823518cd545d9cf057be0cf89d015af055c6a4d22302204f18c6a906628a70fe3070a804b878637faab098d2d38474140913518a328ce8012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff178f32985ede440f395c626b14a4474a8d75ecb04aaab45b2a538592afaa0b2e000000006a47304402207af5f77e06684c937b513e823518cd545d9cf057be0cf89d015af055c6a4d223022020f25559aabe32c2b9a20c8d6e82f2db406f0ad628cb69bb457cbbef164e4a0c012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff02204e0000000000001976a9146745ed61a219bc660b8ba7dd7abf2aa3002bd0c688ac50c30000000000001976a914ad346f8eb57dee9a37981716e498120ae80e44f788ac00000000";
+        let expected_tx_str_raw = "0100000002c9ee07574a6477248d8dd2436cb2aab39fe004a9bad8530df3a15a60c49599ec010000006a473044022009bf63a028c4dd9988e646861d0408e6a2d1dde31a00e085cfeb121d95d642ef0220452ffee881395f80287448a4407afba93df03673b1653a097ae3d153802b9b78012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff178f32985ede440f395c626b14a4474a8d75ecb04aaab45b2a538592afaa0b2e000000006a47304402207af5f77e06684c937b513e823518cd545d9cf057be0cf89d015af055c6a4d223022020f25559aabe32c2b9a20c8d6e82f2db406f0ad628cb69bb457cbbef164e4a0c012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff02204e0000000000001976a9146745ed61a219bc660b8ba7dd7abf2aa3002bd0c688ac50c30000000000001976a914ad346f8eb57dee9a37981716e498120ae80e44f788ac00000000";
         let expected_tx_str = decode_hex(expected_tx_str_raw.clone()).unwrap();
         let mut cursor = Cursor::new(expected_tx_str);
         let expected_tx = Tx::parse(true,&mut cursor);
@@ -181,17 +162,17 @@ mod tests {
         let private_key = fetch_private_key();
 
         let my_address = "mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK".to_string();
-        let my_address = address_decode_base58(my_address.to_string()).unwrap();
+        let change_script = Address::parse(&my_address).unwrap().script_pubkey();
 
         let target_address = "mwJn1YPMq7y5F8J3LkC5Hxg9PHyZ5K4cFv".to_string();
-        let target_address = address_decode_base58(target_address.to_string()).unwrap();
-
-        let change_script = new_script_p2pkh_locking(my_address);
-        let target_script = new_script_p2pkh_locking(target_address);
+        let target_script = Address::parse(&target_address).unwrap().script_pubkey();
 
         let send_amount = 50000;
         let change_amount = 20000;
 
+        // どちらの入力も my_address 宛てのアウトプットを使うので、script_pubkeyはお釣りと同じ
+        let prev_script_pubkey = change_script.clone();
+
         let change_out = TxOut{ amount: change_amount, script_pub_key: change_script };
         let target_out = TxOut{ amount: send_amount, script_pub_key: target_script };
 
@@ -217,10 +198,11 @@ mod tests {
             true,
         );
 
-        let sec = private_key.clone().point.compressed_sec();
+        let sec = private_key.point.clone().compressed_sec();
 
-        // zの値はどっちもあってそう。kの値を固定してみてトランザクションのチェックを行う
-        let z1 = create_tx.sig_hash(0,true);
+        // kはRFC6979で決まるので、署名も含めて毎回同じトランザクションになる。
+        // 前のトランザクションは取りに行かずにzを求める
+        let z1 = create_tx.sig_hash_with_script_pubkey(0,prev_script_pubkey.clone());
         let z1 = new_secp256k1scalarelement(z1);
         println!("z_val 0: {}",z1.clone());
         let sig1 = private_key.clone().sign(z1);
@@ -231,7 +213,7 @@ mod tests {
         let script_sig1 = new_script(vec![Cmd::Element(sig1),Cmd::Element(sec.clone())]);
         create_tx.tx_ins[0].script_sig = script_sig1.clone();
 
-        let z2 = create_tx.sig_hash(1,true);
+        let z2 = create_tx.sig_hash_with_script_pubkey(1,prev_script_pubkey.clone());
         let z2 = new_secp256k1scalarelement(z2);
         println!("z_val 1: {}",z2.clone());
         let sig2 = private_key.clone().sign(z2);
@@ -252,8 +234,22 @@ mod tests {
 
         assert_eq!(create_tx.clone().serialize_str(),expected_tx_str_raw);
 
-        if !create_tx.verify() {
-            panic!("create_tx.verify() failed");
+        // 署名済みのscript_sigとscript_pubkeyを繋げて評価する
+        for i in 0..create_tx.tx_ins.len() {
+            let z = create_tx.sig_hash_with_script_pubkey(i,prev_script_pubkey.clone());
+            let combined = create_tx.tx_ins[i].script_sig.clone() + prev_script_pubkey.clone();
+            assert!(combined.evaluate(new_secp256k1scalarelement(z)));
+        }
+    }
+
+    // 前のトランザクションを取りに行くのでネットワークが必要
+    #[test]
+    fn verify_testnet_transaction_two_input() {
+        let tx_str = "0100000002c9ee07574a6477248d8dd2436cb2aab39fe004a9bad8530df3a15a60c49599ec010000006a473044022009bf63a028c4dd9988e646861d0408e6a2d1dde31a00e085cfeb121d95d642ef0220452ffee881395f80287448a4407afba93df03673b1653a097ae3d153802b9b78012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff178f32985ede440f395c626b14a4474a8d75ecb04aaab45b2a538592afaa0b2e000000006a47304402207af5f77e06684c937b513e823518cd545d9cf057be0cf89d015af055c6a4d223022020f25559aabe32c2b9a20c8d6e82f2db406f0ad628cb69bb457cbbef164e4a0c012103764e263fa94bb5c54a4898aeb3e22bc025b6c9617f05bda41c9351a874d472ccffffffff02204e0000000000001976a9146745ed61a219bc660b8ba7dd7abf2aa3002bd0c688ac50c30000000000001976a914ad346f8eb57dee9a37981716e498120ae80e44f788ac00000000";
+        let mut cursor = Cursor::new(decode_hex(tx_str).unwrap());
+        let tx = Tx::parse(true,&mut cursor);
+        if !tx.verify() {
+            panic!("tx.verify() failed");
         }
     }
 
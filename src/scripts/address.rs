@@ -0,0 +1,277 @@
+use crate::ecc::decode::{decode_base58_checksum_with_version, Base58Error};
+use crate::ecc::encode::encode_base58_checksum;
+use crate::ecc::network::Network;
+use crate::ecc::segwit_address::{decode_segwit_address, encode_segwit_address, SegwitAddressError};
+use crate::scripts::operation::OperationCodes;
+use crate::scripts::script::{new_script, new_script_p2pkh_locking, Cmd, Script};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+// Base58のバージョン。testnet, signet, regtest は同じ値を使う
+const P2PKH_MAINNET: u8 = 0x00;
+const P2SH_MAINNET: u8 = 0x05;
+const P2PKH_TESTNET: u8 = 0x6f;
+const P2SH_TESTNET: u8 = 0xc4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressPayload {
+    // 公開鍵のhash160
+    P2pkh(Vec<u8>),
+    // redeem scriptのhash160
+    P2sh(Vec<u8>),
+    // 圧縮公開鍵のhash160
+    P2wpkh(Vec<u8>),
+    // witness scriptのsha256
+    P2wsh(Vec<u8>),
+    // 出力鍵のx座標
+    P2tr(Vec<u8>),
+}
+
+// 標準的なアドレス。script_pubkeyとの相互変換ができる
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    // Base58のアドレスと"tb"のアドレスはtestnetとsignet(regtest)を区別できないのでTestnetになる
+    pub(crate) network: Network,
+    pub(crate) payload: AddressPayload,
+}
+
+// アドレスを読めなかった、もしくは使えなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressError {
+    Base58(Base58Error),
+    Segwit(SegwitAddressError),
+    // 知らないBase58のバージョン
+    UnknownVersion(u8),
+    // ハッシュや witness program の長さが合わない
+    InvalidPayloadLength(usize),
+    // P2PKH, P2SH, P2WPKH, P2WSH, P2TR のどれでもないscript
+    UnsupportedScript,
+    // 未定義のwitness version (2〜16) のアドレス
+    UnsupportedWitnessVersion(u8),
+    // アドレスのネットワークとトランザクションのネットワークが違う
+    NetworkMismatch { expected: Network, found: Network },
+}
+
+impl Display for AddressError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Base58(e) => write!(f, "bad address: {}", e),
+            AddressError::Segwit(e) => write!(f, "{}", e),
+            AddressError::UnknownVersion(v) => write!(f, "bad address: unknown version 0x{:02x}", v),
+            AddressError::InvalidPayloadLength(len) => {
+                write!(f, "bad address: invalid payload length {}", len)
+            }
+            AddressError::UnsupportedScript => write!(f, "script_pubkey has no standard address"),
+            AddressError::UnsupportedWitnessVersion(v) => {
+                write!(f, "unsupported witness version {}", v)
+            }
+            AddressError::NetworkMismatch { expected, found } => write!(
+                f,
+                "address is for {:?} but the transaction is for {:?}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+pub fn new_address(network: Network, payload: AddressPayload) -> Address {
+    Address { network, payload }
+}
+
+impl Address {
+    // "bc1", "tb1", "bcrt1" で始まればSegWit、それ以外はBase58として読む
+    #[allow(dead_code)]
+    pub fn parse(s: &str) -> Result<Address, AddressError> {
+        let lower = s.to_lowercase();
+        if lower.starts_with("bc1") || lower.starts_with("tb1") || lower.starts_with("bcrt1") {
+            let (network, version, program) = decode_segwit_address(s).map_err(AddressError::Segwit)?;
+            let payload = match (version, program.len()) {
+                (0, 20) => AddressPayload::P2wpkh(program),
+                (0, 32) => AddressPayload::P2wsh(program),
+                (1, 32) => AddressPayload::P2tr(program),
+                (1, len) => return Err(AddressError::InvalidPayloadLength(len)),
+                (version, _) => return Err(AddressError::UnsupportedWitnessVersion(version)),
+            };
+            return Ok(new_address(network, payload));
+        }
+
+        let (version, hash) = decode_base58_checksum_with_version(s).map_err(AddressError::Base58)?;
+        if hash.len() != 20 {
+            return Err(AddressError::InvalidPayloadLength(hash.len()));
+        }
+        let address = match version {
+            P2PKH_MAINNET => new_address(Network::Mainnet, AddressPayload::P2pkh(hash)),
+            P2SH_MAINNET => new_address(Network::Mainnet, AddressPayload::P2sh(hash)),
+            P2PKH_TESTNET => new_address(Network::Testnet, AddressPayload::P2pkh(hash)),
+            P2SH_TESTNET => new_address(Network::Testnet, AddressPayload::P2sh(hash)),
+            version => return Err(AddressError::UnknownVersion(version)),
+        };
+        Ok(address)
+    }
+
+    // script_pubkeyの形からアドレスを作る。ネットワークはscriptからは分からないので指定する
+    #[allow(dead_code)]
+    pub fn from_script_pubkey(script: &Script, network: Network) -> Result<Address, AddressError> {
+        let op = |code: OperationCodes| Cmd::OperationCode(code as u8);
+        let payload = match script.cmds.as_slice() {
+            [a, b, Cmd::Element(hash), c, d]
+                if *a == op(OperationCodes::OpDup)
+                    && *b == op(OperationCodes::OpHash160)
+                    && *c == op(OperationCodes::OpEqualverify)
+                    && *d == op(OperationCodes::OpChecksig)
+                    && hash.len() == 20 =>
+            {
+                AddressPayload::P2pkh(hash.clone())
+            }
+            [a, Cmd::Element(hash), b]
+                if *a == op(OperationCodes::OpHash160) && *b == op(OperationCodes::OpEqual) && hash.len() == 20 =>
+            {
+                AddressPayload::P2sh(hash.clone())
+            }
+            [a, Cmd::Element(program)] if *a == op(OperationCodes::Op0) && program.len() == 20 => {
+                AddressPayload::P2wpkh(program.clone())
+            }
+            [a, Cmd::Element(program)] if *a == op(OperationCodes::Op0) && program.len() == 32 => {
+                AddressPayload::P2wsh(program.clone())
+            }
+            [a, Cmd::Element(program)] if *a == op(OperationCodes::Op1) && program.len() == 32 => {
+                AddressPayload::P2tr(program.clone())
+            }
+            _ => return Err(AddressError::UnsupportedScript),
+        };
+        Ok(new_address(network, payload))
+    }
+
+    #[allow(dead_code)]
+    pub fn script_pubkey(&self) -> Script {
+        let op = |code: OperationCodes| Cmd::OperationCode(code as u8);
+        match &self.payload {
+            AddressPayload::P2pkh(hash) => new_script_p2pkh_locking(hash.clone()),
+            AddressPayload::P2sh(hash) => new_script(vec![
+                op(OperationCodes::OpHash160),
+                Cmd::Element(hash.clone()),
+                op(OperationCodes::OpEqual),
+            ]),
+            AddressPayload::P2wpkh(program) | AddressPayload::P2wsh(program) => {
+                new_script(vec![op(OperationCodes::Op0), Cmd::Element(program.clone())])
+            }
+            AddressPayload::P2tr(program) => {
+                new_script(vec![op(OperationCodes::Op1), Cmd::Element(program.clone())])
+            }
+        }
+    }
+
+    // mainnetのトランザクションにはmainnetのアドレスだけ、それ以外にはそれ以外のアドレスだけ使える
+    #[allow(dead_code)]
+    pub fn check_network(&self, testnet: bool) -> Result<(), AddressError> {
+        if self.network.is_testnet() != testnet {
+            let expected = if testnet { Network::Testnet } else { Network::Mainnet };
+            return Err(AddressError::NetworkMismatch {
+                expected,
+                found: self.network,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let testnet = self.network.is_testnet();
+        let base58 = |version: u8, hash: &Vec<u8>| {
+            let mut data = vec![version];
+            data.extend_from_slice(hash);
+            encode_base58_checksum(data)
+        };
+        let s = match &self.payload {
+            AddressPayload::P2pkh(hash) => base58(if testnet { P2PKH_TESTNET } else { P2PKH_MAINNET }, hash),
+            AddressPayload::P2sh(hash) => base58(if testnet { P2SH_TESTNET } else { P2SH_MAINNET }, hash),
+            AddressPayload::P2wpkh(program) | AddressPayload::P2wsh(program) => {
+                encode_segwit_address(self.network, 0, program).map_err(|_| fmt::Error)?
+            }
+            AddressPayload::P2tr(program) => encode_segwit_address(self.network, 1, program).map_err(|_| fmt::Error)?,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::helper::helper::decode_hex;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_address_round_trip() {
+        // (アドレス, ネットワーク, scriptPubKey)
+        let vectors = [
+            ("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", Network::Mainnet, "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac"),
+            ("mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK", Network::Testnet, "76a9146745ed61a219bc660b8ba7dd7abf2aa3002bd0c688ac"),
+            ("3P14159f73E4gFr7JterCCQh9QjiTjiZrG", Network::Mainnet, "a914e9c3dd0c07aac76179ebc76a6c78d4d67c6c160a87"),
+            ("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc", Network::Testnet, "a9144e9f39ca4688ff102128ea4ccda34105324305b087"),
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Mainnet, "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                Network::Testnet,
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                Network::Mainnet,
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+        ];
+        for (s, network, script_hex) in vectors.iter() {
+            let address = Address::parse(s).unwrap();
+            assert_eq!(address.network, *network, "{}", s);
+            assert_eq!(address.to_string(), *s);
+
+            let script = address.script_pubkey();
+            let raw = decode_hex(script_hex).unwrap();
+            assert_eq!(script.raw_serialize(), raw, "{}", s);
+
+            let mut serialized = vec![raw.len() as u8];
+            serialized.extend_from_slice(&raw);
+            let parsed = Script::parse(&mut Cursor::new(serialized));
+            assert_eq!(Address::from_script_pubkey(&parsed, *network).unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn test_address_errors() {
+        assert_eq!(
+            Address::parse("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb").unwrap_err(),
+            AddressError::Base58(Base58Error::InvalidChecksum)
+        );
+        assert_eq!(
+            Address::parse(&encode_base58_checksum(vec![0x80u8; 21])).unwrap_err(),
+            AddressError::UnknownVersion(0x80)
+        );
+        assert_eq!(
+            Address::parse("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs").unwrap_err(),
+            AddressError::UnsupportedWitnessVersion(2)
+        );
+        assert!(matches!(
+            Address::parse("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5").unwrap_err(),
+            AddressError::Segwit(_)
+        ));
+        let op_return = new_script(vec![Cmd::OperationCode(OperationCodes::OpReturn as u8)]);
+        assert_eq!(
+            Address::from_script_pubkey(&op_return, Network::Mainnet).unwrap_err(),
+            AddressError::UnsupportedScript
+        );
+
+        let testnet = Address::parse("mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK").unwrap();
+        assert_eq!(testnet.check_network(true), Ok(()));
+        assert_eq!(
+            testnet.check_network(false).unwrap_err(),
+            AddressError::NetworkMismatch {
+                expected: Network::Mainnet,
+                found: Network::Testnet
+            }
+        );
+    }
+}
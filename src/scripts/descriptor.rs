@@ -0,0 +1,842 @@
+use crate::ecc::bip32::{
+    new_derivation_path, parse_child_number, Bip32Error, DerivationPath, ExtendedPrivateKey,
+    ExtendedPublicKey, HARDENED_OFFSET,
+};
+use crate::ecc::network::Network;
+use crate::ecc::secp256k1_point::Secp256k1Point;
+use crate::helper::helper::{decode_hex, hash160, sha256, u8vec_to_str};
+use crate::scripts::address::{Address, AddressError};
+use crate::scripts::operation::OperationCodes;
+use crate::scripts::script::{new_script, new_script_p2pkh_locking, new_script_p2tr_locking, Cmd, Script};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+// BIP380のチェックサム。文字は INPUT_CHARSET の位置の下位5bitと上位の「グループ」に分けてBCH符号に通す
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CHECKSUM_GENERATOR: [u64; 5] = [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd];
+
+// ディスクリプタを読めなかった、もしくは展開できなかった理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum DescriptorError {
+    // INPUT_CHARSET に無い文字
+    InvalidCharacter(char),
+    // '#' の後ろのチェックサムが合わない
+    InvalidChecksum,
+    // 括弧の対応や引数の数がおかしい
+    Syntax(String),
+    UnknownFunction(String),
+    // 使えない場所にある関数 (sh(sh(...)) や wsh(wpkh(...)) など)
+    InvalidContext(String),
+    InvalidKey(String),
+    // multi(k, ...) の k が 1..=n に無い
+    InvalidThreshold(usize),
+    // トップレベルは3個、sh()は15個、wsh()は20個まで
+    TooManyKeys(usize),
+    // ワイルドカードに渡したインデックスが 2^31 以上
+    InvalidIndex(u32),
+    Bip32(Bip32Error),
+    Address(AddressError),
+}
+
+impl Display for DescriptorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::InvalidCharacter(c) => write!(f, "invalid descriptor character: {:?}", c),
+            DescriptorError::InvalidChecksum => write!(f, "bad descriptor checksum"),
+            DescriptorError::Syntax(s) => write!(f, "bad descriptor syntax: {}", s),
+            DescriptorError::UnknownFunction(s) => write!(f, "unknown descriptor function: {}", s),
+            DescriptorError::InvalidContext(s) => write!(f, "{}() is not allowed here", s),
+            DescriptorError::InvalidKey(s) => write!(f, "bad descriptor key: {}", s),
+            DescriptorError::InvalidThreshold(k) => write!(f, "bad multisig threshold: {}", k),
+            DescriptorError::TooManyKeys(n) => write!(f, "too many keys in multisig: {}", n),
+            DescriptorError::InvalidIndex(i) => write!(f, "bad wildcard index: {}", i),
+            DescriptorError::Bip32(e) => write!(f, "{}", e),
+            DescriptorError::Address(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DescriptorError {}
+
+fn checksum_polymod(c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ val;
+    for (i, g) in CHECKSUM_GENERATOR.iter().enumerate() {
+        if (c0 >> i) & 1 == 1 {
+            c ^= g;
+        }
+    }
+    c
+}
+
+// '#' より前の部分から8文字のチェックサムを計算する
+#[allow(dead_code)]
+pub fn descriptor_checksum(desc: &str) -> Result<String, DescriptorError> {
+    let mut c = 1u64;
+    let mut cls = 0u64;
+    let mut cls_count = 0;
+    for ch in desc.chars() {
+        let pos = INPUT_CHARSET.find(ch).ok_or(DescriptorError::InvalidCharacter(ch))? as u64;
+        c = checksum_polymod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        cls_count += 1;
+        if cls_count == 3 {
+            c = checksum_polymod(c, cls);
+            cls = 0;
+            cls_count = 0;
+        }
+    }
+    if cls_count > 0 {
+        c = checksum_polymod(c, cls);
+    }
+    for _ in 0..8 {
+        c = checksum_polymod(c, 0);
+    }
+    c ^= 1;
+    Ok((0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect())
+}
+
+// 鍵がどの関数の中にあるか。使える鍵の形式と multi() の鍵の数の上限が変わる
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+    Top,
+    Sh,
+    SegwitV0,
+    Tr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Wildcard {
+    None,
+    // /*
+    Unhardened,
+    // /*'
+    Hardened,
+}
+
+#[derive(Debug, Clone)]
+pub enum KeyExpression {
+    // SEC形式(33byte, 65byte)、tr()の中では x-only(32byte) も使える
+    Single(Vec<u8>),
+    Xpub(ExtendedPublicKey),
+    Xprv(ExtendedPrivateKey),
+}
+
+// [fingerprint/path]xpub/path/* の形の鍵
+#[derive(Debug, Clone)]
+pub struct DescriptorKey {
+    pub(crate) origin: Option<([u8; 4], DerivationPath)>,
+    pub(crate) key: KeyExpression,
+    pub(crate) path: Vec<u32>,
+    pub(crate) wildcard: Wildcard,
+}
+
+fn write_path(f: &mut Formatter<'_>, indexes: &[u32]) -> fmt::Result {
+    for index in indexes.iter() {
+        if *index >= HARDENED_OFFSET {
+            write!(f, "/{}'", index - HARDENED_OFFSET)?;
+        } else {
+            write!(f, "/{}", index)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_hex(s: &str) -> bool {
+    s.len().is_multiple_of(2) && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl DescriptorKey {
+    fn parse(s: &str, ctx: Context) -> Result<DescriptorKey, DescriptorError> {
+        let (origin, rest) = match s.strip_prefix('[') {
+            Some(rest) => {
+                let end = rest
+                    .find(']')
+                    .ok_or_else(|| DescriptorError::Syntax("missing ']' after key origin".to_string()))?;
+                let mut parts = rest[..end].split('/');
+                let fingerprint = parts.next().unwrap_or("");
+                if fingerprint.len() != 8 || !is_hex(fingerprint) {
+                    return Err(DescriptorError::InvalidKey("fingerprint must be 8 hex characters".to_string()));
+                }
+                let mut fp = [0u8; 4];
+                fp.copy_from_slice(&decode_hex(fingerprint).unwrap());
+                let indexes = parts
+                    .map(parse_child_number)
+                    .collect::<Result<Vec<u32>, Bip32Error>>()
+                    .map_err(DescriptorError::Bip32)?;
+                (Some((fp, new_derivation_path(indexes))), &rest[end + 1..])
+            }
+            None => (None, s),
+        };
+
+        let mut parts = rest.split('/');
+        let key_str = parts.next().unwrap_or("");
+        let mut path = vec![];
+        let mut wildcard = Wildcard::None;
+        for part in parts {
+            if wildcard != Wildcard::None {
+                return Err(DescriptorError::Syntax("'*' must be the last path element".to_string()));
+            }
+            match part {
+                "*" => wildcard = Wildcard::Unhardened,
+                "*'" | "*h" => wildcard = Wildcard::Hardened,
+                _ => path.push(parse_child_number(part).map_err(DescriptorError::Bip32)?),
+            }
+        }
+
+        let key = if is_hex(key_str) {
+            if !path.is_empty() || wildcard != Wildcard::None {
+                return Err(DescriptorError::InvalidKey("a hex public key cannot have a path".to_string()));
+            }
+            let bytes = decode_hex(key_str).unwrap();
+            let allowed = match ctx {
+                Context::Top | Context::Sh => bytes.len() == 33 || bytes.len() == 65,
+                Context::SegwitV0 => bytes.len() == 33,
+                Context::Tr => bytes.len() == 32 || bytes.len() == 33,
+            };
+            if !allowed {
+                return Err(DescriptorError::InvalidKey(format!(
+                    "{}-byte public key is not allowed here",
+                    bytes.len()
+                )));
+            }
+            // スクリプトでは使えるが、ディスクリプタでは0x06/0x07のhybrid形式は使えない
+            if bytes.len() == 65 && bytes[0] != 0x04 {
+                return Err(DescriptorError::InvalidKey(format!(
+                    "public key prefix {:#04x} is not allowed",
+                    bytes[0]
+                )));
+            }
+            if bytes.len() == 32 {
+                Secp256k1Point::parse_x_only(&bytes)
+                    .ok_or_else(|| DescriptorError::InvalidKey("x-only public key is not on the curve".to_string()))?;
+            } else {
+                Secp256k1Point::parse_sec(bytes.clone()).map_err(|e| DescriptorError::InvalidKey(e.to_string()))?;
+            }
+            KeyExpression::Single(bytes)
+        } else if key_str.starts_with("xpub") || key_str.starts_with("tpub") {
+            KeyExpression::Xpub(ExtendedPublicKey::parse(key_str).map_err(DescriptorError::Bip32)?)
+        } else if key_str.starts_with("xprv") || key_str.starts_with("tprv") {
+            KeyExpression::Xprv(ExtendedPrivateKey::parse(key_str).map_err(DescriptorError::Bip32)?)
+        } else {
+            // 秘密鍵かもしれないので文字列はエラーに含めない
+            return Err(DescriptorError::InvalidKey(
+                "expected a hex public key, xpub/tpub or xprv/tprv".to_string(),
+            ));
+        };
+        Ok(DescriptorKey {
+            origin,
+            key,
+            path,
+            wildcard,
+        })
+    }
+
+    // index番目の公開鍵。xpub/xprvからは圧縮形式になる。ワイルドカードが無ければindexは使わない
+    fn derive(&self, index: u32) -> Result<Vec<u8>, DescriptorError> {
+        let mut indexes = self.path.clone();
+        match self.wildcard {
+            Wildcard::None => {}
+            _ if index >= HARDENED_OFFSET => return Err(DescriptorError::InvalidIndex(index)),
+            Wildcard::Unhardened => indexes.push(index),
+            Wildcard::Hardened => indexes.push(index + HARDENED_OFFSET),
+        }
+        let path = new_derivation_path(indexes);
+        match &self.key {
+            KeyExpression::Single(bytes) => Ok(bytes.clone()),
+            KeyExpression::Xpub(xpub) => {
+                let child = xpub.derive_path(&path).map_err(DescriptorError::Bip32)?;
+                Ok(child.public_key.compressed_sec())
+            }
+            KeyExpression::Xprv(xprv) => {
+                let child = xprv.derive_path(&path).map_err(DescriptorError::Bip32)?;
+                Ok(child.private_key.point.clone().compressed_sec())
+            }
+        }
+    }
+
+    fn is_ranged(&self) -> bool {
+        self.wildcard != Wildcard::None
+    }
+}
+
+impl Display for DescriptorKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some((fingerprint, path)) = &self.origin {
+            write!(f, "[{}", u8vec_to_str(fingerprint.to_vec()))?;
+            write_path(f, &path.indexes)?;
+            write!(f, "]")?;
+        }
+        match &self.key {
+            KeyExpression::Single(bytes) => write!(f, "{}", u8vec_to_str(bytes.clone()))?,
+            KeyExpression::Xpub(xpub) => write!(f, "{}", xpub)?,
+            KeyExpression::Xprv(xprv) => write!(f, "{}", xprv)?,
+        }
+        write_path(f, &self.path)?;
+        match self.wildcard {
+            Wildcard::None => Ok(()),
+            Wildcard::Unhardened => write!(f, "/*"),
+            Wildcard::Hardened => write!(f, "/*'"),
+        }
+    }
+}
+
+// 出力スクリプトディスクリプタ (BIP380〜386)。tr() はスクリプトツリーの無い鍵パスのみ
+#[derive(Debug, Clone)]
+pub enum Descriptor {
+    Pk(DescriptorKey),
+    Pkh(DescriptorKey),
+    Wpkh(DescriptorKey),
+    // multi() と sortedmulti()
+    Multi {
+        threshold: usize,
+        keys: Vec<DescriptorKey>,
+        sorted: bool,
+    },
+    Sh(Box<Descriptor>),
+    Wsh(Box<Descriptor>),
+    Tr(DescriptorKey),
+    Addr(Address),
+    Raw(Vec<u8>),
+}
+
+// "name(args)" を (name, args) に分ける。
+// 入力には秘密鍵が含まれているかもしれないので、エラーには入力の文字列を含めない
+fn split_call(s: &str) -> Result<(&str, &str), DescriptorError> {
+    let open = s
+        .find('(')
+        .ok_or_else(|| DescriptorError::Syntax("expected a function call".to_string()))?;
+    if !s.ends_with(')') {
+        return Err(DescriptorError::Syntax("missing ')'".to_string()));
+    }
+    let args = &s[open + 1..s.len() - 1];
+    let mut depth = 0i32;
+    for c in args.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            return Err(DescriptorError::Syntax("unbalanced ')'".to_string()));
+        }
+    }
+    if depth != 0 {
+        return Err(DescriptorError::Syntax("unbalanced '('".to_string()));
+    }
+    Ok((&s[..open], args))
+}
+
+// 括弧の外のカンマで分ける
+fn split_args(args: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(&args[start..]);
+    result
+}
+
+fn op(code: OperationCodes) -> Cmd {
+    Cmd::OperationCode(code as u8)
+}
+
+// 0〜16はOP_0〜OP_16、それより大きい値は1byteのプッシュにする
+fn small_int(n: usize) -> Cmd {
+    match n {
+        0 => op(OperationCodes::Op0),
+        1..=16 => Cmd::OperationCode(OperationCodes::Op1 as u8 + (n as u8 - 1)),
+        _ => Cmd::Element(vec![n as u8]),
+    }
+}
+
+// raw()のバイト列をScriptにする。Scriptはプッシュを最小の形に直して持つので、
+// 途中で切れているプッシュや最小でないプッシュのように、元のバイト列に戻らないものはエラーにする
+fn raw_script(bytes: &[u8]) -> Result<Script, DescriptorError> {
+    let invalid = || DescriptorError::Syntax(format!("raw({}) is not a script", u8vec_to_str(bytes.to_vec())));
+    let mut cmds = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = bytes[i];
+        i += 1;
+        let (length, header) = match code {
+            1..=75 => (code as usize, 0),
+            76 => (*bytes.get(i).ok_or_else(invalid)? as usize, 1),
+            77 => {
+                let b = bytes.get(i..i + 2).ok_or_else(invalid)?;
+                (u16::from_le_bytes([b[0], b[1]]) as usize, 2)
+            }
+            _ => {
+                cmds.push(Cmd::OperationCode(code));
+                continue;
+            }
+        };
+        i += header;
+        let data = bytes.get(i..i + length).ok_or_else(invalid)?;
+        if length > 520 {
+            return Err(invalid());
+        }
+        cmds.push(Cmd::Element(data.to_vec()));
+        i += length;
+    }
+    let script = new_script(cmds);
+    if script.raw_serialize() != bytes {
+        return Err(invalid());
+    }
+    Ok(script)
+}
+
+impl Descriptor {
+    // '#' 以降のチェックサムは省略できる。ある場合は検証する
+    #[allow(dead_code)]
+    pub fn parse(s: &str) -> Result<Descriptor, DescriptorError> {
+        let desc = match s.split_once('#') {
+            Some((desc, checksum)) => {
+                if descriptor_checksum(desc)? != checksum {
+                    return Err(DescriptorError::InvalidChecksum);
+                }
+                desc
+            }
+            None => {
+                descriptor_checksum(s)?;
+                s
+            }
+        };
+        Descriptor::parse_expression(desc, Context::Top)
+    }
+
+    fn parse_expression(s: &str, ctx: Context) -> Result<Descriptor, DescriptorError> {
+        let (name, args) = split_call(s)?;
+        let descriptor = match (name, ctx) {
+            ("pk", _) => Descriptor::Pk(DescriptorKey::parse(args, ctx)?),
+            ("pkh", _) => Descriptor::Pkh(DescriptorKey::parse(args, ctx)?),
+            ("wpkh", Context::Top) | ("wpkh", Context::Sh) => {
+                Descriptor::Wpkh(DescriptorKey::parse(args, Context::SegwitV0)?)
+            }
+            ("multi", _) | ("sortedmulti", _) => {
+                let args = split_args(args);
+                let threshold: usize = args[0]
+                    .parse()
+                    .map_err(|_| DescriptorError::Syntax("multisig threshold is not a number".to_string()))?;
+                let keys = args[1..]
+                    .iter()
+                    .map(|key| DescriptorKey::parse(key, ctx))
+                    .collect::<Result<Vec<DescriptorKey>, DescriptorError>>()?;
+                let limit = match ctx {
+                    Context::Top => 3,
+                    Context::Sh => 15,
+                    _ => 20,
+                };
+                if keys.len() > limit {
+                    return Err(DescriptorError::TooManyKeys(keys.len()));
+                }
+                if threshold == 0 || threshold > keys.len() {
+                    return Err(DescriptorError::InvalidThreshold(threshold));
+                }
+                Descriptor::Multi {
+                    threshold,
+                    keys,
+                    sorted: name == "sortedmulti",
+                }
+            }
+            ("sh", Context::Top) => Descriptor::Sh(Box::new(Descriptor::parse_expression(args, Context::Sh)?)),
+            ("wsh", Context::Top) | ("wsh", Context::Sh) => {
+                Descriptor::Wsh(Box::new(Descriptor::parse_expression(args, Context::SegwitV0)?))
+            }
+            ("tr", Context::Top) => {
+                if split_args(args).len() != 1 {
+                    return Err(DescriptorError::Syntax("tr() script trees are not supported".to_string()));
+                }
+                Descriptor::Tr(DescriptorKey::parse(args, Context::Tr)?)
+            }
+            ("addr", Context::Top) => Descriptor::Addr(Address::parse(args).map_err(DescriptorError::Address)?),
+            ("raw", Context::Top) => {
+                if !is_hex(args) {
+                    return Err(DescriptorError::Syntax(format!("bad hex in raw({})", args)));
+                }
+                let bytes = decode_hex(args).unwrap();
+                raw_script(&bytes)?;
+                Descriptor::Raw(bytes)
+            }
+            ("wpkh", _) | ("sh", _) | ("wsh", _) | ("tr", _) | ("addr", _) | ("raw", _) => {
+                return Err(DescriptorError::InvalidContext(name.to_string()))
+            }
+            _ => return Err(DescriptorError::UnknownFunction(name.to_string())),
+        };
+        Ok(descriptor)
+    }
+
+    // ワイルドカードを含む鍵があるか
+    #[allow(dead_code)]
+    pub fn is_ranged(&self) -> bool {
+        match self {
+            Descriptor::Pk(key) | Descriptor::Pkh(key) | Descriptor::Wpkh(key) | Descriptor::Tr(key) => key.is_ranged(),
+            Descriptor::Multi { keys, .. } => keys.iter().any(|key| key.is_ranged()),
+            Descriptor::Sh(inner) | Descriptor::Wsh(inner) => inner.is_ranged(),
+            Descriptor::Addr(_) | Descriptor::Raw(_) => false,
+        }
+    }
+
+    // index番目のscript_pubkey。sh()とwsh()の中ではredeem script / witness script になる
+    #[allow(dead_code)]
+    pub fn script_pubkey(&self, index: u32) -> Result<Script, DescriptorError> {
+        let script = match self {
+            Descriptor::Pk(key) => new_script(vec![Cmd::Element(key.derive(index)?), op(OperationCodes::OpChecksig)]),
+            Descriptor::Pkh(key) => new_script_p2pkh_locking(hash160(key.derive(index)?)),
+            Descriptor::Wpkh(key) => new_script(vec![op(OperationCodes::Op0), Cmd::Element(hash160(key.derive(index)?))]),
+            Descriptor::Multi { threshold, keys, sorted } => {
+                let mut pubkeys = keys
+                    .iter()
+                    .map(|key| key.derive(index))
+                    .collect::<Result<Vec<Vec<u8>>, DescriptorError>>()?;
+                if *sorted {
+                    pubkeys.sort();
+                }
+                let mut cmds = vec![small_int(*threshold)];
+                cmds.extend(pubkeys.into_iter().map(Cmd::Element));
+                cmds.push(small_int(keys.len()));
+                cmds.push(op(OperationCodes::OpCheckmultisig));
+                new_script(cmds)
+            }
+            Descriptor::Sh(inner) => new_script(vec![
+                op(OperationCodes::OpHash160),
+                Cmd::Element(hash160(inner.script_pubkey(index)?.raw_serialize())),
+                op(OperationCodes::OpEqual),
+            ]),
+            Descriptor::Wsh(inner) => new_script(vec![
+                op(OperationCodes::Op0),
+                Cmd::Element(sha256(inner.script_pubkey(index)?.raw_serialize())),
+            ]),
+            Descriptor::Tr(key) => {
+                let pubkey = key.derive(index)?;
+                let x_only = if pubkey.len() == 33 { &pubkey[1..] } else { &pubkey[..] };
+                let program = Secp256k1Point::parse_x_only(x_only)
+                    .and_then(|point| point.p2tr_witness_program(None))
+                    .ok_or_else(|| DescriptorError::InvalidKey("bad taproot internal key".to_string()))?;
+                new_script_p2tr_locking(program)
+            }
+            Descriptor::Addr(address) => address.script_pubkey(),
+            Descriptor::Raw(bytes) => raw_script(bytes)?,
+        };
+        Ok(script)
+    }
+
+    // pk() や トップレベルの multi() のようにアドレスの無いスクリプトはエラー
+    #[allow(dead_code)]
+    pub fn address(&self, index: u32, network: Network) -> Result<Address, DescriptorError> {
+        if let Descriptor::Addr(address) = self {
+            address.check_network(network.is_testnet()).map_err(DescriptorError::Address)?;
+            return Ok(address.clone());
+        }
+        Address::from_script_pubkey(&self.script_pubkey(index)?, network).map_err(DescriptorError::Address)
+    }
+
+    #[allow(dead_code)]
+    pub fn script_pubkeys(&self, range: Range<u32>) -> Result<Vec<Script>, DescriptorError> {
+        range.map(|i| self.script_pubkey(i)).collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn addresses(&self, range: Range<u32>, network: Network) -> Result<Vec<Address>, DescriptorError> {
+        range.map(|i| self.address(i, network)).collect()
+    }
+
+    // チェックサム無しの正規形。hardenedは ' で書く
+    fn expression(&self) -> String {
+        match self {
+            Descriptor::Pk(key) => format!("pk({})", key),
+            Descriptor::Pkh(key) => format!("pkh({})", key),
+            Descriptor::Wpkh(key) => format!("wpkh({})", key),
+            Descriptor::Multi { threshold, keys, sorted } => {
+                let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+                let name = if *sorted { "sortedmulti" } else { "multi" };
+                format!("{}({},{})", name, threshold, keys.join(","))
+            }
+            Descriptor::Sh(inner) => format!("sh({})", inner.expression()),
+            Descriptor::Wsh(inner) => format!("wsh({})", inner.expression()),
+            Descriptor::Tr(key) => format!("tr({})", key),
+            Descriptor::Addr(address) => format!("addr({})", address),
+            Descriptor::Raw(bytes) => format!("raw({})", u8vec_to_str(bytes.clone())),
+        }
+    }
+}
+
+// 正規形にチェックサムを付けたもの
+impl Display for Descriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let expression = self.expression();
+        let checksum = descriptor_checksum(&expression).map_err(|_| fmt::Error)?;
+        write!(f, "{}#{}", expression, checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use crate::ecc::bip39::Mnemonic;
+
+    const G: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const G2: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+
+    // "abandon ... about" のアカウント鍵を [fingerprint/path]xpub の形で返す
+    fn account_key(path: &str, network: Network) -> String {
+        let mnemonic = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let master = mnemonic.to_master_key("", network).unwrap();
+        assert_eq!(master.fingerprint(), [0x73, 0xc5, 0xda, 0x0a]);
+        let path = DerivationPath::parse(path).unwrap();
+        let account = master.derive_path(&path).unwrap().extended_public_key();
+        format!("[73c5da0a{}]{}", &path.to_string()[1..], account)
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(
+            descriptor_checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap(),
+            "02wpgw69"
+        );
+        let raw = Descriptor::parse("raw(deadbeef)#89f8spxm").unwrap();
+        assert_eq!(raw.to_string(), "raw(deadbeef)#89f8spxm");
+        assert_eq!(
+            raw.script_pubkey(0).unwrap().raw_serialize(),
+            decode_hex("deadbeef").unwrap()
+        );
+        assert_eq!(
+            Descriptor::parse("raw(deadbeef)#89f8spxn").unwrap_err(),
+            DescriptorError::InvalidChecksum
+        );
+        assert_eq!(
+            Descriptor::parse("raw(deadbeef)#").unwrap_err(),
+            DescriptorError::InvalidChecksum
+        );
+        assert_eq!(
+            descriptor_checksum("raw(deadbeef)\n").unwrap_err(),
+            DescriptorError::InvalidCharacter('\n')
+        );
+    }
+
+    #[test]
+    fn test_raw() {
+        // 75byteのプッシュ(4b)もそのまま戻る
+        let push75 = format!("4b{}", "ab".repeat(75));
+        let raw = Descriptor::parse(&format!("raw({})", push75)).unwrap();
+        assert_eq!(
+            raw.script_pubkey(0).unwrap().raw_serialize(),
+            decode_hex(&push75).unwrap()
+        );
+        // 途中で切れているプッシュ
+        assert!(matches!(
+            Descriptor::parse("raw(01)").unwrap_err(),
+            DescriptorError::Syntax(_)
+        ));
+        assert!(matches!(
+            Descriptor::parse("raw(4d01)").unwrap_err(),
+            DescriptorError::Syntax(_)
+        ));
+        // 最小でないプッシュはバイト列を保てない
+        assert!(matches!(
+            Descriptor::parse("raw(4c0100)").unwrap_err(),
+            DescriptorError::Syntax(_)
+        ));
+    }
+
+    #[test]
+    fn test_wallet_descriptors() {
+        // BIP44, BIP49, BIP84, BIP86 のテストベクタの最初の受け取りアドレス
+        let vectors = [
+            ("pkh", "m/44'/0'/0'", Network::Mainnet, "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"),
+            ("sh(wpkh", "m/49'/1'/0'", Network::Testnet, "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"),
+            ("wpkh", "m/84'/0'/0'", Network::Mainnet, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"),
+            ("tr", "m/86'/0'/0'", Network::Mainnet, "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"),
+        ];
+        for (function, path, network, first) in vectors.iter() {
+            let close = ")".repeat(function.matches('(').count() + 1);
+            let s = format!("{}({}/0/*{}", function, account_key(path, *network), close);
+            let descriptor = Descriptor::parse(&s).unwrap();
+            assert!(descriptor.is_ranged());
+            let addresses = descriptor.addresses(0..3, *network).unwrap();
+            assert_eq!(addresses[0].to_string(), *first, "{}", s);
+            assert_ne!(addresses[0], addresses[1]);
+            let scripts = descriptor.script_pubkeys(0..3).unwrap();
+            assert_eq!(scripts[2].raw_serialize(), addresses[2].script_pubkey().raw_serialize());
+        }
+
+        // BIP84 の2番目の受け取りアドレス
+        let wpkh = Descriptor::parse(&format!("wpkh({}/0/*)", account_key("m/84'/0'/0'", Network::Mainnet))).unwrap();
+        assert_eq!(
+            wpkh.address(1, Network::Mainnet).unwrap().to_string(),
+            "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+        );
+        assert_eq!(
+            wpkh.address(HARDENED_OFFSET, Network::Mainnet).unwrap_err(),
+            DescriptorError::InvalidIndex(HARDENED_OFFSET)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let account = account_key("m/84h/0h/0h", Network::Mainnet);
+        let with_h = format!("wpkh({}/0/*)", account.replace('\'', "h"));
+        let descriptor = Descriptor::parse(&format!("{}#{}", with_h, descriptor_checksum(&with_h).unwrap())).unwrap();
+        let canonical = descriptor.to_string();
+        let expected = format!("wpkh({}/0/*)", account);
+        assert_eq!(canonical, format!("{}#{}", expected, descriptor_checksum(&expected).unwrap()));
+        assert_eq!(Descriptor::parse(&canonical).unwrap().to_string(), canonical);
+
+        for s in [
+            format!("pk({})", G),
+            format!("sh(multi(1,{},{}))", G, G2),
+            format!("sh(wsh(sortedmulti(2,{},{})))", G2, G),
+            format!("tr({})", &G[2..]),
+            "addr(bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4)".to_string(),
+        ]
+        .iter()
+        {
+            let descriptor = Descriptor::parse(s).unwrap();
+            assert!(!descriptor.is_ranged());
+            assert_eq!(descriptor.to_string(), format!("{}#{}", s, descriptor_checksum(s).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_multisig() {
+        // sortedmultiは鍵をSEC形式の辞書順に並べる
+        let expected = format!("5121{}21{}52ae", G, G2);
+        let multi = Descriptor::parse(&format!("multi(1,{},{})", G, G2)).unwrap();
+        let sorted = Descriptor::parse(&format!("sortedmulti(1,{},{})", G2, G)).unwrap();
+        assert_eq!(u8vec_to_str(multi.script_pubkey(0).unwrap().raw_serialize()), expected);
+        assert_eq!(u8vec_to_str(sorted.script_pubkey(0).unwrap().raw_serialize()), expected);
+        assert_eq!(
+            multi.address(0, Network::Mainnet).unwrap_err(),
+            DescriptorError::Address(AddressError::UnsupportedScript)
+        );
+
+        let sh = Descriptor::parse(&format!("sh(sortedmulti(1,{},{}))", G2, G)).unwrap();
+        assert_eq!(
+            u8vec_to_str(sh.script_pubkey(0).unwrap().raw_serialize()),
+            "a9144c72901bbfedcb86eef17d0e94b36dbc3c9f391287"
+        );
+        let wsh = Descriptor::parse(&format!("wsh(sortedmulti(1,{},{}))", G2, G)).unwrap();
+        assert_eq!(
+            wsh.address(0, Network::Mainnet).unwrap().to_string(),
+            "bc1qd6e6c86xp56gw8pty8suuqhscpttea2c5m2fggzjkxzk5nl9faks5pehxu"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+        assert!(Descriptor::parse(&format!("pkh({})", uncompressed)).is_ok());
+        assert!(matches!(
+            Descriptor::parse(&format!("wpkh({})", uncompressed)).unwrap_err(),
+            DescriptorError::InvalidKey(_)
+        ));
+        assert!(matches!(
+            Descriptor::parse(&format!("wsh(pk({}))", uncompressed)).unwrap_err(),
+            DescriptorError::InvalidKey(_)
+        ));
+        // hybrid形式 (Gのyは偶数なので0x06)
+        let hybrid = format!("06{}", &uncompressed[2..]);
+        assert_eq!(
+            Descriptor::parse(&format!("pkh({})", hybrid)).unwrap_err(),
+            DescriptorError::InvalidKey("public key prefix 0x06 is not allowed".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("sh(multi(1,{},07{}))", G, &uncompressed[2..])).unwrap_err(),
+            DescriptorError::InvalidKey("public key prefix 0x07 is not allowed".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("sh(sh(pk({})))", G)).unwrap_err(),
+            DescriptorError::InvalidContext("sh".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("wsh(wpkh({}))", G)).unwrap_err(),
+            DescriptorError::InvalidContext("wpkh".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("sh(tr({}))", G)).unwrap_err(),
+            DescriptorError::InvalidContext("tr".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("combo({})", G)).unwrap_err(),
+            DescriptorError::UnknownFunction("combo".to_string())
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("multi(3,{},{})", G, G2)).unwrap_err(),
+            DescriptorError::InvalidThreshold(3)
+        );
+        assert_eq!(
+            Descriptor::parse(&format!("multi(1,{},{},{},{})", G, G2, G, G2)).unwrap_err(),
+            DescriptorError::TooManyKeys(4)
+        );
+        assert!(Descriptor::parse(&format!("wsh(multi(1,{},{},{},{}))", G, G2, G, G2)).is_ok());
+        assert!(matches!(
+            Descriptor::parse(&format!("wpkh({}", G)).unwrap_err(),
+            DescriptorError::Syntax(_)
+        ));
+        assert!(matches!(
+            Descriptor::parse(&format!("wpkh({}/0)", G)).unwrap_err(),
+            DescriptorError::InvalidKey(_)
+        ));
+
+        // xpubからはhardenedの子を導出できない
+        let account = account_key("m/84'/0'/0'", Network::Mainnet);
+        let hardened = Descriptor::parse(&format!("wpkh({}/0/*')", account)).unwrap();
+        assert_eq!(
+            hardened.script_pubkey(0).unwrap_err(),
+            DescriptorError::Bip32(Bip32Error::HardenedFromPublic(HARDENED_OFFSET))
+        );
+        assert!(matches!(
+            Descriptor::parse(&format!("wpkh({}/*/0)", account)).unwrap_err(),
+            DescriptorError::Syntax(_)
+        ));
+
+        // 書き間違えたディスクリプタのエラーに秘密鍵が出てこない
+        let xprv = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap()
+        .to_master_key("", Network::Mainnet)
+        .unwrap()
+        .to_string();
+        let malformed = [
+            xprv.clone(),
+            format!("wpkh({}", xprv),
+            format!("wpkh({}))", xprv),
+            format!("wpkh(({})", xprv),
+            format!("wpkh([73c5da0a/84'{}/0/*)", xprv),
+            format!("wpkh([{}]{})", &xprv[..8], G),
+            format!("multi({},{})", xprv, G),
+        ];
+        for s in malformed.iter() {
+            let message = Descriptor::parse(s).unwrap_err().to_string();
+            assert!(!message.contains(&xprv[4..]), "{}", message);
+            assert!(!message.contains(&xprv[..8]), "{}", message);
+        }
+
+        // addr() のネットワークと違うネットワークでは展開できない
+        let addr = Descriptor::parse("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap();
+        assert!(matches!(
+            addr.address(0, Network::Mainnet).unwrap_err(),
+            DescriptorError::Address(AddressError::NetworkMismatch { .. })
+        ));
+    }
+}
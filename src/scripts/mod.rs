@@ -1,3 +1,5 @@
+pub(crate) mod address;
+pub(crate) mod descriptor;
 mod element;
 mod operation;
 pub(crate) mod script;
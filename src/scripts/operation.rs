@@ -1,3 +1,4 @@
+use crate::ecc::secp256k1_batch_verify::{new_secp256k1verifyitem, Secp256k1VerifyItem};
 use crate::ecc::secp256k1_point::{new_secp256k1point_g, Secp256k1Point};
 use crate::ecc::secp256k1_scalar_element::Secp256k1ScalarElement;
 use crate::ecc::secp256k1_signature::Secp256k1Signature;
@@ -91,6 +92,8 @@ pub enum OperationCodes {
     OpHash160,
     OpHash256,
     OpChecksig = 172,
+    OpChecksigverify,
+    OpCheckmultisig,
 }
 
 #[allow(dead_code)]
@@ -125,6 +128,9 @@ fn encode_num(num: BigInt) -> Element {
     return new_element_from_bytes(v);
 }
 
+// OP_CHECKMULTISIGで使える公開鍵の数の上限
+const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
 fn decode_num(element: Element) -> BigInt {
     if element.is_empty() {
         return BigInt::zero();
@@ -153,6 +159,34 @@ fn decode_num(element: Element) -> BigInt {
     return result;
 }
 
+// OP_CHECKSIG系の引数(署名, 公開鍵)をスタックから取り出して読む
+// スタックが足りなければNone、空の署名や読めない公開鍵・署名はSome(None)で、検証するまでもなく失敗
+fn pop_checksig_args(
+    stack: &mut Stack<Element>,
+) -> Option<Option<(Secp256k1Point, Secp256k1Signature)>> {
+    if stack.len() < 2 {
+        return None;
+    }
+    let sec_pubkey = stack.pop().unwrap();
+    let el = stack.pop().unwrap();
+    let sz = el.inner_data.len();
+    // 空の署名はsighashの1byteすら無い
+    if sz == 0 {
+        return Some(None);
+    }
+    let der_signature = el.inner_data[..(sz - 1)].to_vec();
+    #[cfg(test)]
+    println!("DER_SIG: {}",u8vec_to_str(der_signature.clone()));
+    // BIP66以前の署名も検証できるように緩いDERで読む
+    match (
+        Secp256k1Point::parse_sec(sec_pubkey.inner_data),
+        Secp256k1Signature::parse_lax(&der_signature),
+    ) {
+        (Ok(point), Ok(sig)) => Some(Some((point, sig))),
+        _ => Some(None),
+    }
+}
+
 // z: 署名ハッシュ
 pub enum Operation {
     NormalOperation(fn(&mut Stack<Element>) -> bool),
@@ -1196,19 +1230,14 @@ impl Operations {
     #[allow(dead_code)]
     // z: 署名ハッシュ
     pub fn op_checksig(stack: &mut Stack<Element>, z: Secp256k1ScalarElement) -> bool {
-        if stack.len() < 2 {
-            return false;
-        }
-        let sec_pubkey = stack.pop().unwrap();
-        let el = stack.pop().unwrap();
-        let sz = el.inner_data.len();
-        let bytes = &el.inner_data[..(sz - 1)];
-        let der_signature = bytes.to_vec();
-        #[cfg(test)]
-        println!("DER_SIG: {}",u8vec_to_str(der_signature.clone()));
-        // ここの実装が怪しい。 Elementとder_signatureが違っていそうなので確認する。
-        let point = Secp256k1Point::parse(sec_pubkey.inner_data);
-        let sig = Secp256k1Signature::parse(der_signature);
+        let (point, sig) = match pop_checksig_args(stack) {
+            None => return false,
+            Some(Some(args)) => args,
+            Some(None) => {
+                stack.push(encode_num(BigInt::zero()));
+                return true;
+            }
+        };
         if point.verify(z, sig) {
             #[cfg(test)]
             println!("CHECK_SIG: success");
@@ -1221,6 +1250,26 @@ impl Operations {
         return true;
     }
 
+    // 検証はせずに(公開鍵, z, 署名)をdeferredに積み、成功したものとしてスタックに1を積む
+    pub fn op_checksig_deferred(
+        stack: &mut Stack<Element>,
+        z: Secp256k1ScalarElement,
+        deferred: &mut Vec<Secp256k1VerifyItem>,
+    ) -> bool {
+        // 読めない公開鍵・署名は後で検証するまでもなく失敗なので、ここで0を積む
+        let (point, sig) = match pop_checksig_args(stack) {
+            None => return false,
+            Some(Some(args)) => args,
+            Some(None) => {
+                stack.push(encode_num(BigInt::zero()));
+                return true;
+            }
+        };
+        deferred.push(new_secp256k1verifyitem(point, z, sig));
+        stack.push(encode_num(BigInt::one()));
+        true
+    }
+
     #[allow(dead_code)]
     // z: 署名ハッシュ
     pub fn op_checksigverify(stack: &mut Stack<Element>, z: Secp256k1ScalarElement) -> bool {
@@ -1234,24 +1283,31 @@ impl Operations {
         if stack.len() < 1 {
             return false;
         }
-        let n = decode_num(stack.pop().unwrap());
+        // 負の数や大きすぎる数はスクリプトの失敗として扱う
+        let n = match decode_num(stack.pop().unwrap()).to_usize() {
+            Some(n) if n <= MAX_PUBKEYS_PER_MULTISIG => n,
+            _ => return false,
+        };
         if stack.len() < n + 1 {
             return false;
         }
         let mut sec_pubkeys: Vec<Element> = vec![];
         for _ in 0..n {
-            sec_pubkeys.push(*stack.pop().unwrap());
+            sec_pubkeys.push(stack.pop().unwrap());
         }
-        let m = decode_num(*stack.pop().unwrap());
+        let m = match decode_num(stack.pop().unwrap()).to_usize() {
+            Some(m) if m <= n => m,
+            _ => return false,
+        };
         if stack.len() < m + 1 {
             return false;
         }
         let mut der_signatures: Vec<Element> = vec![];
         for _ in 0..m {
             let mut el = stack.pop().unwrap();
-            // delete last 1byte
-            el.inner_data.pop().unwrap();
-            der_signatures.push(*el);
+            // delete last 1byte (空の署名はそのまま残り、下で読めずに失敗する)
+            el.inner_data.pop();
+            der_signatures.push(el);
         }
         // Off-by-one エラーの回避
         stack.pop();
@@ -1262,13 +1318,20 @@ impl Operations {
         // ref: https://en.bitcoin.it/wiki/Script
 
         // parse all points
-        let mut points:Vec<Secp256k1Point> = vec![];
+        // 読めない公開鍵はどの署名とも一致しないものとして扱う
+        let mut points:Vec<Option<Secp256k1Point>> = vec![];
         for sec in sec_pubkeys {
-            points.push(Secp256k1Point::parse(sec.inner_data));
+            points.push(Secp256k1Point::parse_sec(sec.inner_data).ok());
         }
         let mut sigs:Vec<Secp256k1Signature> = vec![];
         for der in der_signatures {
-            sigs.push(Secp256k1Signature::parse(der.inner_data));
+            match Secp256k1Signature::parse_lax(&der.inner_data) {
+                Ok(sig) => sigs.push(sig),
+                Err(_) => {
+                    stack.push(encode_num(BigInt::zero()));
+                    return true;
+                }
+            }
         }
 
         for sig in sigs {
@@ -1279,9 +1342,10 @@ impl Operations {
             }
             while points.len() != 0 {
                 // pop top element
-                let point = points.remove(0);
-                if point.verify(z.clone(),sig.clone()) {
-                    break;
+                if let Some(point) = points.remove(0) {
+                    if point.verify(z.clone(),sig.clone()) {
+                        break;
+                    }
                 }
             }
             stack.push(encode_num(BigInt::from(1)));
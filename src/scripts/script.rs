@@ -1,3 +1,4 @@
+use crate::ecc::secp256k1_batch_verify::Secp256k1VerifyItem;
 use crate::ecc::secp256k1_scalar_element::Secp256k1ScalarElement;
 use crate::helper::helper::{encode_varint, read_varint, u8vec_to_str};
 use crate::scripts::element::{new_element, new_element_from_bytes, Element};
@@ -43,6 +44,17 @@ pub fn new_script_p2pkh_locking(pubkey_hash_20bytes: Vec<u8>) -> Script {
     }
 }
 
+// OP_1 <32byteのwitness program>
+#[allow(dead_code)]
+pub fn new_script_p2tr_locking(witness_program_32bytes: Vec<u8>) -> Script {
+    Script {
+        cmds: vec![
+            OperationCode(OperationCodes::Op1 as u8),
+            Cmd::Element(witness_program_32bytes),
+        ],
+    }
+}
+
 pub fn new_script_p2pkh_unlocking(der_sig: Vec<u8>, compressed_public_sec: Vec<u8>) -> Script {
     Script {
         cmds: vec![Cmd::Element(der_sig), Cmd::Element(compressed_public_sec)],
@@ -90,7 +102,7 @@ impl Script {
                     std::panic::panic_any(read_result.err().unwrap());
                 }
                 cmds.push(Cmd::Element(buf));
-                count += (data_length + 1) as u64;
+                count += data_length as u64 + 1;
             } else if current_byte == 77 {
                 // 2byteだけ読み込んで決めるその値分の読み込み
                 let mut buf = [0u8; 2];
@@ -106,7 +118,7 @@ impl Script {
                     std::panic::panic_any(read_result.err().unwrap());
                 }
                 cmds.push(Cmd::Element(buf));
-                count += (data_length + 2) as u64;
+                count += data_length as u64 + 2;
             } else {
                 let op_code = current_byte;
                 cmds.push(Cmd::OperationCode(op_code));
@@ -135,12 +147,12 @@ impl Script {
                 }
                 Cmd::Element(v) => {
                     let len = v.len();
-                    if len < 75 {
+                    if len <= 75 {
                         result.append(&mut (len as u8).to_le_bytes().to_vec());
-                    } else if len > 75 && len < 0x100 {
+                    } else if len < 0x100 {
                         result.append(&mut (76 as u8).to_le_bytes().to_vec());
                         result.append(&mut (len as u8).to_le_bytes().to_vec());
-                    } else if len > 0x100 && len <= 520 {
+                    } else if len <= 520 {
                         result.append(&mut (77 as u8).to_le_bytes().to_vec());
                         result.append(&mut (len as u16).to_le_bytes().to_vec());
                     } else {
@@ -154,6 +166,25 @@ impl Script {
     }
     #[allow(dead_code)]
     pub fn evaluate(&self, z: Secp256k1ScalarElement) -> bool {
+        self.evaluate_inner(z, None)
+    }
+
+    // OP_CHECKSIGの署名検証をその場で行わず、成功したものとして評価を進めてdeferredに積む。
+    // 積まれた署名は呼び出し側でまとめて(バッチで)検証する必要がある。
+    #[allow(dead_code)]
+    pub fn evaluate_deferred(
+        &self,
+        z: Secp256k1ScalarElement,
+        deferred: &mut Vec<Secp256k1VerifyItem>,
+    ) -> bool {
+        self.evaluate_inner(z, Some(deferred))
+    }
+
+    fn evaluate_inner(
+        &self,
+        z: Secp256k1ScalarElement,
+        mut deferred: Option<&mut Vec<Secp256k1VerifyItem>>,
+    ) -> bool {
         let mut now_cmds = self.cmds.clone();
         let mut stack: Stack<Element> = new_stack();
         let mut alt_stack: Stack<Element> = new_stack();
@@ -189,7 +220,12 @@ impl Script {
                         }
                         Operation::AdditionalScalarElementOperation(_op) => {
                             // let operation_result = op(&mut stack, z.clone());
-                            let operation_result = Operations::op_checksig(&mut stack, z.clone());
+                            let operation_result = match deferred.as_mut() {
+                                Some(deferred) => {
+                                    Operations::op_checksig_deferred(&mut stack, z.clone(), deferred)
+                                }
+                                None => Operations::op_checksig(&mut stack, z.clone()),
+                            };
                             if !operation_result {
                                 #[cfg(test)]
                                 println!("bad operation. code: {}", code);
@@ -286,6 +322,7 @@ mod tests {
     extern crate test;
 
     use super::*;
+    use crate::ecc::secp256k1_batch_verify::batch_verify;
     use crate::ecc::secp256k1_point::Secp256k1Point;
     use crate::ecc::secp256k1_privatekey::new_secp_256k1privatekey;
     use crate::ecc::secp256k1_scalar_element::{
@@ -307,7 +344,7 @@ mod tests {
 
         let s = "04887387e452b8eacc4acfde10d9aaf7f6d9a0f975aabb10d006e4da568744d06c61de6d95231cd89026e286df3b6ae4a894a3378e393e93a0f45b666329a0ae34";
         let sec_pubkey = helper::decode_hex(s).unwrap();
-        let _p1 = Secp256k1Point::parse(sec_pubkey.clone());
+        let _p1 = Secp256k1Point::parse(sec_pubkey.clone()).unwrap();
 
         let sig = helper::decode_hex("3045022000eff69ef2b1bd93a66ed5219add4fb51e11a840f404876325a1e8ffe0529a2c022100c7207fee197d27c618aea621406f6bf5ef6fca38681d82b2f06fddbdce6feab601").unwrap();
 
@@ -321,7 +358,135 @@ mod tests {
         let sig_script = new_script(sig_cmds);
 
         let combined_script = sig_script + pubkey_script;
-        assert_eq!(combined_script.evaluate(z), true);
+        assert!(combined_script.evaluate(z.clone()));
+
+        // 後でまとめて検証する場合は、OP_CHECKSIGは成功扱いで署名が積まれる
+        let mut deferred = vec![];
+        assert!(combined_script.evaluate_deferred(z, &mut deferred));
+        let wrong_z = new_secp256k1scalarelement_from_i32(1);
+        assert!(combined_script.evaluate_deferred(wrong_z, &mut deferred));
+        assert_eq!(batch_verify(&deferred), Err(vec![1]));
+    }
+
+    #[test]
+    fn test_serialize_push_lengths() {
+        // 75byteまでは長さ1byte、255byteまではOP_PUSHDATA1、520byteまではOP_PUSHDATA2
+        let cases: [(usize, Vec<u8>); 6] = [
+            (1, vec![0x01]),
+            (75, vec![0x4b]),
+            (76, vec![0x4c, 0x4c]),
+            (255, vec![0x4c, 0xff]),
+            (256, vec![0x4d, 0x00, 0x01]),
+            (520, vec![0x4d, 0x08, 0x02]),
+        ];
+        for (len, prefix) in cases.iter() {
+            let script = new_script(vec![
+                Cmd::Element(vec![0xab; *len]),
+                Cmd::OperationCode(OperationCodes::OpDrop as u8),
+            ]);
+            let raw = script.raw_serialize();
+            assert_eq!(&raw[..prefix.len()], &prefix[..]);
+            assert_eq!(raw.len(), prefix.len() + len + 1);
+            let parsed = Script::parse(&mut Cursor::new(script.serialize()));
+            assert_eq!(parsed.cmds, script.cmds);
+        }
+    }
+
+    #[test]
+    fn test_checksig_rejects_x_only_pubkey() {
+        // yが偶数の公開鍵なら、x-onlyとして読むと同じ点になってしまう
+        let private = (1..)
+            .map(new_secp256k1scalarelement_from_i32)
+            .map(new_secp_256k1privatekey)
+            .find(|key| key.point.has_even_y())
+            .unwrap();
+        let z = new_secp256k1scalarelement_from_i32(4321);
+        let compressed = private.point.clone().compressed_sec();
+        let x_only = private.point.x_only();
+        let mut sig = private.sign(z.clone()).der();
+        sig.push(1);
+
+        let script = |pubkey: Vec<u8>| {
+            new_script(vec![
+                Cmd::Element(sig.clone()),
+                Cmd::Element(pubkey),
+                Cmd::OperationCode(OperationCodes::OpChecksig as u8),
+            ])
+        };
+        assert!(script(compressed).evaluate(z.clone()));
+
+        // SEC形式でない32byteの公開鍵ではOP_CHECKSIGは0を積む
+        let mut stack = new_stack();
+        stack.push(new_element_from_bytes(sig.clone()));
+        stack.push(new_element_from_bytes(x_only.clone()));
+        assert!(Operations::op_checksig(&mut stack, z.clone()));
+        assert_eq!(stack.pop().unwrap().inner_data, Vec::<u8>::new());
+        assert!(!script(x_only.clone()).evaluate(z.clone()));
+
+        let mut deferred = vec![];
+        assert!(!script(x_only).evaluate_deferred(z, &mut deferred));
+        assert!(deferred.is_empty());
+    }
+
+    #[test]
+    fn test_checksig_empty_signature() {
+        let private = new_secp_256k1privatekey(new_secp256k1scalarelement_from_i32(12345));
+        let z = new_secp256k1scalarelement_from_i32(4321);
+        let pubkey = private.point.clone().compressed_sec();
+
+        // 空の署名ではパニックせずに0を積む
+        let mut stack = new_stack();
+        stack.push(new_element_from_bytes(vec![]));
+        stack.push(new_element_from_bytes(pubkey.clone()));
+        assert!(Operations::op_checksig(&mut stack, z.clone()));
+        assert_eq!(stack.pop().unwrap().inner_data, Vec::<u8>::new());
+
+        let script = new_script(vec![
+            Cmd::Element(vec![]),
+            Cmd::Element(pubkey.clone()),
+            Cmd::OperationCode(OperationCodes::OpChecksig as u8),
+        ]);
+        assert!(!script.evaluate(z.clone()));
+        let mut deferred = vec![];
+        assert!(!script.evaluate_deferred(z.clone(), &mut deferred));
+        assert!(deferred.is_empty());
+
+        // OP_0 <空の署名> OP_1 <公開鍵> OP_1 の状態で OP_CHECKMULTISIG
+        let mut stack = new_stack();
+        stack.push(new_element_from_bytes(vec![]));
+        stack.push(new_element_from_bytes(vec![]));
+        stack.push(new_element_from_bytes(vec![1]));
+        stack.push(new_element_from_bytes(pubkey));
+        stack.push(new_element_from_bytes(vec![1]));
+        assert!(Operations::op_checkmultisig(&mut stack, z));
+        assert_eq!(stack.pop().unwrap().inner_data, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_checkmultisig_bad_counts() {
+        let z = new_secp256k1scalarelement_from_i32(4321);
+        let private = new_secp_256k1privatekey(new_secp256k1scalarelement_from_i32(12345));
+        let pubkey = private.point.clone().compressed_sec();
+        // 下から順に積む。最後がn
+        let run = |items: Vec<Vec<u8>>| {
+            let mut stack = new_stack();
+            for item in items {
+                stack.push(new_element_from_bytes(item));
+            }
+            Operations::op_checkmultisig(&mut stack, z.clone())
+        };
+        // nが負の数
+        assert!(!run(vec![vec![], vec![], vec![1], pubkey.clone(), vec![0x81]]));
+        // nがusizeに収まらない
+        assert!(!run(vec![vec![], vec![], vec![1], pubkey.clone(), vec![0xff; 9]]));
+        // nが20より大きい
+        let mut items = vec![vec![], vec![1]];
+        items.extend(std::iter::repeat_n(pubkey.clone(), 21));
+        items.push(vec![21]);
+        assert!(!run(items));
+        // mが負の数、mがnより大きい
+        assert!(!run(vec![vec![], vec![], vec![0x81], pubkey.clone(), vec![1]]));
+        assert!(!run(vec![vec![], vec![], vec![], vec![2], pubkey, vec![1]]));
     }
 
     #[test]
@@ -332,7 +497,7 @@ mod tests {
         // 秘密鍵
         let pk = new_secp_256k1privatekey(e);
         // 公開鍵の圧縮sec
-        let compressed_public_sec = pk.clone().point.compressed_sec();
+        let compressed_public_sec = pk.point.clone().compressed_sec();
         let compressed_public_sec_hashed = hash160(compressed_public_sec.clone());
         // 署名先
         let z = digest(Algorithm::SHA256, b"Programming Bitcoin!");
@@ -4,10 +4,12 @@ use crate::helper::helper::{
 use crate::tx::tx_in::TxIn;
 use crate::tx::tx_out::TxOut;
 
+use crate::ecc::secp256k1_batch_verify::{batch_verify, Secp256k1VerifyItem};
 use crate::ecc::secp256k1_scalar_element::new_secp256k1scalarelement;
 
 use crate::ecc::secp256k1_privatekey::Secp256k1PrivateKey;
 
+use crate::scripts::address::{Address, AddressError};
 use crate::scripts::script::{new_script, Cmd};
 use crate::Script;
 use num_bigint::BigUint;
@@ -46,10 +48,36 @@ impl Tx {
         }
     }
 
+    // アドレス宛のアウトプットを追加する。testnetのアドレスをmainnetのトランザクションに使うような間違いはエラーにする
+    #[allow(dead_code)]
+    pub fn add_output(&mut self, address: &Address, amount: u64) -> Result<(), AddressError> {
+        address.check_network(self.testnet)?;
+        self.tx_outs.push(TxOut {
+            amount,
+            script_pub_key: address.script_pubkey(),
+        });
+        Ok(())
+    }
+
+    // 署名はまとめてバッチ検証する。
+    // 全ての署名が正しければ、1つずつ検証した場合と結果は変わらない。
     pub fn verify(&self) -> bool {
         if self.clone().fee() < BigUint::from(0u64) {
             return false;
         }
+        let mut deferred: Vec<Secp256k1VerifyItem> = vec![];
+        let mut all_passed = true;
+        for i in 0..self.tx_ins.len() {
+            if !self.verify_input_deferred(i, &mut deferred) {
+                all_passed = false;
+                break;
+            }
+        }
+        if all_passed && batch_verify(&deferred).is_ok() {
+            return true;
+        }
+        // 不正な署名があった場合は、OP_CHECKSIGが成功したと仮定して評価した結果は信用できないので
+        // 1入力ずつ検証し直す
         for i in 0..self.clone().tx_ins.len() {
             if !self.verify_input(i) {
                 println!("tx_in: {} failed",i);
@@ -68,13 +96,27 @@ impl Tx {
         combined.evaluate(z)
     }
 
+    // OP_CHECKSIGの署名をdeferredに積みながらスクリプトを評価する
+    fn verify_input_deferred(
+        &self,
+        input_idx: usize,
+        deferred: &mut Vec<Secp256k1VerifyItem>,
+    ) -> bool {
+        let tx_in = self.tx_ins[input_idx].clone();
+        let script_pub_key = tx_in.script_pubkey(self.testnet);
+        let combined = tx_in.clone().script_sig.clone() + script_pub_key;
+        let z = self.sig_hash(input_idx, self.testnet);
+        let z = new_secp256k1scalarelement(z);
+        combined.evaluate_deferred(z, deferred)
+    }
+
     pub fn sign_input(&mut self, input_idx: usize, private_key: Secp256k1PrivateKey) -> bool {
         let z = self.sig_hash(input_idx, self.testnet);
         let z = new_secp256k1scalarelement(z);
         let mut der = private_key.clone().sign(z).der();
         der.append(&mut (Sighash::All as u8).to_le_bytes().to_vec());
         let sig = der;
-        let sec = private_key.point.compressed_sec();
+        let sec = private_key.point.clone().compressed_sec();
         self.tx_ins[input_idx].script_sig = new_script(vec![Cmd::Element(sig), Cmd::Element(sec)]);
         return self.verify_input(input_idx);
     }
@@ -83,12 +125,18 @@ impl Tx {
     // トランザクションの署名ハッシュzを取得する。(署名の検証に利用する)
     // ScriptSigの一部に署名がくっついているので、くっつく前の状態まで復元する
     pub fn sig_hash(&self, input_idx: usize, testnet: bool) -> BigUint {
+        let script_pubkey = self.tx_ins[input_idx].script_pubkey(testnet);
+        self.sig_hash_with_script_pubkey(input_idx, script_pubkey)
+    }
+
+    // 使うアウトプットのscript_pubkeyが分かっている場合は、前のトランザクションを取りに行かずにzを求められる
+    pub fn sig_hash_with_script_pubkey(&self, input_idx: usize, script_pubkey: Script) -> BigUint {
         let mut result = self.version.to_le_bytes().to_vec();
         result.append(&mut encode_varint(self.clone().tx_ins.len() as u128));
 
         for (i, tx_in) in self.clone().tx_ins.clone().iter().enumerate() {
             let script_sig = if i == input_idx {
-                tx_in.script_pubkey(testnet)
+                script_pubkey.clone()
             } else {
                 Script { cmds: vec![] }
             };
@@ -341,4 +389,24 @@ mod tests {
         println!("tx1info: \n{}\n ", tx.clone());
         println!("fee: {}", tx.fee())
     }
+
+    #[test]
+    fn test_add_output_network_mismatch() {
+        let address = Address::parse("mpw1fSjdDKX6Qs2FAi8Q6Qqm7TKS296sDK").unwrap();
+        let mut testnet_tx = Tx::new(1, vec![], vec![], 0, true);
+        testnet_tx.add_output(&address, 1000).unwrap();
+        assert_eq!(testnet_tx.tx_outs[0].amount, 1000);
+        assert_eq!(
+            testnet_tx.tx_outs[0].script_pub_key.raw_serialize(),
+            decode_hex("76a9146745ed61a219bc660b8ba7dd7abf2aa3002bd0c688ac").unwrap()
+        );
+
+        let mut mainnet_tx = Tx::new(1, vec![], vec![], 0, false);
+        let err = mainnet_tx.add_output(&address, 1000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "address is for Testnet but the transaction is for Mainnet"
+        );
+        assert!(mainnet_tx.tx_outs.is_empty());
+    }
 }